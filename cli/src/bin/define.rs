@@ -17,8 +17,10 @@ fn main() -> operation_api_core::Result<()> {
 
     let log_level: tracing::Level = cli.log_level.clone().into();
 
+    // stdout is reserved for the language server protocol
     let mut layer = tracing_subscriber::fmt()
         .pretty()
+        .with_writer(std::io::stderr)
         .with_max_level(log_level);
 
     #[cfg(test)]
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use operation_api_core::checks::{RuleConfig, RuleRegistry};
 use operation_api_manifests::NewForConfig;
 
 #[derive(Default, clap::ValueEnum, Clone, Debug)]
@@ -47,17 +48,61 @@ impl Cli {
                 let gen_conf = operation_api_core::generate::GenerationConfig::new(
                     args.config.config_dir.as_deref(),
                 )?;
-                operation_api_core::generate::Generation::new(gen_conf)?
-                    .generate_all(None)
-                    .await
+                let generation = operation_api_core::generate::Generation::new(gen_conf)?;
+                generation
+                    .check(&rules(args.config.config_dir.as_deref())?)
+                    .report(&mut std::io::stderr())?;
+                generation.generate_all(None).await
             },
             Command::Check(args) => {
-                // we only need to initialize as we perform pre-checks at object creation
+                // references are resolved at object creation, leaving only the rules to run
+                let gen_conf = operation_api_core::generate::GenerationConfig::new(
+                    args.config.config_dir.as_deref(),
+                )?;
+                let findings = operation_api_core::generate::Generation::new(gen_conf)?
+                    .check(&rules(args.config.config_dir.as_deref())?);
+                if !args.fix {
+                    return findings.report(&mut std::io::stderr());
+                }
+
+                let config = operation_api_parser::fmt::FormatConfig::new(
+                    args.config.config_dir.as_deref(),
+                )?;
+                let fixes = findings.fix(args.unsafe_fixes, &config);
+                for report in &fixes.reports {
+                    eprintln!("{report:?}");
+                }
+                fixes.write()?;
+                if fixes.fixed > 0 {
+                    eprintln!(
+                        "fixed {} finding(s) in {} file(s)",
+                        fixes.fixed,
+                        fixes.files.len()
+                    );
+                }
+                fixes
+                    .remaining
+                    .report(&mut std::io::stderr())
+            },
+            Command::Diff(args) => {
                 let gen_conf = operation_api_core::generate::GenerationConfig::new(
                     args.config.config_dir.as_deref(),
                 )?;
-                let _ = operation_api_core::generate::Generation::new(gen_conf)?;
-                Ok(())
+                let current = operation_api_core::generate::Generation::new(gen_conf)?.ctx;
+
+                let mut baseline = operation_api_core::context::Context::new();
+                baseline.load_path(&args.baseline)?;
+                baseline.finish()?;
+
+                operation_api_core::diff::Diff::new(&baseline, &current).report()
+            },
+            Command::Export(args) => {
+                let gen_conf = operation_api_core::generate::GenerationConfig::new(
+                    args.config.config_dir.as_deref(),
+                )?;
+                operation_api_core::generate::Generation::new(gen_conf)?
+                    .ctx
+                    .export(&args.out, &args.format)
             },
             Command::Init(args) => Ok(operation_api_manifests::init(args.name, args.dir)?),
 
@@ -65,15 +110,25 @@ impl Cli {
                 let targets =
                     operation_api_manifests::files::match_paths(&args.include, &args.exclude)?;
 
-                Ok(
-                    operation_api_parser::fmt::fmt(args.config.config_dir, targets, args.dry)
-                        .await?,
-                )
+                let opts = operation_api_parser::fmt::FmtOptions::builder()
+                    .dry(args.dry)
+                    .check(args.check)
+                    .safe(args.safe)
+                    .warn_is_fail(args.warn_is_fail)
+                    .build();
+
+                Ok(operation_api_parser::fmt::fmt(args.config.config_dir, targets, opts).await?)
             },
+            Command::Lsp => Ok(operation_api_lsp::stdio()?),
         }
     }
 }
 
+fn rules(config_dir: Option<&str>) -> operation_api_manifests::Result<RuleRegistry> {
+    let config = <RuleConfig as NewForConfig>::new(config_dir)?;
+    Ok(RuleRegistry::new(config))
+}
+
 #[derive(clap::Subcommand, Debug, Clone)]
 enum Command {
     #[clap(alias = "gen", alias = "g")]
@@ -84,6 +139,14 @@ enum Command {
     /// checks models for soundness
     Check(CheckArgs),
 
+    #[clap(alias = "d")]
+    /// compares models against a baseline, failing on breaking changes without a version bump
+    Diff(DiffArgs),
+
+    #[clap(alias = "e")]
+    /// exports models as definitions, for use as a baseline to `diff`
+    Export(ExportArgs),
+
     #[clap(alias = "i")]
     /// initializes a new schema project
     Init(InitArgs),
@@ -91,6 +154,9 @@ enum Command {
     #[clap(alias = "f")]
     /// formats schemas
     Fmt(FmtArgs),
+
+    /// runs the language server over stdio
+    Lsp,
 }
 
 #[derive(clap::Args, Debug, Clone)]
@@ -109,6 +175,54 @@ struct GenArgs {
 struct CheckArgs {
     #[clap(flatten)]
     config: WithConfig,
+
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "if --fix, safe fixes are applied to schema files, which are then formatted"
+    )]
+    fix: bool,
+
+    #[clap(
+        long,
+        default_value_t = false,
+        requires = "fix",
+        help = "if --unsafe-fixes, unsafe fixes are applied along with safe ones"
+    )]
+    unsafe_fixes: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct DiffArgs {
+    #[clap(flatten)]
+    config: WithConfig,
+
+    #[clap(
+        help = "the package, schema or definitions file, or directory of exported definitions to compare against."
+    )]
+    baseline: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ExportArgs {
+    #[clap(flatten)]
+    config: WithConfig,
+
+    #[clap(
+        short,
+        long,
+        help = "the directory to write definitions to, one file per namespace."
+    )]
+    out: PathBuf,
+
+    #[clap(
+        short,
+        long,
+        default_value = "json",
+        value_parser = ["json", "toml"],
+        help = "the format to write definitions in."
+    )]
+    format: String,
 }
 
 #[derive(clap::Args, Debug, Clone)]
@@ -135,9 +249,17 @@ struct FmtArgs {
     )]
     dry: bool,
 
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "if --check, no edits will be written and a diff is printed for every file which would change. fails if any file is not formatted"
+    )]
+    check: bool,
+
     #[clap(
         long,
         default_value_t = true,
+        action = clap::ArgAction::Set,
         help = "if --safe=false, unsafe edits will be applied"
     )]
     safe: bool,
@@ -0,0 +1,135 @@
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode};
+use operation_api_manifests::rules::{Fix, RuleLevel};
+
+use crate::checks::{Rule, fix::Edit};
+
+/// a rule which did not hold, graded by the level the rule is configured at
+#[derive(Debug)]
+pub struct Finding {
+    pub code: String,
+    pub level: RuleLevel,
+    pub description: &'static str,
+    pub fix: Fix,
+    pub error: crate::Error,
+    /// edits which would fix the finding, if the rule provides any
+    pub edits: Vec<Edit>,
+}
+
+impl Finding {
+    pub fn new(
+        rule: &Rule,
+        error: crate::Error,
+    ) -> Self {
+        let (error, edits) = match error {
+            crate::Error::Fixable { error, edits } => (*error, edits),
+            error => (error, vec![]),
+        };
+        Self {
+            code: format!("{}::{}", rule.group, rule.name),
+            level: rule.level.clone(),
+            description: rule.description,
+            fix: rule.fix.clone(),
+            error,
+            edits,
+        }
+    }
+}
+
+impl Display for Finding {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for Finding {}
+
+impl Diagnostic for Finding {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(&self.code))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(match self.level {
+            RuleLevel::Silent | RuleLevel::Info => Severity::Advice,
+            RuleLevel::Warn => Severity::Warning,
+            RuleLevel::Error => Severity::Error,
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let fix = (!self.edits.is_empty()).then(|| self.fix.diagnostic());
+        let help = [Some(self.description), fix]
+            .into_iter()
+            .flatten()
+            .filter(|it| !it.is_empty())
+            .collect::<Vec<_>>();
+        if help.is_empty() {
+            return None;
+        }
+        Some(Box::new(help.join("\n")))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        match &self.error {
+            crate::Error::Miette(report) => report.source_code(),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        match &self.error {
+            crate::Error::Miette(report) => report.labels(),
+            _ => None,
+        }
+    }
+}
+
+/// findings of every rule run over a context
+#[derive(Debug, Default)]
+pub struct Findings {
+    pub findings: Vec<Finding>,
+}
+
+impl Findings {
+    pub fn push(
+        &mut self,
+        finding: Finding,
+    ) {
+        self.findings.push(finding);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    pub fn count(
+        &self,
+        level: RuleLevel,
+    ) -> usize {
+        self.findings
+            .iter()
+            .filter(|it| it.level == level)
+            .count()
+    }
+
+    /// renders every finding into `w`, failing if any was an error
+    pub fn report(
+        self,
+        w: &mut impl std::io::Write,
+    ) -> crate::Result<()> {
+        let errors = self.count(RuleLevel::Error);
+        let warnings = self.count(RuleLevel::Warn);
+        for finding in self.findings {
+            writeln!(w, "{:?}", miette::Report::new(finding))?;
+        }
+        if errors > 0 {
+            return Err(crate::Error::ChecksFailed { errors, warnings });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use operation_api_manifests::rules::Fix;
+use operation_api_parser::fmt::{FormatConfig, fix};
+
+use crate::{
+    checks::{Finding, Findings},
+    source::{SourceFile, SourceSpan},
+};
+
+/// a replacement of the source text within `span`
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub span: SourceSpan,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn new(
+        span: SourceSpan,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// edits of the same text which disagree on its replacement
+    fn conflicts(
+        &self,
+        other: &Self,
+    ) -> bool {
+        let (a, b) = (&self.span.span, &other.span.span);
+        self.span.path() == other.span.path()
+            && a.start < b.end
+            && b.start < a.end
+            && (a != b || self.replacement != other.replacement)
+    }
+}
+
+/// the outcome of fixing findings, before anything is written
+#[derive(Debug, Default)]
+pub struct Fixes {
+    /// the fixed and formatted source of every edited file
+    pub files: BTreeMap<PathBuf, String>,
+    pub fixed: usize,
+    /// findings which were not fixed
+    pub remaining: Findings,
+    /// files refused as formatting them would change their tokens, and comments formatting moved
+    pub reports: Vec<miette::Report>,
+}
+
+impl Fixes {
+    pub fn write(&self) -> crate::Result<()> {
+        for (path, source) in &self.files {
+            std::fs::write(path, source)?;
+        }
+        Ok(())
+    }
+}
+
+impl Finding {
+    pub fn is_fixable(
+        &self,
+        unsafe_fixes: bool,
+    ) -> bool {
+        !self.edits.is_empty()
+            && match self.fix {
+                Fix::Safe => true,
+                Fix::Unsafe => unsafe_fixes,
+                Fix::Skip => false,
+            }
+    }
+}
+
+impl Findings {
+    /// applies the edits of every fixable finding, formatting each edited file.
+    ///
+    /// findings conflicting with an earlier one are left for a later run, and any file which
+    /// would no longer parse, or whose tokens formatting would change, is left untouched along
+    /// with every finding editing it.
+    pub fn fix(
+        self,
+        unsafe_fixes: bool,
+        config: &FormatConfig,
+    ) -> Fixes {
+        let mut accepted: Vec<Finding> = vec![];
+        let mut remaining = vec![];
+        for finding in self.findings {
+            let conflicts = || {
+                accepted.iter().any(|other| {
+                    finding.edits.iter().any(|edit| {
+                        other
+                            .edits
+                            .iter()
+                            .any(|it| edit.conflicts(it))
+                    })
+                })
+            };
+            if finding.is_fixable(unsafe_fixes) && !conflicts() {
+                accepted.push(finding);
+            } else {
+                remaining.push(finding);
+            }
+        }
+
+        let mut refused = vec![];
+        loop {
+            let mut by_file = BTreeMap::<PathBuf, (Arc<SourceFile>, Vec<fix::Edit>)>::new();
+            for edit in accepted.iter().flat_map(|it| &it.edits) {
+                by_file
+                    .entry(edit.span.path().to_path_buf())
+                    .or_insert_with(|| (edit.span.file.clone(), vec![]))
+                    .1
+                    .push(fix::Edit::new(
+                        edit.span.span.clone(),
+                        edit.replacement.clone(),
+                    ));
+            }
+
+            let mut files = BTreeMap::new();
+            let mut failed = BTreeSet::new();
+            let mut moved = vec![];
+            for (path, (file, edits)) in by_file {
+                match fix::apply(&path, &file.source, edits, config) {
+                    Ok(fixed) => {
+                        if let Some(unsafe_edit) = fixed.safety.unsafe_edit {
+                            refused.push(miette::Report::new(unsafe_edit));
+                            failed.insert(path);
+                            continue;
+                        }
+                        moved.extend(
+                            fixed
+                                .safety
+                                .moved
+                                .into_iter()
+                                .map(miette::Report::new),
+                        );
+                        files.insert(path, fixed.source);
+                    },
+                    Err(err) => {
+                        tracing::warn!("not fixing '{}': {err}", path.display());
+                        failed.insert(path);
+                    },
+                }
+            }
+
+            if failed.is_empty() {
+                return Fixes {
+                    files,
+                    fixed: accepted.len(),
+                    remaining: Findings {
+                        findings: remaining,
+                    },
+                    reports: refused.into_iter().chain(moved).collect(),
+                };
+            }
+
+            let (keep, drop): (Vec<_>, Vec<_>) = accepted.into_iter().partition(|it| {
+                !it.edits
+                    .iter()
+                    .any(|edit| failed.contains(edit.span.path()))
+            });
+            accepted = keep;
+            remaining.extend(drop);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use operation_api_parser::fmt::FormatConfig;
+
+    use super::Fixes;
+    use crate::{
+        checks::{RuleConfig, RuleRegistry},
+        context::Context,
+        lower::Lowering,
+    };
+
+    const SOURCE: &str = r#"namespace test;
+
+struct some_struct {
+	fooBar: i32
+};
+
+struct Holder {
+	inner: some_struct,
+	all: some_struct[]
+};
+
+enum Color {
+	red = "r",
+	green
+};
+"#;
+
+    const FIXED: &str = r#"namespace test;
+
+struct SomeStruct {
+	foo_bar: i32
+};
+
+struct Holder {
+	inner: SomeStruct,
+	all: SomeStruct[]
+};
+
+enum Color {
+	Red = "r",
+	Green = "green"
+};
+"#;
+
+    fn fixes(
+        source: &str,
+        config: &str,
+        unsafe_fixes: bool,
+    ) -> Fixes {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_source("test.pld", source)
+            .unwrap();
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish().unwrap())
+            .unwrap();
+        ctx.finish().unwrap();
+
+        let config = toml::from_str::<RuleConfig>(config).unwrap();
+        RuleRegistry::new(config)
+            .run(&ctx)
+            .fix(unsafe_fixes, &FormatConfig::default())
+    }
+
+    fn fix(
+        source: &str,
+        config: &str,
+        unsafe_fixes: bool,
+    ) -> (Option<String>, Vec<String>) {
+        let fixes = fixes(source, config, unsafe_fixes);
+        let remaining = fixes
+            .remaining
+            .findings
+            .into_iter()
+            .filter(|it| it.code != "form::Fails")
+            .map(|it| it.code)
+            .collect();
+        (
+            fixes
+                .files
+                .get(std::path::Path::new("test.pld"))
+                .cloned(),
+            remaining,
+        )
+    }
+
+    #[test]
+    fn test_fix() {
+        let (fixed, remaining) = fix(SOURCE, "", false);
+        assert_eq!(fixed.as_deref(), Some(FIXED));
+        assert_eq!(remaining, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_fix_comments() {
+        let source = SOURCE.replace(
+            "struct Holder {",
+            "/* holds a struct */\nstruct Holder { /* inline */",
+        );
+        let (fixed, _) = fix(&source, "", false);
+        let fixed = fixed.unwrap();
+        assert!(fixed.contains("/* holds a struct */"), "{fixed}");
+        assert!(fixed.contains("/* inline */"), "{fixed}");
+    }
+
+    #[test]
+    fn test_fix_unsafe_format() {
+        // formatting would not keep the comment after the last field
+        let source = SOURCE.replace("all: some_struct[]\n", "all: some_struct[] /* trails */\n");
+        let fixes = fixes(&source, "", false);
+        assert!(fixes.files.is_empty(), "{:?}", fixes.files);
+        assert_eq!(fixes.fixed, 0);
+        assert_eq!(fixes.reports.len(), 1);
+    }
+
+    #[test_case::test_case("[overrides.form.TypeCase]\nfix = \"unsafe\"", false, &["form::TypeCase"]; "unsafe skipped")]
+    #[test_case::test_case("[overrides.form.TypeCase]\nfix = \"unsafe\"", true, &[]; "unsafe applied")]
+    #[test_case::test_case("[overrides.form.TypeCase]\nfix = \"skip\"", true, &["form::TypeCase"]; "skip")]
+    fn test_fix_level(
+        config: &str,
+        unsafe_fixes: bool,
+        expect: &[&str],
+    ) {
+        let (fixed, remaining) = fix(SOURCE, config, unsafe_fixes);
+        assert_eq!(remaining, expect);
+        assert_eq!(
+            fixed.unwrap().contains("struct SomeStruct"),
+            expect.is_empty()
+        );
+    }
+}
@@ -1,10 +1,17 @@
 use operation_api_manifests::rules::*;
 use serde::Deserialize;
 
-use crate::{OneOf, Struct, Type};
+use crate::{
+    Enum, ErrorTy, OneOf, Operation, Struct, Type, context::Context, namespace::Namespace,
+};
 
+pub mod finding;
+pub mod fix;
+pub mod naming;
 pub mod self_ref;
 
+pub use finding::{Finding, Findings};
+
 #[allow(unused_variables)]
 pub trait Check: Send + Sync {
     fn check_type(
@@ -26,14 +33,54 @@ pub trait Check: Send + Sync {
     ) -> crate::Result<()> {
         Ok(())
     }
+
+    fn check_enum(
+        &self,
+        def: &Enum,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn check_error(
+        &self,
+        def: &ErrorTy,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn check_operation(
+        &self,
+        op: &Operation,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// checks spanning several definitions, run once the context is finished
+    fn check_context(
+        &self,
+        ctx: &Context,
+    ) -> crate::Result<()> {
+        Ok(())
+    }
 }
 
-#[derive(Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum RuleGroup {
     Form,
 }
 
+impl std::fmt::Display for RuleGroup {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Form => write!(f, "form"),
+        }
+    }
+}
+
 operation_api_manifests::rule_config! {
     "op-check" for RuleGroup
 }
@@ -85,12 +132,68 @@ impl RuleRegistry {
         Self { collector, config }
     }
 
-    // pub async fn run(&self) -> crate::Result<()> {
-    //     let mut futs = vec![];
-    //     for plugin in &self.collector.plugins {
+    /// runs every rule which is not silenced over each namespace of the context
+    pub fn run(
+        &self,
+        ctx: &Context,
+    ) -> Findings {
+        let mut findings = Findings::default();
+        for rule in &self.collector.plugins {
+            if rule.level == RuleLevel::Silent {
+                continue;
+            }
+            let mut report = |result: crate::Result<()>| {
+                if let Err(error) = result {
+                    for error in error.flatten() {
+                        findings.push(Finding::new(rule, error));
+                    }
+                }
+            };
+            for ns in ctx.namespaces.values() {
+                run_namespace(rule.handle.as_ref(), ns, &mut report);
+            }
+            report(rule.handle.check_context(ctx));
+        }
+        findings
+    }
+}
 
-    //     }
-    // }
+fn run_namespace(
+    check: &dyn Check,
+    ns: &Namespace,
+    report: &mut impl FnMut(crate::Result<()>),
+) {
+    for def in ns.defs.values() {
+        report(check.check_struct(def));
+        for field in def.fields.values() {
+            if let crate::FieldOrRef::Value(field) = field {
+                report(check.check_type(&field.ty));
+            }
+        }
+    }
+    for def in ns.enums.values() {
+        report(check.check_enum(def));
+    }
+    for def in ns.one_ofs.values() {
+        report(check.check_one_of(def));
+        for variant in def.variants.values() {
+            report(check.check_type(&variant.ty));
+        }
+    }
+    for def in ns.errors.values() {
+        report(check.check_error(def));
+        for variant in def.variants.values() {
+            report(check.check_type(&variant.ty));
+        }
+    }
+    for op in ns.ops.values() {
+        report(check.check_operation(op));
+        for field in op.inputs.values().chain(op.outputs.values()) {
+            if let crate::FieldOrRef::Value(field) = field {
+                report(check.check_type(&field.ty));
+            }
+        }
+    }
 }
 
 #[macro_export]
@@ -134,7 +237,7 @@ mod test {
             check.check_type(&Type::Binary).is_err()
         }
     }; "test plugin loads" )]
-    fn test_plugin<F: Fn(Box<dyn Check>) -> ()>(
+    fn test_plugin<F: Fn(Box<dyn Check>)>(
         name: &'static str,
         f: F,
     ) {
@@ -149,4 +252,80 @@ mod test {
 
         panic!("did not run {name}")
     }
+
+    fn context(source: &str) -> Context {
+        let mut lowering = crate::lower::Lowering::new();
+        lowering
+            .add_source("test.pld", format!("namespace test;\n{source}"))
+            .unwrap();
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish().unwrap())
+            .unwrap();
+        ctx.finish().unwrap();
+        ctx
+    }
+
+    fn findings(
+        config: &str,
+        source: &str,
+        code: &str,
+    ) -> Vec<Finding> {
+        let config = toml::from_str::<RuleConfig>(config).unwrap();
+        RuleRegistry::new(config)
+            .run(&context(source))
+            .findings
+            .into_iter()
+            .filter(|it| it.code == code)
+            .collect()
+    }
+
+    #[test_case::test_case("", "struct A { a: i32, b: str };", 2; "struct fields")]
+    #[test_case::test_case("", "oneof A { a(i32), b(str) };", 2; "one_of variants")]
+    #[test_case::test_case("", "operation add(a: i32, b: i32) -> i32;", 3; "operation fields")]
+    #[test_case::test_case("", "enum A { a = 1 };", 0; "enum members")]
+    #[test_case::test_case("[overrides.form.Fails]\nlevel = \"silent\"", "struct A { a: i32 };", 0; "silenced")]
+    fn test_run(
+        config: &str,
+        source: &str,
+        expect: usize,
+    ) {
+        assert_eq!(findings(config, source, "form::Fails").len(), expect);
+    }
+
+    #[test_case::test_case("", Some(miette::Severity::Error), true; "error")]
+    #[test_case::test_case("[overrides.form.Fails]\nlevel = \"warn\"", Some(miette::Severity::Warning), false; "warn")]
+    #[test_case::test_case("[overrides.form.Fails]\nlevel = \"info\"", Some(miette::Severity::Advice), false; "info")]
+    fn test_report(
+        config: &str,
+        severity: Option<miette::Severity>,
+        fails: bool,
+    ) {
+        use miette::Diagnostic;
+
+        let findings = findings(config, "struct A { a: i32 };", "form::Fails");
+        assert_eq!(findings[0].severity(), severity);
+        assert_eq!(
+            findings[0]
+                .help()
+                .map(|it| it.to_string())
+                .as_deref(),
+            Some("rule abc: must be xyz")
+        );
+        let mut out = vec![];
+        let report = Findings { findings }.report(&mut out);
+        assert_eq!(report.is_err(), fails);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("rule abc: must be xyz"), "{out}");
+    }
+
+    #[test]
+    fn test_run_context() {
+        let findings = findings(
+            "",
+            "struct A { b: B };\nstruct B { a: A };",
+            "form::SelfRef",
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].level, RuleLevel::Error);
+    }
 }
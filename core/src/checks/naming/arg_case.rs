@@ -0,0 +1,21 @@
+use super::*;
+
+crate::rule! {
+    ArgCase in Form @ Warn: Safe; "operation arguments and results are named in snake_case"
+}
+
+impl Check for ArgCase {
+    fn check_operation(
+        &self,
+        op: &Operation,
+    ) -> crate::Result<()> {
+        crate::Error::many(
+            [
+                SNAKE.fields("argument", &op.inputs),
+                SNAKE.fields("result", &op.outputs),
+            ]
+            .into_iter()
+            .filter_map(Result::err),
+        )
+    }
+}
@@ -0,0 +1,56 @@
+use super::*;
+
+crate::rule! {
+    CaseCollision in Form @ Error: Skip; "names in the same scope must stay distinct once generators convert their case"
+}
+
+impl Check for CaseCollision {
+    fn check_context(
+        &self,
+        ctx: &Context,
+    ) -> crate::Result<()> {
+        let mut errors = vec![];
+        for ns in ctx.namespaces.values() {
+            errors.extend(
+                PASCAL.collisions(
+                    "type",
+                    &ns.name,
+                    ns.defs
+                        .keys()
+                        .chain(ns.enums.keys())
+                        .chain(ns.one_ofs.keys())
+                        .chain(ns.errors.keys())
+                        .map(|it| &it.name),
+                ),
+            );
+            errors.extend(SNAKE.collisions(
+                "operation",
+                &ns.name,
+                ns.ops.keys().map(|it| &it.name),
+            ));
+
+            for (key, def) in &ns.defs {
+                let scope = format!("{}.{}", ns.name, key.name);
+                errors.extend(SNAKE.collisions("field", scope, def.fields.keys()));
+            }
+            for (key, def) in &ns.enums {
+                let scope = format!("{}.{}", ns.name, key.name);
+                errors.extend(PASCAL.collisions("variant", scope, def.variants.keys()));
+            }
+            for (key, def) in &ns.one_ofs {
+                let scope = format!("{}.{}", ns.name, key.name);
+                errors.extend(PASCAL.collisions("variant", scope, def.variants.keys()));
+            }
+            for (key, def) in &ns.errors {
+                let scope = format!("{}.{}", ns.name, key.name);
+                errors.extend(PASCAL.collisions("variant", scope, def.variants.keys()));
+            }
+            for (key, op) in &ns.ops {
+                let scope = format!("{}.{}", ns.name, key.name);
+                errors.extend(SNAKE.collisions("argument", &scope, op.inputs.keys()));
+                errors.extend(SNAKE.collisions("result", &scope, op.outputs.keys()));
+            }
+        }
+        crate::Error::many(errors)
+    }
+}
@@ -0,0 +1,14 @@
+use super::*;
+
+crate::rule! {
+    FieldCase in Form @ Warn: Safe; "struct fields are named in snake_case"
+}
+
+impl Check for FieldCase {
+    fn check_struct(
+        &self,
+        def: &Struct,
+    ) -> crate::Result<()> {
+        SNAKE.fields("field", &def.fields)
+    }
+}
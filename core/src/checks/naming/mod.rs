@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use convert_case::{Case, Casing};
+
+use crate::{
+    FieldOrRef, FieldsList, Ident,
+    checks::{fix::Edit, *},
+    namespace::References,
+    resolve::at,
+    source::SourceSpan,
+};
+
+pub mod arg_case;
+pub mod case_collision;
+pub mod field_case;
+pub mod operation_case;
+pub mod type_case;
+pub mod variant_case;
+
+/// a case identifiers of some kind are expected in, as listed in `docs/src/naming.md`
+#[derive(Clone, Copy)]
+pub struct Convention {
+    pub case: Case<'static>,
+    pub name: &'static str,
+}
+
+pub const PASCAL: Convention = Convention {
+    case: Case::Pascal,
+    name: "PascalCase",
+};
+
+pub const SNAKE: Convention = Convention {
+    case: Case::Snake,
+    name: "snake_case",
+};
+
+impl Convention {
+    pub fn rename(
+        &self,
+        name: &Ident,
+    ) -> Ident {
+        name.to_string().to_case(self.case).into()
+    }
+
+    /// a misnamed identifier, renamed at its declaration and at every reference when fixed
+    fn expect(
+        &self,
+        kind: &'static str,
+        name: &Ident,
+        span: Option<&SourceSpan>,
+        references: Option<&References>,
+    ) -> Option<crate::Error> {
+        self.expect_with(kind, name, span, references, ToString::to_string)
+    }
+
+    /// as [`Self::expect`], with the text replacing the declared name derived from its rename
+    fn expect_with(
+        &self,
+        kind: &'static str,
+        name: &Ident,
+        span: Option<&SourceSpan>,
+        references: Option<&References>,
+        declaration: impl FnOnce(&Ident) -> String,
+    ) -> Option<crate::Error> {
+        let rename = self.rename(name);
+        if &rename == name {
+            return None;
+        }
+        let edits = match (span, references) {
+            // a reference without a span would be left dangling
+            (_, Some(references)) if references.untracked => vec![],
+            (Some(span), references) => {
+                std::iter::once(Edit::new(span.clone(), declaration(&rename)))
+                    .chain(
+                        references
+                            .into_iter()
+                            .flat_map(|it| &it.spans)
+                            .map(|span| Edit::new(span.clone(), rename.to_string())),
+                    )
+                    .collect()
+            },
+            (None, _) => vec![],
+        };
+        let err = crate::Error::Misnamed {
+            kind,
+            name: name.clone(),
+            case: self.name,
+            rename,
+        };
+        Some(at(span, err).with_edits(edits))
+    }
+
+    fn fields(
+        &self,
+        kind: &'static str,
+        fields: &FieldsList,
+    ) -> crate::Result<()> {
+        crate::Error::many(fields.iter().filter_map(|(name, field)| {
+            let span = match field {
+                FieldOrRef::Value(field) => field.meta.span.as_ref(),
+                _ => None,
+            };
+            self.expect(kind, name, span, None)
+        }))
+    }
+
+    /// names which convert to the same identifier, ignoring case
+    fn collisions<'n>(
+        &self,
+        kind: &'static str,
+        scope: impl std::fmt::Display,
+        names: impl IntoIterator<Item = &'n Ident>,
+    ) -> Vec<crate::Error> {
+        let mut converted = BTreeMap::<String, Vec<&Ident>>::new();
+        for name in names {
+            // versions of a declaration share its name
+            let same = converted
+                .entry(self.rename(name).to_string().to_lowercase())
+                .or_default();
+            if !same.contains(&name) {
+                same.push(name);
+            }
+        }
+        converted
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .map(|names| {
+                crate::Error::CaseCollision {
+                    kind,
+                    names: names
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    scope: scope.to_string(),
+                    case: self.name,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lower::Lowering;
+
+    fn context(source: &str) -> Context {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_source("test.pld", format!("namespace test;\n{source}"))
+            .unwrap();
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish().unwrap())
+            .unwrap();
+        ctx.finish().unwrap();
+        ctx
+    }
+
+    fn findings(
+        source: &str,
+        rule: &str,
+    ) -> Vec<String> {
+        RuleRegistry::new(RuleConfig::default())
+            .run(&context(source))
+            .findings
+            .into_iter()
+            .filter(|it| it.code == format!("form::{rule}"))
+            .map(|it| it.to_string())
+            .collect()
+    }
+
+    #[test_case::test_case("struct Foo { a: i32 };", "TypeCase", &[]; "pascal struct")]
+    #[test_case::test_case(
+        "struct foo_bar { a: i32 };",
+        "TypeCase",
+        &["struct foo_bar should be PascalCase, rename it to FooBar"];
+        "snake struct"
+    )]
+    #[test_case::test_case(
+        "enum color { Red = 1 };\noneof any_of { a(i32), b(str) };",
+        "TypeCase",
+        &[
+            "enum color should be PascalCase, rename it to Color",
+            "one_of any_of should be PascalCase, rename it to AnyOf",
+        ];
+        "enum and one_of"
+    )]
+    #[test_case::test_case(
+        "enum Color { red = 1, Green = 2 };",
+        "VariantCase",
+        &["variant red should be PascalCase, rename it to Red"];
+        "enum variant"
+    )]
+    #[test_case::test_case(
+        "struct Foo { fooBar: i32, Baz: i32, ok: i32 };",
+        "FieldCase",
+        &[
+            "field fooBar should be snake_case, rename it to foo_bar",
+            "field Baz should be snake_case, rename it to baz",
+        ];
+        "fields"
+    )]
+    #[test_case::test_case(
+        "operation addOne(someValue: i32) -> i32;",
+        "OperationCase",
+        &["operation addOne should be snake_case, rename it to add_one"];
+        "operation"
+    )]
+    #[test_case::test_case(
+        "operation add_one(someValue: i32) -> i32;",
+        "ArgCase",
+        &["argument someValue should be snake_case, rename it to some_value"];
+        "argument"
+    )]
+    #[test_case::test_case(
+        "struct Foo { foo_bar: i32, fooBar: i32 };",
+        "CaseCollision",
+        &["fields foo_bar, fooBar in test.Foo collide once converted to snake_case"];
+        "field collision"
+    )]
+    #[test_case::test_case(
+        "struct FooBar { a: i32 };\nenum Foobar { A = 1 };",
+        "CaseCollision",
+        &["types FooBar, Foobar in test collide once converted to PascalCase"];
+        "type collision"
+    )]
+    #[test_case::test_case("struct Foo { foo: i32, bar: i32 };", "CaseCollision", &[]; "distinct")]
+    fn test_naming(
+        source: &str,
+        rule: &str,
+        expect: &[&str],
+    ) {
+        assert_eq!(findings(source, rule), expect);
+    }
+
+    #[test]
+    fn test_naming_package() {
+        let mut ctx = Context::new();
+        ctx.load_package("../parser/samples/abc-corp")
+            .unwrap();
+        ctx.finish().unwrap();
+        let findings = RuleRegistry::new(RuleConfig::default())
+            .run(&ctx)
+            .findings
+            .into_iter()
+            .filter(|it| it.code != "form::Fails")
+            .map(|it| it.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(findings, Vec::<String>::new());
+    }
+}
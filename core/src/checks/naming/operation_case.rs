@@ -0,0 +1,14 @@
+use super::*;
+
+crate::rule! {
+    OperationCase in Form @ Warn: Safe; "operations are named in snake_case"
+}
+
+impl Check for OperationCase {
+    fn check_operation(
+        &self,
+        op: &Operation,
+    ) -> crate::Result<()> {
+        crate::Error::many(SNAKE.expect("operation", &op.meta.name, op.meta.span.as_ref(), None))
+    }
+}
@@ -0,0 +1,33 @@
+use super::*;
+
+crate::rule! {
+    TypeCase in Form @ Warn: Safe; "structs, enums, one_ofs and errors are named in PascalCase"
+}
+
+impl Check for TypeCase {
+    // references are kept by namespace, so types are checked along with their namespace
+    fn check_context(
+        &self,
+        ctx: &Context,
+    ) -> crate::Result<()> {
+        let mut errors = vec![];
+        for ns in ctx.namespaces.values() {
+            let mut expect = |kind, name, span: &Option<SourceSpan>| {
+                errors.extend(PASCAL.expect(kind, name, span.as_ref(), ns.references.get(name)));
+            };
+            for def in ns.defs.values() {
+                expect("struct", &def.meta.name, &def.meta.span);
+            }
+            for def in ns.enums.values() {
+                expect("enum", &def.meta.name, &def.meta.span);
+            }
+            for def in ns.one_ofs.values() {
+                expect("one_of", &def.meta.name, &def.meta.span);
+            }
+            for def in ns.errors.values() {
+                expect("error", &def.meta.name, &def.meta.span);
+            }
+        }
+        crate::Error::many(errors)
+    }
+}
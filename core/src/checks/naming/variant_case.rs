@@ -0,0 +1,61 @@
+use super::*;
+use crate::StrOrInt;
+
+crate::rule! {
+    VariantCase in Form @ Warn: Safe; "enum, one_of and error variants are named in PascalCase"
+}
+
+impl Check for VariantCase {
+    fn check_enum(
+        &self,
+        def: &Enum,
+    ) -> crate::Result<()> {
+        crate::Error::many(
+            def.variants
+                .iter()
+                .filter_map(|(name, variant)| {
+                    let span = variant.meta.span.as_ref();
+                    // a string variant without a value is valued by its name, which is kept
+                    let implicit = matches!(&variant.value, StrOrInt::String(value) if *value == name.to_string())
+                        && span.is_some_and(|span| {
+                            !span.file.source[span.span.end..]
+                                .trim_start()
+                                .starts_with('=')
+                        });
+                    PASCAL.expect_with("variant", name, span, None, |rename| {
+                        match implicit {
+                            true => format!("{rename} = \"{name}\""),
+                            false => rename.to_string(),
+                        }
+                    })
+                }),
+        )
+    }
+
+    fn check_one_of(
+        &self,
+        one_of: &OneOf,
+    ) -> crate::Result<()> {
+        crate::Error::many(
+            one_of
+                .variants
+                .iter()
+                .filter_map(|(name, variant)| {
+                    PASCAL.expect("variant", name, variant.span.as_ref(), None)
+                }),
+        )
+    }
+
+    fn check_error(
+        &self,
+        def: &ErrorTy,
+    ) -> crate::Result<()> {
+        crate::Error::many(
+            def.variants
+                .iter()
+                .filter_map(|(name, variant)| {
+                    PASCAL.expect("variant", name, variant.span.as_ref(), None)
+                }),
+        )
+    }
+}
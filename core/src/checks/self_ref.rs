@@ -1,7 +1,64 @@
-use crate::checks::*;
+use crate::{checks::*, graph::RefGraph};
 
 crate::rule! {
-    SelfRef in Form @ Warn: Unsafe; ""
+    SelfRef in Form @ Error: Skip; "recursive types must be optional, an array, or one of several variants somewhere on the cycle"
 }
 
-impl Check for SelfRef {}
+impl Check for SelfRef {
+    fn check_context(
+        &self,
+        ctx: &Context,
+    ) -> crate::Result<()> {
+        match RefGraph::new(&ctx.namespaces)
+            .unrepresentable()
+            .first()
+        {
+            Some(cycle) => {
+                Err(crate::Error::UnrepresentableCycle {
+                    cycle: cycle
+                        .iter()
+                        .map(|(ns, key)| format!("{ns}::{}", key.name))
+                        .collect::<Vec<_>>()
+                        .join(" -> "),
+                })
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lower::Lowering;
+
+    fn context(source: &str) -> Context {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_source("test.pld", format!("namespace test;\n{source}"))
+            .unwrap();
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish().unwrap())
+            .unwrap();
+        ctx.finish().unwrap();
+        ctx
+    }
+
+    #[test_case::test_case("struct A { a?: A };", None; "optional")]
+    #[test_case::test_case("struct A { b: B };\noneof B { a(A), c(i32) };", None; "escapes through oneof")]
+    #[test_case::test_case(
+        "struct A { b: B };\nstruct B { a: A };",
+        Some("test::A -> test::B is recursive through required members only, so no value of it can exist");
+        "required"
+    )]
+    fn test_self_ref(
+        source: &str,
+        expect: Option<&str>,
+    ) {
+        let err = SelfRef
+            .check_context(&context(source))
+            .err()
+            .map(|err| err.to_string());
+        assert_eq!(err.as_deref(), expect);
+    }
+}
@@ -1,6 +1,15 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-use crate::{Definitions, Ident, namespace::Namespace};
+use crate::{
+    Definitions, Ident,
+    graph::RefGraph,
+    lower::{self, Lowering},
+    namespace::Namespace,
+    resolve,
+};
 
 #[derive(Default)]
 pub struct Context {
@@ -16,6 +25,15 @@ impl Context {
         for ns in self.namespaces.values_mut() {
             ns.check()?;
         }
+        resolve::check(&self.namespaces)?;
+        resolve::pin(&mut self.namespaces);
+
+        for edge in RefGraph::new(&self.namespaces).recursive() {
+            if let Some(ns) = self.namespaces.get_mut(&edge.from.0) {
+                ns.recursive
+                    .insert((edge.from.1.clone(), edge.member.clone()));
+            }
+        }
         Ok(())
     }
 
@@ -23,6 +41,9 @@ impl Context {
         &mut self,
         source: PathBuf,
     ) -> crate::Result<()> {
+        if lower::is_schema(&source) {
+            return self.with_definitions(lower::load_from_path(source)?);
+        }
         self.with_definition(Definitions::load_from_path(source)?)
     }
 
@@ -30,8 +51,67 @@ impl Context {
         &mut self,
         sources: Vec<PathBuf>,
     ) -> crate::Result<()> {
+        // schemas are lowered together so that namespaces may span several files
+        let mut lowering = Lowering::new();
         for source in sources {
-            self.load_from_source(source)?;
+            if lower::is_schema(&source) {
+                lowering.add_file(source)?;
+            } else {
+                self.load_from_source(source)?;
+            }
+        }
+        self.with_definitions(lowering.finish()?)
+    }
+
+    /// loads a package from its `schema/lib.pld`, following `use` items
+    pub fn load_package(
+        &mut self,
+        root: impl AsRef<std::path::Path>,
+    ) -> crate::Result<()> {
+        let mut lowering = Lowering::new();
+        lowering.add_package(root)?;
+        self.with_definitions(lowering.finish()?)
+    }
+
+    /// loads a package, a single schema or definitions file, or a directory of definitions such
+    /// as written by [`Context::export`]
+    pub fn load_path(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> crate::Result<()> {
+        let path = path.as_ref();
+        if path.join("schema").join("lib.pld").is_file() {
+            return self.load_package(path);
+        }
+        if !path.is_dir() {
+            return self.load_from_source(path.to_path_buf());
+        }
+
+        let mut sources = vec![];
+        for entry in std::fs::read_dir(path)? {
+            let source = entry?.path();
+            let known = source
+                .extension()
+                .and_then(|it| it.to_str())
+                .is_some_and(|it| matches!(it, "pld" | "toml" | "json" | "yaml" | "yml"));
+            if known && source.is_file() {
+                sources.push(source);
+            }
+        }
+        sources.sort();
+        self.load_from_sources(sources)
+    }
+
+    /// writes each namespace to `dir` as a single definition, named by the namespace
+    pub fn export(
+        &self,
+        dir: impl AsRef<Path>,
+        ext: &str,
+    ) -> crate::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for ns in self.namespaces.values() {
+            Definitions::NamespaceV1(ns.clone()).export(dir.join(format!("{}.{ext}", ns.name)))?;
         }
         Ok(())
     }
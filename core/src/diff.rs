@@ -0,0 +1,737 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use crate::{
+    CompoundType, Enum, FieldOrRef, FieldsList, Ident, Key, Named, OneOfVariant, Operation,
+    StrOrInt, Struct, Type,
+    context::Context,
+    namespace::{Declarations, Namespace},
+};
+
+/// how a change affects clients built against the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Impact {
+    /// documentation or ordering only, nothing on the wire changes
+    Cosmetic,
+    /// existing clients keep working
+    Compatible,
+    /// existing clients may fail to encode or decode values
+    Breaking,
+}
+
+impl Display for Impact {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Cosmetic => "cosmetic",
+            Self::Compatible => "compatible",
+            Self::Breaking => "breaking",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Change {
+    pub impact: Impact,
+    /// the changed item, and member if any, e.g. `Foo.bar`
+    pub site: String,
+    pub description: String,
+    /// set when the version of the changed item, or of its namespace, was raised
+    pub bumped: bool,
+}
+
+impl Change {
+    /// a breaking change the version does not account for
+    pub fn is_unversioned(&self) -> bool {
+        self.impact == Impact::Breaking && !self.bumped
+    }
+}
+
+/// changes between two contexts, by namespace
+#[derive(Debug, Default, serde::Serialize)]
+pub struct Diff {
+    pub namespaces: BTreeMap<Ident, Vec<Change>>,
+}
+
+impl Diff {
+    pub fn new(
+        baseline: &Context,
+        current: &Context,
+    ) -> Self {
+        let mut this = Self::default();
+        let names = baseline
+            .namespaces
+            .keys()
+            .chain(current.namespaces.keys())
+            .collect::<BTreeSet<_>>();
+        for name in names {
+            let mut differ = Differ::default();
+            match (baseline.namespaces.get(name), current.namespaces.get(name)) {
+                (Some(_), None) => differ.push(Impact::Breaking, name, "namespace removed"),
+                (None, Some(_)) => differ.push(Impact::Compatible, name, "namespace added"),
+                (Some(base), Some(cur)) => differ.namespace(base, cur),
+                (None, None) => {},
+            }
+            if !differ.changes.is_empty() {
+                this.namespaces
+                    .insert(name.clone(), differ.changes);
+            }
+        }
+        this
+    }
+
+    pub fn changes(&self) -> impl Iterator<Item = &Change> {
+        self.namespaces.values().flatten()
+    }
+
+    /// prints every change, failing on breaking changes without a version bump
+    pub fn report(&self) -> crate::Result<()> {
+        print!("{self}");
+        let count = self
+            .changes()
+            .filter(|it| it.is_unversioned())
+            .count();
+        if count > 0 {
+            return Err(crate::Error::UnversionedBreakingChanges { count });
+        }
+        Ok(())
+    }
+}
+
+impl Display for Diff {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for (ns, changes) in &self.namespaces {
+            writeln!(f, "{ns}")?;
+            for change in changes {
+                write!(
+                    f,
+                    "  {:<10} {}: {}",
+                    change.impact, change.site, change.description
+                )?;
+                if change.is_unversioned() {
+                    write!(f, " (without a version bump)")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Differ {
+    changes: Vec<Change>,
+    /// the namespace version was raised, covering every change within it
+    bumped: bool,
+}
+
+impl Differ {
+    fn push(
+        &mut self,
+        impact: Impact,
+        site: impl Display,
+        description: impl Into<String>,
+    ) {
+        self.push_bumped(impact, site, description, false);
+    }
+
+    fn push_bumped(
+        &mut self,
+        impact: Impact,
+        site: impl Display,
+        description: impl Into<String>,
+        bumped: bool,
+    ) {
+        self.changes.push(Change {
+            impact,
+            site: site.to_string(),
+            description: description.into(),
+            bumped: bumped || self.bumped,
+        });
+    }
+
+    fn namespace(
+        &mut self,
+        base: &Namespace,
+        cur: &Namespace,
+    ) {
+        self.bumped = cur.version > base.version;
+        self.items("struct", &base.defs, &cur.defs, |this, site, base, cur| {
+            this.structs(site, base, cur)
+        });
+        self.items("enum", &base.enums, &cur.enums, |this, site, base, cur| {
+            this.enums(site, base, cur)
+        });
+        self.items(
+            "one_of",
+            &base.one_ofs,
+            &cur.one_ofs,
+            |this, site, base, cur| {
+                let bumped = cur.meta.version > base.meta.version;
+                this.variants(site, &base.variants, &cur.variants, bumped);
+                this.description(site, &base.meta.description, &cur.meta.description);
+            },
+        );
+        self.items(
+            "error",
+            &base.errors,
+            &cur.errors,
+            |this, site, base, cur| {
+                let bumped = cur.meta.version > base.meta.version;
+                this.variants(site, &base.variants, &cur.variants, bumped);
+                this.description(site, &base.meta.description, &cur.meta.description);
+            },
+        );
+        self.items("operation", &base.ops, &cur.ops, |this, site, base, cur| {
+            this.operations(site, base, cur)
+        });
+    }
+
+    /// declarations removed from or added to a namespace, diffing those in both.
+    ///
+    /// each version is compared with the same version, or else with the latest version which
+    /// replaced it. declarations held in several versions are named along with their version.
+    fn items<T>(
+        &mut self,
+        kind: &str,
+        base: &BTreeMap<Key, T>,
+        cur: &BTreeMap<Key, T>,
+        mut diff: impl FnMut(&mut Self, &str, &T, &T),
+    ) {
+        let site = |key: &Key| {
+            match base.is_versioned(&key.name) || cur.is_versioned(&key.name) {
+                true => key.to_string(),
+                false => key.name.to_string(),
+            }
+        };
+
+        let mut replacing = BTreeSet::new();
+        for (key, base_item) in base {
+            let replaced = cur.get_key_value(key).or_else(|| {
+                cur.versions(&key.name)
+                    .last()
+                    .filter(|(it, _)| !base.contains_key(it))
+            });
+            match replaced {
+                Some((cur_key, cur_item)) => {
+                    replacing.insert(cur_key);
+                    diff(self, &site(cur_key), base_item, cur_item)
+                },
+                None => self.push(Impact::Breaking, site(key), format!("{kind} removed")),
+            }
+        }
+        for key in cur
+            .keys()
+            .filter(|it| !replacing.contains(it))
+        {
+            self.push(Impact::Compatible, site(key), format!("{kind} added"));
+        }
+    }
+
+    fn description(
+        &mut self,
+        site: impl Display,
+        base: &Option<String>,
+        cur: &Option<String>,
+    ) {
+        if base != cur {
+            self.push(Impact::Cosmetic, site, "description changed");
+        }
+    }
+
+    fn structs(
+        &mut self,
+        site: &str,
+        base: &Struct,
+        cur: &Struct,
+    ) {
+        let bumped = cur.meta.version > base.meta.version;
+        self.fields(site, "field", &base.fields, &cur.fields, Flow::Both, bumped);
+        self.description(site, &base.meta.description, &cur.meta.description);
+    }
+
+    /// returned members flow from the server to clients, rather than from clients, so that
+    /// narrowing them is compatible while widening them is not. fields of structs may flow either
+    /// way, so that only changes which hold both ways are compatible.
+    fn fields(
+        &mut self,
+        parent: &str,
+        kind: &str,
+        base: &FieldsList,
+        cur: &FieldsList,
+        flow: Flow,
+        bumped: bool,
+    ) {
+        let returned = flow == Flow::Returned;
+        for (name, base) in base.iter() {
+            let site = format!("{parent}.{name}");
+            let Some(cur) = cur.get(name) else {
+                self.push_bumped(Impact::Breaking, site, format!("{kind} removed"), bumped);
+                continue;
+            };
+            match (base, cur) {
+                (FieldOrRef::Value(base), FieldOrRef::Value(cur)) => {
+                    match (base.optional, cur.optional) {
+                        (true, false) if returned => {
+                            self.push(Impact::Compatible, &site, format!("{kind} is now required"))
+                        },
+                        (true, false) => {
+                            self.push_bumped(
+                                Impact::Breaking,
+                                &site,
+                                format!("{kind} is now required"),
+                                bumped,
+                            )
+                        },
+                        (false, true) if flow == Flow::Sent => {
+                            self.push(
+                                Impact::Compatible,
+                                &site,
+                                format!("{kind} may now be absent"),
+                            )
+                        },
+                        (false, true) => {
+                            self.push_bumped(
+                                Impact::Breaking,
+                                &site,
+                                format!("{kind} may now be absent"),
+                                bumped,
+                            )
+                        },
+                        _ => {},
+                    }
+                    self.ty(&site, required(&base.ty), required(&cur.ty), flow, bumped);
+                    self.description(&site, &base.meta.description, &cur.meta.description);
+                },
+                (base, cur) if base != cur => {
+                    self.push_bumped(Impact::Breaking, site, format!("{kind} changed"), bumped)
+                },
+                _ => {},
+            }
+        }
+        for (name, field) in cur
+            .iter()
+            .filter(|(name, _)| !base.contains_key(*name))
+        {
+            let site = format!("{parent}.{name}");
+            match field {
+                FieldOrRef::Value(field) if field.optional => {
+                    self.push(Impact::Compatible, site, format!("optional {kind} added"))
+                },
+                _ if returned => self.push(Impact::Compatible, site, format!("{kind} added")),
+                _ => {
+                    self.push_bumped(
+                        Impact::Breaking,
+                        site,
+                        format!("required {kind} added"),
+                        bumped,
+                    )
+                },
+            }
+        }
+        self.order(parent, kind, base.keys(), cur.keys());
+    }
+
+    /// members kept in both, but declared in another order
+    fn order<'n>(
+        &mut self,
+        parent: &str,
+        kind: &str,
+        base: impl Iterator<Item = &'n Ident>,
+        cur: impl Iterator<Item = &'n Ident> + Clone,
+    ) {
+        let kept = |it: &&Ident| cur.clone().any(|other| other == *it);
+        let base = base.filter(kept).collect::<Vec<_>>();
+        let cur = cur
+            .clone()
+            .filter(|it| base.contains(it))
+            .collect::<Vec<_>>();
+        if base != cur {
+            self.push(Impact::Cosmetic, parent, format!("{kind}s reordered"));
+        }
+    }
+
+    /// sent members may only widen and returned members may only narrow, while members flowing
+    /// both ways must keep their type
+    fn ty(
+        &mut self,
+        site: &str,
+        base: &Type,
+        cur: &Type,
+        flow: Flow,
+        bumped: bool,
+    ) {
+        if base == cur {
+            return;
+        }
+        let (impact, how) = match flow {
+            Flow::Sent if widens(base, cur) => (Impact::Compatible, "widened"),
+            Flow::Returned if widens(cur, base) => (Impact::Compatible, "narrowed"),
+            _ => (Impact::Breaking, "changed"),
+        };
+        self.push_bumped(
+            impact,
+            site,
+            format!("type {how} from {base} to {cur}"),
+            bumped,
+        );
+    }
+
+    fn enums(
+        &mut self,
+        parent: &str,
+        base: &Enum,
+        cur: &Enum,
+    ) {
+        let bumped = cur.meta.version > base.meta.version;
+        for (name, variant) in base.variants.iter() {
+            let site = format!("{parent}.{name}");
+            match cur.variants.get(name) {
+                None => self.push_bumped(Impact::Breaking, site, "variant removed", bumped),
+                Some(cur) if cur.value != variant.value => {
+                    self.push_bumped(
+                        Impact::Breaking,
+                        site,
+                        format!(
+                            "variant renumbered from {} to {}",
+                            value(&variant.value),
+                            value(&cur.value)
+                        ),
+                        bumped,
+                    )
+                },
+                Some(cur) => {
+                    self.description(site, &variant.meta.description, &cur.meta.description)
+                },
+            }
+        }
+        for name in cur
+            .variants
+            .keys()
+            .filter(|it| !base.variants.contains_key(*it))
+        {
+            self.push(
+                Impact::Compatible,
+                format!("{parent}.{name}"),
+                "variant added",
+            );
+        }
+        self.order(parent, "variant", base.variants.keys(), cur.variants.keys());
+        self.description(parent, &base.meta.description, &cur.meta.description);
+    }
+
+    fn variants(
+        &mut self,
+        parent: &str,
+        base: &Named<OneOfVariant>,
+        cur: &Named<OneOfVariant>,
+        bumped: bool,
+    ) {
+        for (name, variant) in base.iter() {
+            let site = format!("{parent}.{name}");
+            match cur.get(name) {
+                None => self.push_bumped(Impact::Breaking, site, "variant removed", bumped),
+                Some(cur) => {
+                    self.ty(&site, &variant.ty, &cur.ty, Flow::Both, bumped);
+                    self.description(&site, &variant.description, &cur.description);
+                },
+            }
+        }
+        for name in cur
+            .keys()
+            .filter(|it| !base.contains_key(*it))
+        {
+            self.push(
+                Impact::Compatible,
+                format!("{parent}.{name}"),
+                "variant added",
+            );
+        }
+        self.order(parent, "variant", base.keys(), cur.keys());
+    }
+
+    fn operations(
+        &mut self,
+        parent: &str,
+        base: &Operation,
+        cur: &Operation,
+    ) {
+        let bumped = cur.meta.version > base.meta.version;
+        self.fields(
+            parent,
+            "argument",
+            &base.inputs,
+            &cur.inputs,
+            Flow::Sent,
+            bumped,
+        );
+        self.fields(
+            parent,
+            "result",
+            &base.outputs,
+            &cur.outputs,
+            Flow::Returned,
+            bumped,
+        );
+        if base.infallible != cur.infallible || base.error != cur.error {
+            let describe = |op: &Operation| {
+                match &op.error {
+                    Some(error) if !op.infallible => error.to_string(),
+                    _ => "none".into(),
+                }
+            };
+            self.push_bumped(
+                Impact::Breaking,
+                parent,
+                format!("error changed from {} to {}", describe(base), describe(cur)),
+                bumped,
+            );
+        }
+        if base.kind != cur.kind {
+            self.push_bumped(
+                Impact::Breaking,
+                parent,
+                format!("kind changed from {} to {}", base.kind, cur.kind),
+                bumped,
+            );
+        }
+        self.description(parent, &base.meta.description, &cur.meta.description);
+    }
+}
+
+/// every value of `from` is also a value of `to`
+fn widens(
+    from: &Type,
+    to: &Type,
+) -> bool {
+    if from == to {
+        return true;
+    }
+    match (from, to) {
+        (Type::F32, Type::F64) => true,
+        (Type::CompoundType(from), Type::CompoundType(to)) => {
+            match (from, to) {
+                (CompoundType::Option { ty: from }, CompoundType::Option { ty: to })
+                | (CompoundType::Array { ty: from }, CompoundType::Array { ty: to })
+                | (CompoundType::SizedArray { ty: from, .. }, CompoundType::Array { ty: to }) => {
+                    widens(from, to)
+                },
+                (
+                    CompoundType::SizedArray { size: a, ty: from },
+                    CompoundType::SizedArray { size: b, ty: to },
+                ) => a == b && widens(from, to),
+                _ => false,
+            }
+        },
+        (from, to) => {
+            match (integer(from), integer(to)) {
+                (Some((from_signed, from_bits)), Some((to_signed, to_bits))) => {
+                    match (from_signed, to_signed) {
+                        (false, true) => to_bits > from_bits,
+                        (true, false) => false,
+                        _ => to_bits >= from_bits,
+                    }
+                },
+                _ => false,
+            }
+        },
+    }
+}
+
+/// the type of a field regardless of whether it is optional
+fn required(ty: &Type) -> &Type {
+    match ty {
+        Type::CompoundType(CompoundType::Option { ty }) => ty,
+        ty => ty,
+    }
+}
+
+/// signedness and width of integer types
+pub(crate) fn integer(ty: &Type) -> Option<(bool, usize)> {
+    Some(match ty {
+        Type::U8 => (false, 8),
+        Type::U16 => (false, 16),
+        Type::U32 => (false, 32),
+        Type::U64 | Type::Usize => (false, 64),
+        Type::I8 => (true, 8),
+        Type::I16 => (true, 16),
+        Type::I32 => (true, 32),
+        Type::I64 => (true, 64),
+        _ => return None,
+    })
+}
+
+fn value(value: &StrOrInt) -> String {
+    match value {
+        StrOrInt::Int(value) => value.to_string(),
+        StrOrInt::String(value) => format!("{value:?}"),
+    }
+}
+
+/// the way the values of members flow between clients and the server
+#[derive(Clone, Copy, PartialEq)]
+enum Flow {
+    /// from clients, as arguments of operations
+    Sent,
+    /// from the server, as results of operations
+    Returned,
+    /// either way, as fields of structs
+    Both,
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::Diff;
+    use crate::{context::Context, lower::Lowering};
+
+    fn ctx(source: &str) -> Context {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_source("test.pld", source)
+            .unwrap();
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish().unwrap())
+            .unwrap();
+        ctx.finish().unwrap();
+        ctx
+    }
+
+    #[test_case(
+        "struct Foo { a: i32, b: str };",
+        "struct Foo { a: i32 };",
+        "test\n  breaking   Foo.b: field removed (without a version bump)\n";
+        "field removed"
+    )]
+    #[test_case(
+        "struct Foo { a: i32 };",
+        "struct Foo { a: i32, b?: str, c: str };",
+        "test\n  compatible Foo.b: optional field added\n  breaking   Foo.c: required field added (without a version bump)\n";
+        "fields added"
+    )]
+    #[test_case(
+        "struct Foo { a?: i32, b: i32 };",
+        "struct Foo { a: i32, b?: i32 };",
+        "test\n  breaking   Foo.a: field is now required (without a version bump)\n  breaking   Foo.b: field may now be absent (without a version bump)\n";
+        "optionality"
+    )]
+    #[test_case(
+        "struct Foo { a: i32, b: u16, c: i64, d: f32[] };",
+        "struct Foo { a: i64, b: i32, c: i32, d: f64[] };",
+        "test\n  breaking   Foo.a: type changed from i32 to i64 (without a version bump)\n  breaking   Foo.b: type changed from u16 to i32 (without a version bump)\n  breaking   Foo.c: type changed from i64 to i32 (without a version bump)\n  breaking   Foo.d: type changed from [f32] to [f64] (without a version bump)\n";
+        "field types"
+    )]
+    #[test_case(
+        "struct Foo { a: i32, b: i32 };",
+        "// documented\nstruct Foo { b: i32, a: i32 };",
+        "test\n  cosmetic   Foo: fields reordered\n  cosmetic   Foo: description changed\n";
+        "cosmetic"
+    )]
+    #[test_case(
+        "enum E { A = 1, B = 2, C = 3 };",
+        "enum E { A = 1, B = 4, D = 5 };",
+        "test\n  breaking   E.B: variant renumbered from 2 to 4 (without a version bump)\n  breaking   E.C: variant removed (without a version bump)\n  compatible E.D: variant added\n";
+        "enum variants"
+    )]
+    #[test_case(
+        "oneof O { A(i32), B(str) };",
+        "oneof O { A(i64), C(str) };",
+        "test\n  breaking   O.A: type changed from i32 to i64 (without a version bump)\n  breaking   O.B: variant removed (without a version bump)\n  compatible O.C: variant added\n";
+        "oneof variants"
+    )]
+    #[test_case(
+        "struct Foo { a: i32 };\nstruct Bar { a: i32 };",
+        "struct Foo { a: i32 };\nstruct Baz { a: i32 };",
+        "test\n  breaking   Bar: struct removed (without a version bump)\n  compatible Baz: struct added\n";
+        "items"
+    )]
+    #[test_case(
+        "operation add(a: i32, b: i32) -> i32;",
+        "operation add(a: i64, b: i32, c: i32) -> i64;",
+        "test\n  compatible add.a: type widened from i32 to i64\n  breaking   add.c: required argument added (without a version bump)\n  breaking   add.value: type changed from i32 to i64 (without a version bump)\n";
+        "operation widened"
+    )]
+    #[test_case(
+        "operation add(a: i64) -> i64;",
+        "operation add(a: i32) -> i32;",
+        "test\n  breaking   add.a: type changed from i64 to i32 (without a version bump)\n  compatible add.value: type narrowed from i64 to i32\n";
+        "operation narrowed"
+    )]
+    #[test_case(
+        "operation add(a: i32, b?: i32) -> { c?: i32, d: i32 };",
+        "operation add(a?: i32, b: i32) -> { c: i32, d?: i32 };",
+        "test\n  compatible add.a: argument may now be absent\n  breaking   add.b: argument is now required (without a version bump)\n  compatible add.c: result is now required\n  breaking   add.d: result may now be absent (without a version bump)\n";
+        "operation optionality"
+    )]
+    #[test_case(
+        "#![error(E)]\nerror E { A(i32) };\noperation foo() -> i32;",
+        "#![error(E)]\nerror E { A(i32) };\noperation foo() -> i32!;",
+        "test\n  breaking   foo: error changed from none to E (without a version bump)\n";
+        "operation error"
+    )]
+    #[test_case(
+        "struct Foo { a: i32 };",
+        "#[version(2)]\nstruct Foo { a: str };",
+        "test\n  breaking   Foo.a: type changed from i32 to string\n";
+        "item version bump"
+    )]
+    #[test_case(
+        "#![version(1)]\nstruct Foo { a: i32 };",
+        "#![version(2)]\nstruct Foo { b: i32 };",
+        "test\n  breaking   Foo.a: field removed\n  breaking   Foo.b: required field added\n";
+        "namespace version bump"
+    )]
+    #[test_case(
+        "struct Foo { a: i32 };",
+        "#[version(1)]\nstruct Foo { a: i32 };\n#[version(2)]\nstruct Foo { a: str };",
+        "test\n  compatible Foo@2: struct added\n";
+        "version added"
+    )]
+    #[test_case(
+        "#[version(1)]\nstruct Foo { a: i32 };\n#[version(2)]\nstruct Foo { a: str };",
+        "#[version(2)]\nstruct Foo { a: str, b: i32 };",
+        "test\n  breaking   Foo@1: struct removed (without a version bump)\n  breaking   Foo@2.b: required field added (without a version bump)\n";
+        "version removed"
+    )]
+    #[test_case("struct Foo { a: i32 };", "struct Foo { a: i32 };", ""; "unchanged")]
+    fn test_diff(
+        base: &str,
+        cur: &str,
+        expect: &str,
+    ) {
+        let header = "namespace test;\n";
+        let diff = Diff::new(
+            &ctx(&format!("{header}{base}")),
+            &ctx(&format!("{header}{cur}")),
+        );
+        assert_eq!(diff.to_string(), expect);
+        assert_eq!(diff.report().is_ok(), !expect.contains("without"));
+    }
+
+    #[test_case("json")]
+    #[test_case("toml")]
+    fn test_export(ext: &str) {
+        let mut current = Context::new();
+        current
+            .load_package("../parser/samples/abc-corp")
+            .unwrap();
+        current.finish().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        current.export(dir.path(), ext).unwrap();
+
+        let mut baseline = Context::new();
+        baseline.load_path(dir.path()).unwrap();
+        baseline.finish().unwrap();
+        assert_eq!(Diff::new(&baseline, &current).to_string(), "");
+    }
+}
@@ -1,14 +1,22 @@
+mod common;
 pub mod context;
 pub mod files;
+pub mod go;
+pub mod graphql;
+pub mod json_schema;
 pub mod matcher;
+pub mod openapi;
+pub mod protobuf;
 pub mod python;
 pub mod remote;
 pub mod rust;
+pub mod typescript;
 
 use std::{
     collections::BTreeMap,
     fmt::Debug,
     path::{Path, PathBuf},
+    pin::Pin,
     sync::Arc,
 };
 
@@ -19,13 +27,21 @@ use validator::Validate;
 
 use crate::{
     Enum, ErrorTy, Ident, Named, OneOf, Operation, Result, Struct,
+    checks::{Findings, RuleRegistry},
     context::Context,
     default,
     generate::{
         context::WithNsContext,
         files::{MemFlush, WithFlush},
+        go::GoGenerator,
+        graphql::GraphqlGenerator,
+        json_schema::JsonSchemaGenerator,
+        openapi::OpenApiGenerator,
+        protobuf::ProtobufGenerator,
+        python::PythonGenerator,
         remote::RemoteConfig,
         rust::{RustGenState, RustGenerator},
+        typescript::TypescriptGenerator,
     },
 };
 
@@ -43,6 +59,15 @@ pub trait LanguageTrait {
 #[serde(rename_all = "snake_case")]
 pub enum Language {
     Rust,
+    #[serde(alias = "json-schema")]
+    JsonSchema,
+    #[serde(rename = "openapi")]
+    OpenApi,
+    Typescript,
+    Python,
+    Go,
+    Protobuf,
+    Graphql,
 }
 
 #[derive(Deserialize, PartialEq, Debug)]
@@ -172,11 +197,103 @@ pub struct JsonSchemaConfig {}
 
 impl ConfigExt for JsonSchemaConfig {}
 
+/// e.g.
+/// ```toml
+/// [openapi]
+/// title = "abc corp"
+/// servers = ["https://api.abc.corp"]
+///
+/// [openapi.prefixes]
+/// "abc.corp.test" = "/test"
+/// ```
+#[derive(Deserialize, PartialEq, Debug, Clone, Default, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct OpenApiConfig {
+    /// the title of every document, defaulting to the name of its namespace
+    #[serde(default)]
+    pub title: Option<String>,
+
+    #[serde(default)]
+    pub servers: Vec<String>,
+
+    /// the path operations of a namespace are placed under, defaulting to the namespace with
+    /// each segment as a path segment, e.g. `/abc/corp/test`
+    #[serde(default)]
+    pub prefixes: Named<String>,
+}
+
+impl ConfigExt for OpenApiConfig {}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Default)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "snake_case")]
+pub enum TypescriptDateTime {
+    /// rfc 3339 strings, as they are serialized
+    #[default]
+    String,
+    /// `Date`s, for clients which revive them when parsing
+    Date,
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Default, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct TypescriptConfig {
+    #[serde(default)]
+    pub datetime: TypescriptDateTime,
+}
+
+impl ConfigExt for TypescriptConfig {}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Default, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct PythonConfig {}
+
+impl ConfigExt for PythonConfig {}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Default, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct GoConfig {
+    /// the module path the package of every namespace is placed under, e.g.
+    /// `github.com/abc/corp/gen`. packages import each other by their name alone without one.
+    #[serde(default)]
+    pub import_path: Option<String>,
+}
+
+impl ConfigExt for GoConfig {}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Default, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct ProtobufConfig {}
+
+impl ConfigExt for ProtobufConfig {}
+
+/// e.g.
+/// ```toml
+/// [graphql.prefixes]
+/// "abc.corp.billing" = "Billing"
+/// ```
+#[derive(Deserialize, PartialEq, Debug, Clone, Default, Validate)]
+#[cfg_attr(test, derive(serde::Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub struct GraphqlConfig {
+    /// leads the name of every type and operation of a namespace, telling apart those which
+    /// collide with another namespace in the single schema of every namespace
+    #[serde(default)]
+    pub prefixes: Named<String>,
+}
+
+impl ConfigExt for GraphqlConfig {}
+
 crate::default!(
     Vec<Target>: { targets = vec![Target::Types] },
 );
 
-#[derive(Deserialize, PartialEq, Debug, Validate)]
+#[derive(Deserialize, PartialEq, Debug, Default, Validate)]
 #[cfg_attr(test, derive(serde::Serialize))]
 pub struct Source {
     #[serde(default)]
@@ -188,22 +305,86 @@ pub struct Source {
     exclude: Vec<String>,
 }
 
-#[derive(Deserialize, PartialEq, Debug, Validate)]
-#[cfg_attr(test, derive(serde::Serialize))]
-#[serde(rename_all = "kebab-case")]
-pub struct GenerationConfig {
-    #[serde(default = "default_targets")]
-    pub targets: Vec<Target>,
+/// registers every generator, along with its language, the config it is given and the field of
+/// [`GenerationConfig`] holding it
+macro_rules! generators {
+    ($($field: ident: $lang: ident => $generator: expr, $config: ty);+ $(;)?) => {
+        #[derive(Deserialize, PartialEq, Debug, Validate)]
+        #[cfg_attr(test, derive(serde::Serialize))]
+        #[serde(rename_all = "kebab-case")]
+        pub struct GenerationConfig {
+            #[serde(default = "default_targets")]
+            pub targets: Vec<Target>,
+
+            #[serde(default)]
+            pub languages: Vec<Language>,
+
+            #[validate(nested)]
+            pub sources: Source,
+
+            $(
+                #[serde(default)]
+                #[validate(nested)]
+                pub $field: Option<GenOpts<$config>>,
+            )+
+        }
 
-    #[serde(default)]
-    pub languages: Vec<Language>,
+        impl Default for GenerationConfig {
+            fn default() -> Self {
+                Self {
+                    targets: default_targets(),
+                    languages: vec![],
+                    sources: Default::default(),
+                    $($field: None,)+
+                }
+            }
+        }
 
-    #[validate(nested)]
-    pub sources: Source,
+        impl GenerationConfig {
+            pub fn set_mem(
+                &mut self,
+                mem: bool,
+            ) {
+                $(
+                    if let Some(opts) = &mut self.$field {
+                        opts.mem = mem;
+                    }
+                )+
+            }
+        }
 
-    #[serde(default)]
-    #[validate(nested)]
-    pub rust: Option<GenOpts<RustConfig>>,
+        impl Generation {
+            /// a generator for each language which is both listed and configured
+            fn generators(
+                &self,
+                mem_flush: Option<MemFlush>,
+            ) -> Vec<Generator<'_>> {
+                let mut generators: Vec<Generator<'_>> = vec![];
+                $(
+                    if self.config.languages.contains(&Language::$lang)
+                        && let Some(opts) = &self.config.$field
+                    {
+                        let mem_flush = mem_flush.clone();
+                        generators.push(Box::new(move || {
+                            $generator.gen_ty_ctx(&self.ctx, opts, mem_flush.clone(), &self.config)
+                        }));
+                    }
+                )+
+                generators
+            }
+        }
+    };
+}
+
+generators! {
+    rust: Rust => RustGenerator, RustConfig;
+    json_schema: JsonSchema => JsonSchemaGenerator, JsonSchemaConfig;
+    openapi: OpenApi => OpenApiGenerator, OpenApiConfig;
+    typescript: Typescript => TypescriptGenerator, TypescriptConfig;
+    python: Python => PythonGenerator, PythonConfig;
+    go: Go => GoGenerator, GoConfig;
+    protobuf: Protobuf => ProtobufGenerator, ProtobufConfig;
+    graphql: Graphql => GraphqlGenerator, GraphqlConfig;
 }
 
 impl NewForConfig for GenerationConfig {
@@ -253,7 +434,8 @@ where
                 mem_flush.clone(),
             );
 
-            if root.targets.contains(&Target::Types) {
+            // the server refers to the declarations of its namespace, so it implies them
+            if root.targets.contains(&Target::Types) || root.targets.contains(&Target::Server) {
                 for enm in ns.enums.values() {
                     self.gen_enum(&ns_ctx, enm)?;
                 }
@@ -275,6 +457,10 @@ where
                 }
             }
 
+            if root.targets.contains(&Target::Server) {
+                self.gen_server(&ns_ctx)?;
+            }
+
             ctx_ns.insert(ns.name.clone(), ns_ctx);
         }
 
@@ -295,6 +481,15 @@ where
         def: &Operation,
     ) -> Result<()>;
 
+    /// the server of the operations of a namespace, for languages which generate one
+    #[allow(unused)]
+    fn gen_server(
+        &self,
+        state: &WithNsContext<'_, State, Ext, Self>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     fn gen_enum(
         &self,
         state: &WithNsContext<'_, State, Ext, Self>,
@@ -322,25 +517,18 @@ impl GenerationConfig {
     pub fn get_ctx(&self) -> crate::Result<Context> {
         let mut ctx = Context::new();
 
-        for source in self.sources()? {
-            ctx.load_from_source(source)?;
-        }
+        ctx.load_from_sources(self.sources()?)?;
 
         ctx.finish()?;
 
         Ok(ctx)
     }
-
-    pub fn set_mem(
-        &mut self,
-        mem: bool,
-    ) {
-        if let Some(rs) = &mut self.rust {
-            rs.mem = mem;
-        }
-    }
 }
 
+type Generator<'a> = Box<dyn Fn() -> Result<()> + Send + Sync + 'a>;
+
+type GeneratorFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
 pub struct Generation {
     pub config: GenerationConfig,
     pub ctx: Context,
@@ -352,32 +540,20 @@ impl Generation {
         Ok(Self { config, ctx })
     }
 
-    fn wants_rust(&self) -> Option<&GenOpts<RustConfig>> {
-        if self
-            .config
-            .languages
-            .contains(&Language::Rust)
-            && let Some(rust) = &self.config.rust
-        {
-            Some(rust)
-        } else {
-            None
-        }
+    /// runs every registered rule over the loaded context
+    pub fn check(
+        &self,
+        rules: &RuleRegistry,
+    ) -> Findings {
+        rules.run(&self.ctx)
     }
 
     pub fn generate_all_sync(
         &self,
         mem_flush: Option<MemFlush>,
     ) -> crate::Result<()> {
-        let mut generators = vec![];
-
-        if let Some(rust) = self.wants_rust() {
-            generators.push(Box::new(|| {
-                RustGenerator.gen_ty_ctx(&self.ctx, rust, mem_flush.clone(), &self.config)
-            }));
-        }
-
-        for it in generators
+        for it in self
+            .generators(mem_flush)
             .into_par_iter()
             .map(|handle| (*handle)())
             .collect::<Vec<_>>()
@@ -392,12 +568,11 @@ impl Generation {
         &self,
         mem_flush: Option<MemFlush>,
     ) -> crate::Result<()> {
-        let mut futs = vec![];
-        if let Some(rust) = self.wants_rust() {
-            futs.push(Box::pin(async move {
-                RustGenerator.gen_ty_ctx(&self.ctx, rust, mem_flush.clone(), &self.config)
-            }));
-        }
+        let futs: Vec<GeneratorFuture<'_>> = self
+            .generators(mem_flush)
+            .into_iter()
+            .map(|handle| Box::pin(async move { (*handle)() }) as GeneratorFuture<'_>)
+            .collect();
 
         for fut in futs {
             fut.await?;
@@ -409,7 +584,9 @@ impl Generation {
 
 #[cfg(test)]
 mod test {
-    use crate::{Definitions, generate::files::MemCollector};
+    use test_case::test_case;
+
+    use crate::{Definitions, generate::files::MemCollector, lower::Lowering};
 
     use super::*;
 
@@ -429,6 +606,464 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_gen_mem_schema() -> crate::Result<()> {
+        let conf = GenerationConfig {
+            targets: vec![Target::Types],
+            languages: vec![Language::Rust],
+            sources: Source {
+                remote: vec![],
+                include: vec![
+                    "../parser/samples/error.pld",
+                    "../parser/samples/message_with_enum.pld",
+                    "../parser/samples/op.pld",
+                ]
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+                exclude: vec![],
+            },
+            rust: Some(GenOpts {
+                output_dir: "gen".into(),
+                opts: RustConfig {
+                    vis: Default::default(),
+                    time: DateTimeLibrary::Chrono,
+                },
+                mem: true,
+            }),
+            ..Default::default()
+        };
+
+        let collector = MemCollector::new();
+
+        let generate = Generation::new(conf)?;
+        generate.generate_all_sync(Some(collector.mem_flush()))?;
+
+        assert!(!collector.files().is_empty());
+
+        Ok(())
+    }
+
+    /// the namespace `b`, generated along with each source so that it may refer to `b::B`
+    const NAMESPACE_B: &str = "namespace b;\nstruct B { #[tag(1)] a: i32 };";
+
+    fn opts<Ext: ConfigExt>(opts: Ext) -> Option<GenOpts<Ext>> {
+        Some(GenOpts {
+            output_dir: "gen".into(),
+            opts,
+            mem: true,
+        })
+    }
+
+    /// a config generating `language` into `gen`
+    fn config(language: Language) -> GenerationConfig {
+        GenerationConfig {
+            languages: vec![language],
+            rust: opts(RustConfig {
+                vis: Default::default(),
+                time: DateTimeLibrary::Chrono,
+            }),
+            json_schema: opts(JsonSchemaConfig {}),
+            openapi: opts(OpenApiConfig {
+                title: Some("a api".into()),
+                servers: vec!["https://api.a.corp".into()],
+                prefixes: Named::new([(Ident::new("a"), "/api/a/".to_string())]),
+            }),
+            typescript: opts(TypescriptConfig::default()),
+            python: opts(PythonConfig {}),
+            go: opts(GoConfig {
+                import_path: Some("example.com/gen".into()),
+            }),
+            protobuf: opts(ProtobufConfig {}),
+            graphql: opts(GraphqlConfig::default()),
+            ..Default::default()
+        }
+    }
+
+    /// generates `config` from schema files holding each of `sources`, by the path of each file
+    /// generated
+    fn generate(
+        config: GenerationConfig,
+        sources: &[&str],
+    ) -> crate::Result<BTreeMap<PathBuf, String>> {
+        let mut lowering = Lowering::new();
+        for (i, source) in sources.iter().enumerate() {
+            lowering.add_source(format!("{i}.pld"), *source)?;
+        }
+
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish()?)?;
+        ctx.finish()?;
+
+        let collector = MemCollector::new();
+        Generation { config, ctx }.generate_all_sync(Some(collector.mem_flush()))?;
+
+        Ok(collector
+            .files()
+            .iter()
+            .map(|(path, it)| (path.clone(), String::from_utf8(it.clone()).unwrap()))
+            .collect())
+    }
+
+    /// snapshots every file generated for `language` from `source` and the namespace `b`, rust
+    /// being pretty printed, and returns them
+    fn snapshot(
+        name: &str,
+        language: Language,
+        source: &str,
+    ) -> String {
+        let name = format!("{name}_{}", format!("{language:?}").to_lowercase());
+        let files = generate(config(language), &[source, NAMESPACE_B]).unwrap();
+        let files = files
+            .iter()
+            .map(|(path, it)| {
+                let it = match path.extension().is_some_and(|it| it == "rs") {
+                    true => prettyplease::unparse(&syn::parse_file(it).unwrap()),
+                    false => it.clone(),
+                };
+                format!("==> {}\n{it}", path.display())
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        operation_api_testing::insta_test!(|| {
+            operation_api_testing::assert_snapshot!(name, files);
+        });
+        files
+    }
+
+    /// `files` holds `expect`, however either is indented or wrapped
+    fn assert_holds(
+        files: &str,
+        expect: &str,
+    ) {
+        let squash = |it: &str| {
+            it.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert!(squash(files).contains(&squash(expect)), "{files}");
+    }
+
+    #[test_case(Language::Rust, r#"pub parent: Option<Box<Holder>>,"#; "rust")]
+    #[test_case(Language::JsonSchema, r#""required": [ "a", "all", "pair", "raw", "at", "from", "children" ]"#; "json schema")]
+    #[test_case(Language::OpenApi, r##""parent": { "anyOf": [ { "$ref": "#/components/schemas/Holder" }, { "type": "null" } ] }"##; "openapi")]
+    #[test_case(Language::Typescript, r#"parent?: Holder | null;"#; "typescript")]
+    #[test_case(Language::Python, r#"from_: str = Field(alias="from")"#; "python")]
+    #[test_case(Language::Go, r#"Parent *Holder `json:"parent,omitempty"`"#; "go")]
+    #[test_case(Language::Protobuf, r#"optional Holder parent = 8;"#; "protobuf")]
+    #[test_case(Language::Graphql, r#"children: [HolderInput!]!"#; "graphql")]
+    fn test_gen_structs(
+        language: Language,
+        expect: &str,
+    ) {
+        let files = snapshot(
+            "structs",
+            language,
+            "namespace a;\n// a holder\nstruct Holder {\n#[tag(1)] a: i32,\n#[tag(2)] b?: str,\n#[tag(3)] all: u8[],\n#[tag(4)] pair: i32[2],\n#[tag(5)] raw: binary,\n#[tag(6)] at: datetime,\n#[tag(7)] from: str,\n#[tag(8)] parent?: Holder,\n#[tag(9)] children: Holder[]\n};",
+        );
+        assert_holds(&files, expect);
+    }
+
+    #[test_case(Language::Rust, r#"#[repr(u64)] pub enum Level { Low = 1, High = 2, }"#; "rust")]
+    #[test_case(Language::JsonSchema, r#""Color": { "enum": [ "r", "g" ], "type": "string" }"#; "json schema")]
+    #[test_case(Language::OpenApi, r#""Level": { "enum": [ 1, 2 ], "type": "integer" }"#; "openapi")]
+    #[test_case(Language::Typescript, r#"export type Color = "r" | "g";"#; "typescript")]
+    #[test_case(Language::Python, r#"class Level(IntEnum): LOW = 1 HIGH = 2"#; "python")]
+    #[test_case(Language::Go, r#"ColorRed Color = "r""#; "go")]
+    #[test_case(Language::Protobuf, r#"COLOR_UNSPECIFIED = 0; COLOR_RED = 1; COLOR_GREEN = 3;"#; "protobuf")]
+    #[test_case(Language::Graphql, r#"enum Level { Low High }"#; "graphql")]
+    fn test_gen_enums(
+        language: Language,
+        expect: &str,
+    ) {
+        let files = snapshot(
+            "enums",
+            language,
+            "namespace a;\nenum Level { Low = 1, High = 2 };\nenum Color { #[tag(1)] Red = \"r\", #[tag(3)] Green = \"g\" };\nstruct Paint { #[tag(1)] level: Level, #[tag(2)] color?: Color };",
+        );
+        assert_holds(&files, expect);
+    }
+
+    #[test_case(Language::Rust, r#"#[serde(untagged)] #[fields(version = 1)] pub enum Node {"#; "rust")]
+    #[test_case(Language::JsonSchema, r##"{ "$ref": "#/$defs/NodePair" } ] }"##; "json schema")]
+    #[test_case(Language::OpenApi, r##"{ "$ref": "#/components/schemas/NodePair" } ] }"##; "openapi")]
+    #[test_case(Language::Typescript, r#"export type Node = | number | Tree | number[] | NodePair;"#; "typescript")]
+    #[test_case(Language::Python, r#"Node = Union[int, "Tree", list[int], "NodePair"]"#; "python")]
+    #[test_case(Language::Go, r#"if err := untagged(data, &value); err == nil { it.Value = NodeLeaf{value}"#; "go")]
+    #[test_case(Language::Protobuf, r#"oneof value { int32 leaf = 1;"#; "protobuf")]
+    #[test_case(Language::Graphql, r#"union Node = NodeLeaf | Tree | NodeMany | NodePair"#; "graphql")]
+    fn test_gen_oneofs(
+        language: Language,
+        expect: &str,
+    ) {
+        let files = snapshot(
+            "oneofs",
+            language,
+            "namespace a;\noneof Node { #[tag(1)] leaf(i32), #[tag(2)] tree(Tree), #[tag(4)] many(i32[]), #[tag(5)] pair { #[tag(1)] a: i32, #[tag(2)] b: i32 } };\nstruct Tree { #[tag(1)] node: Node, #[tag(2)] children: Tree[] };",
+        );
+        assert_holds(&files, expect);
+    }
+
+    #[test_case(Language::Rust, r#"#[serde(tag = "type", rename_all = "snake_case")] pub enum Failure {"#; "rust")]
+    #[test_case(Language::JsonSchema, r#""type": { "const": "too_large" }"#; "json schema")]
+    #[test_case(Language::OpenApi, r#""type": { "const": "too_large" }"#; "openapi")]
+    #[test_case(Language::Typescript, r#"| ({ type: "too_large" } & FailureTooLarge);"#; "typescript")]
+    #[test_case(Language::Python, r#"class FailureTooLarge(BaseModel): type: Literal["too_large"] = "too_large""#; "python")]
+    #[test_case(Language::Go, r#"case FailureTooLarge: return "too_large", variant"#; "go")]
+    #[test_case(Language::Protobuf, r#"FailureTooLarge too_large = 1;"#; "protobuf")]
+    #[test_case(Language::Graphql, r#"union Failure = Missing | FailureTooLarge"#; "graphql")]
+    fn test_gen_errors(
+        language: Language,
+        expect: &str,
+    ) {
+        let files = snapshot(
+            "errors",
+            language,
+            "namespace a;\nstruct Missing { #[tag(1)] desc: str };\nerror Failure { #[tag(2)] NotFound(Missing), #[tag(1)] TooLarge { #[tag(1)] limit: i32 } };\n#![error(Failure)]\n// adds two numbers\noperation add(#[tag(1)] a: i32, #[tag(2)] b?: i32) -> i32!;",
+        );
+        assert_holds(&files, expect);
+    }
+
+    #[test_case(Language::Rust, r#"pub legacy: v1::Known,"#; "rust")]
+    #[test_case(Language::JsonSchema, r##""legacy": { "$ref": "#/$defs/Known@1" }"##; "json schema")]
+    #[test_case(Language::OpenApi, r##""legacy": { "$ref": "#/components/schemas/Known@1" }"##; "openapi")]
+    #[test_case(Language::Typescript, r#"export type Known = KnownV2;"#; "typescript")]
+    #[test_case(Language::Python, r#"Known = KnownV2"#; "python")]
+    #[test_case(Language::Go, r#"type Known = KnownV2"#; "go")]
+    #[test_case(Language::Protobuf, r#"KnownV1 legacy = 3;"#; "protobuf")]
+    #[test_case(Language::Graphql, r#"legacy: KnownV1!"#; "graphql")]
+    fn test_gen_versions(
+        language: Language,
+        expect: &str,
+    ) {
+        let files = snapshot(
+            "versions",
+            language,
+            "namespace a;\n#[version(1)]\nstruct Known { #[tag(1)] desc: str };\n#[version(2)]\nstruct Known { #[tag(1)] desc: str, #[tag(2)] code: i32, #[tag(3)] cause?: Known };\nstruct Uses { #[tag(1)] known: Known, #[tag(2)] all: Known[], #[tag(3)] legacy: Known@1 };",
+        );
+        assert_holds(&files, expect);
+    }
+
+    #[test_case(Language::Rust, r#"pub b: super::b::B,"#; "rust")]
+    #[test_case(Language::JsonSchema, r##""b": { "$ref": "b.json#/$defs/B" }"##; "json schema")]
+    #[test_case(Language::OpenApi, r##""b": { "$ref": "b.openapi.json#/components/schemas/B" }"##; "openapi")]
+    #[test_case(Language::Typescript, r#"b: b.B;"#; "typescript")]
+    #[test_case(Language::Python, r#"b: b.B"#; "python")]
+    #[test_case(Language::Go, r#"B b.B `json:"b"`"#; "go")]
+    #[test_case(Language::Protobuf, r#".b.B b = 1;"#; "protobuf")]
+    #[test_case(Language::Graphql, r#"b: BInput!"#; "graphql")]
+    fn test_gen_cross_namespace(
+        language: Language,
+        expect: &str,
+    ) {
+        let files = snapshot(
+            "cross_namespace",
+            language,
+            "namespace a;\nuse b;\nstruct A { #[tag(1)] b: b::B, #[tag(2)] c: C };\nstruct C { #[tag(1)] a: i32 };",
+        );
+        assert_holds(&files, expect);
+    }
+
+    #[test]
+    fn test_gen_server() -> crate::Result<()> {
+        let files = generate(
+            GenerationConfig {
+                // the server implies the declarations it refers to
+                targets: vec![Target::Server],
+                ..config(Language::Rust)
+            },
+            &[
+                "namespace ops;\nerror Failure { NotFound { desc: str } };\n#![error(Failure)]\n// adds two numbers\noperation add(a: i32, b?: i32) -> i32!;\noperation find(name: str) -> { found: i32[] };\n#[version(1)]\noperation ping() -> bool;\n#[version(2)]\noperation ping(retries: u8) -> bool;",
+            ],
+        )?;
+
+        let a = &files[&PathBuf::from("gen/ops.rs")];
+        assert!(a.contains("pub enum Failure"), "{a}");
+        assert!(a.contains("pub struct AddRequest"), "{a}");
+        assert!(a.contains("pub trait OpsServer : Send + Sync"), "{a}");
+        assert!(
+            a.contains("fn add (& self , a : i32 , b : Option < i32 > ,) -> impl std :: future :: Future < Output = Result < i32 , Failure > > + Send ;"),
+            "{a}"
+        );
+        assert!(
+            a.contains("fn find (& self , name : String ,) -> impl std :: future :: Future < Output = FindResponse > + Send ;"),
+            "{a}"
+        );
+        assert!(a.contains("fn ping_v1 (& self ,)"), "{a}");
+        assert!(a.contains("pub struct PingV2Request"), "{a}");
+        assert!(a.contains("\"ping@1\" =>"), "{a}");
+        assert!(a.contains("\"ping@2\" | \"ping\" =>"), "{a}");
+        assert!(
+            a.contains("let response = AddResponse { value : server . add (a , b) . await . map_err (| error | operation_api_sdk :: DispatchError :: failed (\"add\" , error)) ? } ;"),
+            "{a}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_mem_conversions() -> crate::Result<()> {
+        let conf = GenerationConfig {
+            targets: vec![Target::Types],
+            languages: vec![Language::Rust],
+            sources: Source {
+                remote: vec![],
+                include: vec!["../parser/samples/versions.pld".into()],
+                exclude: vec![],
+            },
+            rust: Some(GenOpts {
+                output_dir: "gen".into(),
+                opts: RustConfig {
+                    vis: Default::default(),
+                    time: DateTimeLibrary::Chrono,
+                },
+                mem: true,
+            }),
+            ..Default::default()
+        };
+
+        let collector = MemCollector::new();
+
+        let generate = Generation::new(conf)?;
+        generate.generate_all_sync(Some(collector.mem_flush()))?;
+
+        let files = collector.files();
+        let versions = String::from_utf8(files[&PathBuf::from("gen/versions.rs")].clone()).unwrap();
+        assert!(
+            versions.contains("impl From < v1 :: KnownError > for v2 :: KnownError"),
+            "{versions}"
+        );
+        assert!(
+            versions.contains(
+                "Self { description : value . desc , code : value . code . into () , cause : None , }"
+            ),
+            "{versions}"
+        );
+        assert!(
+            versions.contains("impl TryFrom < v1 :: Limit > for v2 :: Limit"),
+            "{versions}"
+        );
+        assert!(
+            versions.contains("max : value . max . ok_or (operation_api_sdk :: ConversionError :: new (\"versions.Limit@1\" , \"versions.Limit@2\" , \"field `max` is absent\")) ?"),
+            "{versions}"
+        );
+        assert!(
+            versions.contains("\"required field `step` has no counterpart in versions.Limit@2\""),
+            "{versions}"
+        );
+        assert!(
+            versions.contains("impl From < v1 :: Level > for v2 :: Level"),
+            "{versions}"
+        );
+        assert!(
+            versions.contains("v2 :: Level :: Medium => return Err"),
+            "{versions}"
+        );
+
+        Ok(())
+    }
+
+    /// a struct declared on its own is no inline variant, though it is named as one
+    #[test_case(Language::Python, "gen/a.py", "class FailureTooLargeVariant(_Flattened):", "class FailureTooLarge(BaseModel):\n    limit: int\n\n"; "python")]
+    #[test_case(Language::Go, "gen/a/a.go", "type FailureTooLargeVariant struct {\n\tValue FailureTooLarge\n}", "type FailureTooLarge struct {\n\tLimit int32 `json:\"limit\"`\n}"; "go")]
+    fn test_gen_inline_named(
+        language: Language,
+        path: &str,
+        variant: &str,
+        def: &str,
+    ) -> crate::Result<()> {
+        let files = generate(
+            config(language),
+            &[
+                "namespace a;\nstruct FailureTooLarge { limit: i32 };\nerror Failure { TooLarge(FailureTooLarge) };",
+            ],
+        )?;
+
+        let a = &files[&PathBuf::from(path)];
+        assert!(a.contains(variant), "{a}");
+        assert!(a.contains(def), "{a}");
+
+        Ok(())
+    }
+
+    #[test_case(TypescriptDateTime::String, "at: string;"; "datetime as string")]
+    #[test_case(TypescriptDateTime::Date, "at: Date;"; "datetime as date")]
+    fn test_gen_typescript_datetime(
+        datetime: TypescriptDateTime,
+        at: &str,
+    ) -> crate::Result<()> {
+        let files = generate(
+            GenerationConfig {
+                typescript: opts(TypescriptConfig { datetime }),
+                ..config(Language::Typescript)
+            },
+            &["namespace a;\nstruct A { at: datetime };"],
+        )?;
+
+        let a = &files[&PathBuf::from("gen/a.ts")];
+        assert!(a.contains(at), "{a}");
+
+        Ok(())
+    }
+
+    #[test_case("struct A { #[tag(1)] a: i32, b: i32, c: i32 };", "field b of A declares no #[tag(..)]"; "missing tag")]
+    #[test_case("struct A { #[tag(19000)] a: i32 };", "field a of A can not be numbered 19000"; "reserved tag")]
+    #[test_case("struct X { #[tag(1)] a: i32 };\nstruct Y { #[tag(1)] b: i32 };\ntype A = X & Y;", "field b of A can not be numbered 1: it is already the number of a"; "duplicate tag")]
+    #[test_case("operation add(a: i32) -> i32;", "field a of AddRequest declares no #[tag(..)]"; "missing input tag")]
+    #[test_case("oneof A { #[tag(1)] a(i32), b(str) };", "variant b of A declares no #[tag(..)]"; "missing variant tag")]
+    #[test_case("struct B { #[tag(1)] a: i32 };\nerror A { NotFound(B) };", "variant NotFound of A declares no #[tag(..)]"; "missing error variant tag")]
+    #[test_case("oneof A { #[tag(19999)] a(i32) };", "variant a of A can not be numbered 19999"; "reserved variant tag")]
+    #[test_case("enum A { Red = \"r\", #[tag(2)] Green = \"g\" };", "variant Red of A declares no #[tag(..)]"; "missing enum tag")]
+    fn test_gen_protobuf_tags(
+        src: &str,
+        expect: &str,
+    ) {
+        let Err(err) = generate(
+            config(Language::Protobuf),
+            &[&format!("namespace a;\n{src}")],
+        ) else {
+            panic!("expected an error")
+        };
+        assert!(err.to_string().contains(expect), "{err}");
+    }
+
+    #[test]
+    fn test_gen_graphql_prefixes() -> crate::Result<()> {
+        let sources = [
+            "namespace a;\nuse b;\nstruct B { a: i32 };\n#[kind(query)]\noperation get() -> B;",
+            "namespace b;\nstruct B { a: i32 };\n#[kind(query)]\noperation get() -> B;",
+        ];
+        let Err(err) = generate(config(Language::Graphql), &sources) else {
+            panic!("expected a collision")
+        };
+        assert_eq!(
+            err.to_string(),
+            "type B is generated for both a and b\ntype BInput is generated for both a and b\nQuery get is generated for both a and b"
+        );
+
+        let files = generate(
+            GenerationConfig {
+                graphql: opts(GraphqlConfig {
+                    prefixes: Named::new([(Ident::new("b"), "Remote".to_string())]),
+                }),
+                ..config(Language::Graphql)
+            },
+            &sources,
+        )?;
+
+        let schema = &files[&PathBuf::from("gen/schema.graphql")];
+        assert!(
+            schema.contains("type RemoteB {\n  a: Int!\n}\n"),
+            "{schema}"
+        );
+        assert!(
+            schema.contains("type Query {\n  get: B!\n  remoteGet: RemoteB!\n}\n"),
+            "{schema}"
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_config_loader() {
         let mut conf = GenerationConfig::new(Some("../samples/config-a")).unwrap();
@@ -473,6 +1108,7 @@ mod test {
                 },
                 mem: false,
             }),
+            ..Default::default()
         };
 
         assert_eq! {
@@ -487,6 +1123,7 @@ mod test {
             "../samples/test-one-of.toml",
             "../samples/test-operation-error.toml",
             "../samples/test-str-enum.toml",
+            "../samples/test-struct-cross-namespace.toml",
             "../samples/test-struct-known-error.toml",
             "../samples/test-struct-operation-error-unknown.toml",
             "../samples/test-struct-readme.toml",
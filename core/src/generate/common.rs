@@ -0,0 +1,164 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use convert_case::Casing;
+
+use crate::{
+    CompoundType, Ident, Key, OneOfVariant, Struct, Type,
+    generate::LanguageTrait,
+    namespace::{Declarations, Namespace},
+};
+
+/// source gathered across the declarations of a namespace, written once every declaration is
+/// generated
+pub(crate) trait Module: Default {
+    /// what the declarations written into the module refer to, e.g. other namespaces
+    type Refs<'ns>;
+
+    fn merge(
+        &mut self,
+        refs: Self::Refs<'_>,
+        written: Self,
+    );
+}
+
+/// the module of every namespace
+pub(crate) struct Modules<M>(Mutex<BTreeMap<Ident, M>>);
+
+impl<M> Default for Modules<M> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<M: Default> Modules<M> {
+    /// writes into the module of `ns`
+    pub(crate) fn with<R>(
+        &self,
+        ns: &Ident,
+        handle: impl FnOnce(&mut M) -> R,
+    ) -> R {
+        handle(
+            self.0
+                .lock()
+                .unwrap()
+                .entry(ns.clone())
+                .or_default(),
+        )
+    }
+
+    /// the module of `ns`, once every declaration is generated
+    pub(crate) fn take(
+        &self,
+        ns: &Ident,
+    ) -> M {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(ns)
+            .unwrap_or_default()
+    }
+}
+
+impl<M: Module> Modules<M> {
+    /// writes declarations of `ns` through `handle`, merging them into its module along with
+    /// what they refer to
+    pub(crate) fn write<'ns, R>(
+        &self,
+        ns: &Ident,
+        mut refs: M::Refs<'ns>,
+        handle: impl FnOnce(&mut M::Refs<'ns>, &mut M) -> R,
+    ) -> R {
+        let mut written = M::default();
+        let out = handle(&mut refs, &mut written);
+        self.with(ns, |module| module.merge(refs, written));
+        out
+    }
+}
+
+pub(crate) fn pascal(name: &str) -> String {
+    name.to_case(convert_case::Case::Pascal)
+}
+
+/// declarations held in several versions are named by their version, e.g. `KnownErrorV2`
+pub(crate) fn versioned_name(key: &Key) -> String {
+    format!("{}V{}", pascal(&key.name.to_string()), key.version)
+}
+
+pub(crate) fn name(
+    key: &Key,
+    versioned: bool,
+) -> String {
+    match versioned {
+        true => versioned_name(key),
+        false => pascal(&key.name.to_string()),
+    }
+}
+
+/// declarations held in several versions are named by their version, e.g. `KnownError@2`
+pub(crate) fn def_name(
+    key: &Key,
+    versioned: bool,
+) -> String {
+    match versioned {
+        true => key.to_string(),
+        false => key.name.to_string(),
+    }
+}
+
+/// the latest key of every declaration held in several versions
+pub(crate) fn latest<T>(decls: &BTreeMap<Key, T>) -> impl Iterator<Item = &Key> {
+    decls.keys().filter(|key| {
+        decls.is_versioned(&key.name)
+            && decls
+                .versions(&key.name)
+                .last()
+                .is_some_and(|(it, _)| it == *key)
+    })
+}
+
+/// a quoted string literal, escaped as json
+pub(crate) fn literal(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/// the tag of an error variant, matching serde's `rename_all = "snake_case"` of its rust variant
+pub(crate) fn tag(name: &Ident) -> String {
+    let pascal = pascal(&name.to_string());
+    let mut tag = String::new();
+    for (i, ch) in pascal.char_indices() {
+        if i > 0 && ch.is_uppercase() {
+            tag.push('_');
+        }
+        tag.push(ch.to_ascii_lowercase());
+    }
+    tag
+}
+
+/// the struct `variant` declares its fields inline as, e.g. `FailureTooLarge` of
+/// `TooLarge { limit: i32 }` of `Failure`
+pub(crate) fn inline(variant: &OneOfVariant) -> Option<&Ident> {
+    match &variant.ty {
+        Type::CompoundType(CompoundType::Struct { to }) if variant.inline => Some(to),
+        _ => None,
+    }
+}
+
+/// the tag of the error variant declaring its fields inline as `def`
+pub(crate) fn inline_variant(
+    ns: &Namespace,
+    def: &Struct,
+) -> Option<String> {
+    ns.errors.values().find_map(|err| {
+        err.variants
+            .iter()
+            .find(|(_, var)| inline(var) == Some(&def.meta.name))
+            .map(|(ident, _)| tag(ident))
+    })
+}
+
+/// the name of the file generated for a namespace, relative to the output directory
+pub(crate) fn file_name<L: LanguageTrait>(ns: &str) -> String {
+    L::file_name(crate::namespace::normalized_path::<L>(ns))
+        .display()
+        .to_string()
+}
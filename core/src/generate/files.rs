@@ -138,7 +138,7 @@ mod test {
 
         let txt = t.path().join("temp.txt");
         let mut file = super::FileOrMem::new(&txt, false)?;
-        file.write("test".as_bytes())?;
+        file.write_all("test".as_bytes())?;
         file.flush()?;
 
         let data = std::fs::read_to_string(txt)?;
@@ -154,7 +154,7 @@ mod test {
         let mut file = super::FileOrMem::new(&p, true)?;
 
         file.with_flush(collector.mem_flush());
-        file.write("test".as_bytes())?;
+        file.write_all("test".as_bytes())?;
         file.flush()?;
 
         let state = collector.files();
@@ -0,0 +1,786 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    io::Write,
+    path::Path,
+};
+
+use crate::{
+    CompoundType, Enum, ErrorTy, FieldsList, Ident, Key, OneOf, OneOfVariant, Operation, StrOrInt,
+    Struct, Type,
+    generate::{
+        GenOpts, Generate, GoConfig, LanguageTrait,
+        common::{Module, Modules, inline, latest, literal, name, pascal, tag, versioned_name},
+        context::WithNsContext,
+        files::WithFlush,
+    },
+    namespace::{Declarations, Namespace},
+};
+
+pub struct GoGenerator;
+
+impl LanguageTrait for GoGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Flat
+    }
+
+    fn file_ext() -> &'static str {
+        "go"
+    }
+}
+
+/// helpers written once into each package which needs them
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Helper {
+    Binary,
+    Tagged,
+    Untagged,
+}
+
+impl Helper {
+    fn source(self) -> &'static str {
+        match self {
+            Self::Binary => {
+                r#"// Binary is held as an array of octets, rather than as base64
+type Binary []byte
+
+func (it Binary) MarshalJSON() ([]byte, error) {
+	octets := make([]uint16, len(it))
+	for i, octet := range it {
+		octets[i] = uint16(octet)
+	}
+	return json.Marshal(octets)
+}
+
+func (it *Binary) UnmarshalJSON(data []byte) error {
+	var octets []uint8
+	if err := json.Unmarshal(data, &octets); err != nil {
+		return err
+	}
+	*it = Binary(octets)
+	return nil
+}
+"#
+            },
+            Self::Tagged => {
+                r#"// tagged writes value as an object, tagged by its variant in "type"
+func tagged(tag string, value any) ([]byte, error) {
+	data, err := json.Marshal(value)
+	if err != nil {
+		return nil, err
+	}
+	var fields map[string]json.RawMessage
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return nil, err
+	}
+	fields["type"], _ = json.Marshal(tag)
+	return json.Marshal(fields)
+}
+"#
+            },
+            Self::Untagged => {
+                r#"// untagged reads data into value, failing on unknown fields and on missing fields which are
+// not optional, so that only the variant which matches is held
+func untagged(data []byte, value any) error {
+	decoder := json.NewDecoder(bytes.NewReader(data))
+	decoder.DisallowUnknownFields()
+	if err := decoder.Decode(value); err != nil {
+		return err
+	}
+	ty := reflect.TypeOf(value).Elem()
+	if ty.Kind() != reflect.Struct {
+		return nil
+	}
+	var fields map[string]json.RawMessage
+	if err := json.Unmarshal(data, &fields); err != nil {
+		return err
+	}
+	for i := 0; i < ty.NumField(); i++ {
+		name, options, _ := strings.Cut(ty.Field(i).Tag.Get("json"), ",")
+		if _, ok := fields[name]; !ok && name != "" && options != "omitempty" {
+			return fmt.Errorf("missing field %q", name)
+		}
+	}
+	return nil
+}
+"#
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct Package {
+    /// packages of the standard library referred to
+    std: BTreeSet<&'static str>,
+    /// names of the generated packages referred to
+    imports: BTreeSet<String>,
+    helpers: BTreeSet<Helper>,
+    body: String,
+}
+
+impl Module for Package {
+    type Refs<'ns> = Types<'ns>;
+
+    fn merge(
+        &mut self,
+        refs: Types<'_>,
+        written: Self,
+    ) {
+        self.std.extend(refs.std);
+        self.imports.extend(refs.imports);
+        self.helpers.extend(refs.helpers);
+        self.body.push_str(&written.body);
+    }
+}
+
+pub(crate) struct GoGenState {
+    /// the package of every namespace, written once every declaration is generated so that
+    /// imports lead it
+    packages: Modules<Package>,
+}
+
+type State<'ns> = WithNsContext<'ns, GoGenState, GoConfig, GoGenerator>;
+
+impl Generate<GoGenState, GoConfig> for GoGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<GoConfig>,
+    ) -> GoGenState {
+        GoGenState {
+            packages: Default::default(),
+        }
+    }
+
+    #[allow(unused)]
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<GoConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        for ns in ctx.namespaces.values() {
+            let ctx = ctx_ns.get(&ns.name).unwrap();
+            let mut package = ctx.state.packages.take(&ns.name);
+
+            let keys = latest(&ns.defs)
+                .chain(latest(&ns.enums))
+                .chain(latest(&ns.one_ofs))
+                .chain(latest(&ns.errors));
+            for key in keys {
+                let name = pascal(&key.name.to_string());
+                writeln!(package.body, "type {name} = {}\n", versioned_name(key)).unwrap();
+            }
+
+            for helper in &package.helpers {
+                writeln!(package.body, "{}", helper.source()).unwrap();
+            }
+
+            let name = package_name(&ns.name.to_string());
+            let mut out =
+                format!("// Code generated by operation-api. DO NOT EDIT.\n\npackage {name}\n\n");
+            let std = package.std.iter().collect::<Vec<_>>();
+            let imports = package
+                .imports
+                .iter()
+                .map(|it| opts.opts.import_path(it))
+                .collect::<Vec<_>>();
+            match (std.as_slice(), imports.as_slice()) {
+                ([], []) => {},
+                (std, imports) => {
+                    out.push_str("import (\n");
+                    for import in std {
+                        writeln!(out, "\t\"{import}\"").unwrap();
+                    }
+                    if !std.is_empty() && !imports.is_empty() {
+                        out.push('\n');
+                    }
+                    for import in imports {
+                        writeln!(out, "\t\"{import}\"").unwrap();
+                    }
+                    out.push_str(")\n\n");
+                },
+            }
+            out.push_str(package.body.trim_end());
+            out.push('\n');
+
+            ctx.with_file_handle(ctx.path_for_file(format!("{name}/{name}")), |w| {
+                write!(w, "{out}")
+            })?;
+        }
+        Ok(())
+    }
+
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        let versioned = state.ns.ops.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_package(state, |go, out| {
+            out.push_str(&comment(&def.meta.description, ""));
+            writeln!(out, "type {name}Request {}\n", go.fields(None, &def.inputs)).unwrap();
+            out.push_str(&go.arrays(&format!("{name}Request"), &def.inputs));
+            out.push_str(&comment(&def.meta.description, ""));
+            writeln!(
+                out,
+                "type {name}Response {}\n",
+                go.fields(None, &def.outputs)
+            )
+            .unwrap();
+            out.push_str(&go.arrays(&format!("{name}Response"), &def.outputs));
+            if !def.infallible
+                && let Some(error) = &def.error
+            {
+                writeln!(out, "type {name}Error = {}\n", go.reference(error)).unwrap();
+            }
+        });
+        Ok(())
+    }
+
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let versioned = state.ns.defs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_package(state, |go, out| {
+            out.push_str(&comment(&def.meta.description, ""));
+            let fields = go.fields(Some(&def.meta.key()), &def.fields);
+            writeln!(out, "type {name} {fields}\n").unwrap();
+            out.push_str(&go.arrays(&name, &def.fields));
+        });
+        Ok(())
+    }
+
+    /// enums are typed constants, which only (un)marshal their own values
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let versioned = state.ns.enums.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        let underlying = match def
+            .variants
+            .values()
+            .all(|it| matches!(it.value, StrOrInt::Int(..)))
+        {
+            true => "uint64",
+            false => "string",
+        };
+        self.with_package(state, |go, out| {
+            go.std.extend(["encoding/json", "fmt"]);
+            out.push_str(&comment(&def.meta.description, ""));
+            writeln!(out, "type {name} {underlying}\n\nconst (").unwrap();
+            let constants = def
+                .variants
+                .values()
+                .map(|var| format!("{name}{}", pascal(&var.meta.name.to_string())))
+                .collect::<Vec<_>>();
+            let width = constants
+                .iter()
+                .map(String::len)
+                .max()
+                .unwrap_or_default();
+            for (var, constant) in def.variants.values().zip(&constants) {
+                let value = match &var.value {
+                    StrOrInt::Int(value) => value.to_string(),
+                    StrOrInt::String(value) => literal(value),
+                };
+                out.push_str(&comment(&var.meta.description, "\t"));
+                writeln!(out, "\t{constant:width$} {name} = {value}").unwrap();
+            }
+            let constants = constants.join(", ");
+            let verb = match underlying {
+                "string" => "%q",
+                _ => "%d",
+            };
+            write!(
+                out,
+                r#")
+
+func (it {name}) MarshalJSON() ([]byte, error) {{
+	switch it {{
+	case {constants}:
+		return json.Marshal({underlying}(it))
+	}}
+	return nil, fmt.Errorf("{verb} is not a {name}", {underlying}(it))
+}}
+
+func (it *{name}) UnmarshalJSON(data []byte) error {{
+	var value {underlying}
+	if err := json.Unmarshal(data, &value); err != nil {{
+		return err
+	}}
+	switch {name}(value) {{
+	case {constants}:
+		*it = {name}(value)
+		return nil
+	}}
+	return fmt.Errorf("{verb} is not a {name}", value)
+}}
+
+"#
+            )
+            .unwrap();
+        });
+        Ok(())
+    }
+
+    /// variants are unmarshaled in order, holding the first which matches
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let versioned = state.ns.one_ofs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_package(state, |go, out| {
+            go.std.extend(["bytes", "encoding/json", "fmt", "reflect", "strings"]);
+            go.helpers.insert(Helper::Untagged);
+            let variants = def
+                .variants
+                .iter()
+                .map(|(ident, var)| {
+                    let ty = match var.ty {
+                        Type::Never => None,
+                        _ => Some(go.ty(&var.ty)),
+                    };
+                    Variant::new(&name, ident, var, ty)
+                })
+                .collect::<Vec<_>>();
+            variant_types(out, &name, &def.meta.description, &variants);
+
+            let mut marshal = String::new();
+            let mut unmarshal = String::new();
+            let mut null = None;
+            for var in &variants {
+                let (variant, value, hold) = (&var.name, var.value(), var.hold());
+                match &var.ty {
+                    Some(ty) => {
+                        write!(
+                            marshal,
+                            "\tcase {variant}:\n\t\treturn json.Marshal({value})\n"
+                        )
+                        .unwrap();
+                        write!(
+                            unmarshal,
+                            "\t{{\n\t\tvar value {ty}\n\t\tif err := untagged(data, &value); err == nil {{\n\t\t\tit.Value = {hold}\n\t\t\treturn nil\n\t\t}}\n\t}}\n"
+                        )
+                        .unwrap();
+                    },
+                    None => {
+                        write!(marshal, "\tcase {variant}:\n\t\treturn []byte(\"null\"), nil\n")
+                            .unwrap();
+                        null.get_or_insert(variant);
+                    },
+                }
+            }
+            // null unmarshals into any value, so is only held by a variant of none
+            let null = match null {
+                Some(variant) => format!("it.Value = {variant}{{}}\n\t\treturn nil"),
+                None => format!("return fmt.Errorf(\"null is not a {name}\")"),
+            };
+            write!(
+                out,
+                r#"func (it {name}) MarshalJSON() ([]byte, error) {{
+	switch variant := it.Value.(type) {{
+{marshal}	}}
+	return nil, fmt.Errorf("{name} holds no variant")
+}}
+
+func (it *{name}) UnmarshalJSON(data []byte) error {{
+	if string(data) == "null" {{
+		{null}
+	}}
+{unmarshal}	return fmt.Errorf("%s is not a {name}", data)
+}}
+
+"#
+            )
+            .unwrap();
+        });
+        Ok(())
+    }
+
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let versioned = state.ns.errors.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_package(state, |go, out| {
+            go.std.extend(["encoding/json", "fmt"]);
+            go.helpers.insert(Helper::Tagged);
+            let variants = def
+                .variants
+                .iter()
+                .map(|(ident, var)| {
+                    let ty = Some(go.ty(&var.ty));
+                    Variant::new(&name, ident, var, ty)
+                })
+                .collect::<Vec<_>>();
+            variant_types(out, &name, &def.meta.description, &variants);
+
+            let mut tags = String::new();
+            let mut unmarshal = String::new();
+            for var in &variants {
+                let (variant, value, hold) = (&var.name, var.value(), var.hold());
+                let tag = literal(&tag(var.ident));
+                let ty = var.ty.as_deref().unwrap_or_default();
+                write!(tags, "\tcase {variant}:\n\t\treturn {tag}, {value}\n").unwrap();
+                write!(
+                    unmarshal,
+                    "\tcase {tag}:\n\t\tvar value {ty}\n\t\tif err := json.Unmarshal(data, &value); err != nil {{\n\t\t\treturn err\n\t\t}}\n\t\tit.Value = {hold}\n"
+                )
+                .unwrap();
+            }
+            write!(
+                out,
+                r#"func (it {name}) variant() (string, any) {{
+	switch variant := it.Value.(type) {{
+{tags}	}}
+	return "", nil
+}}
+
+func (it {name}) Error() string {{
+	tag, _ := it.variant()
+	return "{name}: " + tag
+}}
+
+func (it {name}) MarshalJSON() ([]byte, error) {{
+	tag, value := it.variant()
+	if tag == "" {{
+		return nil, fmt.Errorf("{name} holds no variant")
+	}}
+	return tagged(tag, value)
+}}
+
+func (it *{name}) UnmarshalJSON(data []byte) error {{
+	var tag struct {{
+		Type string `json:"type"`
+	}}
+	if err := json.Unmarshal(data, &tag); err != nil {{
+		return err
+	}}
+	switch tag.Type {{
+{unmarshal}	default:
+		return fmt.Errorf("%q is not a variant of {name}", tag.Type)
+	}}
+	return nil
+}}
+
+"#
+            )
+            .unwrap();
+        });
+        Ok(())
+    }
+}
+
+impl GoGenerator {
+    fn with_package(
+        &self,
+        state: &State<'_>,
+        handle: impl FnOnce(&mut Types<'_>, &mut String),
+    ) {
+        let types = Types {
+            ns: state.ns,
+            std: BTreeSet::new(),
+            imports: BTreeSet::new(),
+            helpers: BTreeSet::new(),
+        };
+        state
+            .state
+            .packages
+            .write(&state.ns.name, types, |types, package| {
+                handle(types, &mut package.body)
+            });
+    }
+}
+
+impl GoConfig {
+    fn import_path(
+        &self,
+        package: &str,
+    ) -> String {
+        match &self.import_path {
+            Some(path) => format!("{}/{package}", path.trim_end_matches('/')),
+            None => package.into(),
+        }
+    }
+}
+
+/// writes the holder of a one of or error, which holds a single variant of the interface of its
+/// variants
+/// a variant of a one of or error
+struct Variant<'a> {
+    ident: &'a Ident,
+    description: &'a Option<String>,
+    /// the struct the variant is held as, e.g. `NodeLeaf`
+    name: String,
+    /// the type of its value, none for a variant of none
+    ty: Option<String>,
+    /// a variant declaring its fields inline shares its name with their struct, so is held as
+    /// that struct itself
+    inline: bool,
+}
+
+impl<'a> Variant<'a> {
+    fn new(
+        name: &str,
+        ident: &'a Ident,
+        var: &'a OneOfVariant,
+        ty: Option<String>,
+    ) -> Self {
+        let inline = inline(var).is_some();
+        let mut name = format!("{name}{}", pascal(&ident.to_string()));
+        // a struct may be named as the variant holding it, e.g. `TooLarge(FailureTooLarge)`
+        if !inline && ty.as_ref() == Some(&name) {
+            name.push_str("Variant");
+        }
+        Self {
+            ident,
+            description: &var.description,
+            name,
+            ty,
+            inline,
+        }
+    }
+
+    /// the value of `variant`, once matched
+    fn value(&self) -> &'static str {
+        match self.inline {
+            true => "variant",
+            false => "variant.Value",
+        }
+    }
+
+    /// the variant holding `value`, once unmarshaled
+    fn hold(&self) -> String {
+        match self.inline {
+            true => "value".into(),
+            false => format!("{}{{value}}", self.name),
+        }
+    }
+}
+
+fn variant_types(
+    out: &mut String,
+    name: &str,
+    description: &Option<String>,
+    variants: &[Variant<'_>],
+) {
+    out.push_str(&comment(description, ""));
+    writeln!(out, "type {name} struct {{\n\tValue {name}Variant\n}}\n").unwrap();
+    writeln!(
+        out,
+        "// {name}Variant is a variant of {name}\ntype {name}Variant interface {{\n\tis{name}()\n}}\n"
+    )
+    .unwrap();
+    for var in variants {
+        let variant = &var.name;
+        if !var.inline {
+            out.push_str(&comment(var.description, ""));
+            match &var.ty {
+                Some(ty) => writeln!(out, "type {variant} struct {{\n\tValue {ty}\n}}\n"),
+                None => writeln!(out, "type {variant} struct{{}}\n"),
+            }
+            .unwrap();
+        }
+        writeln!(out, "func ({variant}) is{name}() {{}}\n").unwrap();
+    }
+}
+
+/// names the types of a namespace, noting every package and helper they need
+struct Types<'ns> {
+    ns: &'ns Namespace,
+    std: BTreeSet<&'static str>,
+    imports: BTreeSet<String>,
+    helpers: BTreeSet<Helper>,
+}
+
+impl Types<'_> {
+    /// a struct of `fields`. members of `parent` on a reference cycle are held by pointer, so
+    /// that the struct stays finitely sized.
+    fn fields(
+        &mut self,
+        parent: Option<&Key>,
+        fields: &FieldsList,
+    ) -> String {
+        if fields.is_empty() {
+            return "struct{}".into();
+        }
+        let mut lines = vec![];
+        for (name, field) in fields.iter() {
+            let field = field.unwrap_value();
+            let optional = matches!(field.ty, Type::CompoundType(CompoundType::Option { .. }));
+            let mut ty = self.ty(&field.ty);
+            if !optional && parent.is_some_and(|it| self.ns.is_recursive(it, name)) {
+                ty = format!("*{ty}");
+            }
+            let omit = match optional {
+                true => ",omitempty",
+                false => "",
+            };
+            lines.push((
+                comment(&field.meta.description, "\t"),
+                pascal(&name.to_string()),
+                ty,
+                format!("`json:\"{name}{omit}\"`"),
+            ));
+        }
+
+        // aligned as by gofmt
+        let name_width = lines
+            .iter()
+            .map(|(_, it, ..)| it.len())
+            .max()
+            .unwrap_or_default();
+        let ty_width = lines
+            .iter()
+            .map(|(_, _, it, _)| it.len())
+            .max()
+            .unwrap_or_default();
+        let mut out = String::from("struct {\n");
+        for (comment, name, ty, tag) in lines {
+            out.push_str(&comment);
+            writeln!(out, "\t{name:name_width$} {ty:ty_width$} {tag}").unwrap();
+        }
+        out.push('}');
+        out
+    }
+
+    /// marshals the nil slices of required fields of `name` as empty, rather than as null
+    fn arrays(
+        &mut self,
+        name: &str,
+        fields: &FieldsList,
+    ) -> String {
+        let arrays = fields
+            .iter()
+            .filter_map(|(field, it)| {
+                let ty = &it.unwrap_value().ty;
+                matches!(ty, Type::CompoundType(CompoundType::Array { .. }))
+                    .then(|| (pascal(&field.to_string()), self.ty(ty)))
+            })
+            .collect::<Vec<_>>();
+        if arrays.is_empty() {
+            return String::new();
+        }
+        self.std.insert("encoding/json");
+        let mut out =
+            format!("func (it {name}) MarshalJSON() ([]byte, error) {{\n\ttype plain {name}\n");
+        for (field, ty) in arrays {
+            write!(
+                out,
+                "\tif it.{field} == nil {{\n\t\tit.{field} = {ty}{{}}\n\t}}\n"
+            )
+            .unwrap();
+        }
+        out.push_str("\treturn json.Marshal(plain(it))\n}\n\n");
+        out
+    }
+
+    /// the type of a value of `ty`
+    fn ty(
+        &mut self,
+        ty: &Type,
+    ) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::U8 => "uint8".into(),
+            Type::U16 => "uint16".into(),
+            Type::U32 => "uint32".into(),
+            Type::U64 | Type::Usize => "uint64".into(),
+            Type::I8 => "int8".into(),
+            Type::I16 => "int16".into(),
+            Type::I32 => "int32".into(),
+            Type::I64 => "int64".into(),
+            Type::F32 => "float32".into(),
+            Type::F64 => "float64".into(),
+            Type::String => "string".into(),
+            Type::DateTime => {
+                self.std.insert("time");
+                "time.Time".into()
+            },
+            Type::Binary => {
+                self.std.insert("encoding/json");
+                self.helpers.insert(Helper::Binary);
+                "Binary".into()
+            },
+            Type::Complex => "any".into(),
+            Type::Never => "struct{}".into(),
+            Type::CompoundType(ty) => {
+                match ty {
+                    CompoundType::Enum { to }
+                    | CompoundType::OneOf { to }
+                    | CompoundType::Struct { to } => self.reference(to),
+                    CompoundType::Option { ty } => format!("*{}", self.ty(ty)),
+                    CompoundType::Array { ty } => format!("[]{}", self.ty(ty)),
+                    CompoundType::SizedArray { size, ty } => format!("[{size}]{}", self.ty(ty)),
+                }
+            },
+        }
+    }
+
+    /// references into another namespace go through the import of its package
+    fn reference(
+        &mut self,
+        to: &crate::Ident,
+    ) -> String {
+        let (qualifier, name) = to.split_qualified();
+        let name = crate::Ident::new(name);
+        let (name, version) = name.split_version();
+        let name = match version {
+            Some(version) => versioned_name(&Key::new(name, version)),
+            None => pascal(name),
+        };
+        match qualifier {
+            Some(qualifier) if qualifier != self.ns.name.to_string() => {
+                let package = package_name(qualifier);
+                let reference = format!("{package}.{name}");
+                self.imports.insert(package);
+                reference
+            },
+            _ => name,
+        }
+    }
+}
+
+fn package_name(ns: &str) -> String {
+    crate::namespace::normalized_path::<GoGenerator>(ns)
+}
+
+fn comment(
+    description: &Option<String>,
+    indent: &str,
+) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    description
+        .trim()
+        .lines()
+        .map(|line| {
+            match line.trim() {
+                "" => format!("{indent}//\n"),
+                line => format!("{indent}// {line}\n"),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,562 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    io::Write,
+    path::Path,
+};
+
+use crate::{
+    CompoundType, Enum, ErrorTy, FieldsList, Key, OneOf, OneOfVariant, Operation, OperationKind,
+    Struct, Type,
+    generate::{
+        GenOpts, Generate, GraphqlConfig, LanguageTrait,
+        common::{Module, Modules, name, pascal, versioned_name},
+        context::WithNsContext,
+        files::WithFlush,
+    },
+    namespace::{Declarations, Namespace},
+};
+
+const INDENT: &str = "  ";
+
+pub struct GraphqlGenerator;
+
+impl LanguageTrait for GraphqlGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Snake
+    }
+
+    fn file_ext() -> &'static str {
+        "graphql"
+    }
+}
+
+/// scalars which graphql does not specify, declared once used
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Scalar {
+    /// integers which do not fit in graphql's signed 32 bit `Int`
+    BigInt,
+    DateTime,
+    Json,
+}
+
+impl Scalar {
+    fn name(self) -> &'static str {
+        match self {
+            Self::BigInt => "BigInt",
+            Self::DateTime => "DateTime",
+            Self::Json => "JSON",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Schema {
+    scalars: BTreeSet<Scalar>,
+    one_of: bool,
+    /// every type by its name, along with the namespace which declares it
+    types: Vec<(String, crate::Ident, String)>,
+    /// every root field by its kind and name, along with the namespace which declares it
+    fields: Vec<(OperationKind, String, crate::Ident, String)>,
+}
+
+impl Module for Schema {
+    type Refs<'ns> = Types<'ns>;
+
+    fn merge(
+        &mut self,
+        refs: Types<'_>,
+        written: Self,
+    ) {
+        self.scalars.extend(refs.scalars);
+        self.one_of |= written.one_of;
+        self.types.extend(written.types);
+        self.fields.extend(written.fields);
+    }
+}
+
+pub(crate) struct GraphqlGenState {
+    /// every namespace is written into a single schema, once every declaration is generated
+    schema: Modules<Schema>,
+}
+
+type State<'ns> = WithNsContext<'ns, GraphqlGenState, GraphqlConfig, GraphqlGenerator>;
+
+impl Generate<GraphqlGenState, GraphqlConfig> for GraphqlGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<GraphqlConfig>,
+    ) -> GraphqlGenState {
+        GraphqlGenState {
+            schema: Default::default(),
+        }
+    }
+
+    /// namespaces share a single schema, so their names must not collide. colliding names are
+    /// reported rather than renamed, and are told apart by a prefix per namespace.
+    #[allow(unused)]
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<GraphqlConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        let Some(first) = ctx_ns.values().next() else {
+            return Ok(());
+        };
+
+        let mut scalars = BTreeSet::new();
+        let mut one_of = false;
+        let mut types = String::new();
+        let mut roots: BTreeMap<OperationKind, String> = BTreeMap::new();
+        let mut declared = BTreeMap::new();
+        let mut errors = vec![];
+        for ns in ctx.namespaces.values() {
+            let schema = first.state.schema.take(&ns.name);
+            scalars.extend(schema.scalars);
+            one_of |= schema.one_of;
+
+            let names = schema
+                .types
+                .into_iter()
+                .map(|(name, ns, sdl)| (("type", name), ns, sdl))
+                .chain(
+                    schema
+                        .fields
+                        .into_iter()
+                        .map(|(kind, name, ns, sdl)| ((root(kind), name), ns, sdl)),
+                );
+            for ((kind, name), ns, sdl) in names {
+                if let Some(other) = declared.insert((kind, name.clone()), ns.clone()) {
+                    errors.push(crate::Error::NameCollision {
+                        kind,
+                        name,
+                        first: other,
+                        second: ns,
+                    });
+                    continue;
+                }
+                match kind {
+                    "type" => types.push_str(&sdl),
+                    "Query" => {
+                        roots
+                            .entry(OperationKind::Query)
+                            .or_default()
+                            .push_str(&sdl)
+                    },
+                    _ => {
+                        roots
+                            .entry(OperationKind::Mutation)
+                            .or_default()
+                            .push_str(&sdl)
+                    },
+                }
+            }
+        }
+        match errors.len() {
+            0 => {},
+            1 => return Err(errors.remove(0)),
+            _ => return Err(crate::Error::Many(errors)),
+        }
+
+        let mut out = String::from("# Code generated by operation-api. DO NOT EDIT.\n\n");
+        if one_of {
+            out.push_str("directive @oneOf on INPUT_OBJECT\n\n");
+        }
+        for scalar in scalars {
+            writeln!(out, "scalar {}\n", scalar.name()).unwrap();
+        }
+        out.push_str(&types);
+        for (kind, fields) in roots {
+            writeln!(out, "type {} {{\n{fields}}}\n", root(kind)).unwrap();
+        }
+
+        first.with_file_handle(first.path_for_file("schema"), |w| {
+            writeln!(w, "{}", out.trim_end())
+        })?;
+        Ok(())
+    }
+
+    /// operations are a field of `Query` or `Mutation` by their kind, taking their inputs as
+    /// arguments
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        let versioned = state.ns.ops.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        let field = match state.opts.opts.prefixes.get(&state.ns.name) {
+            Some(prefix) => format!("{}{name}", lower_first(prefix)),
+            None => lower_first(&name),
+        };
+        self.with_schema(state, |gql, schema| {
+            let mut args = vec![];
+            for (arg, it) in def.inputs.iter() {
+                let it = it.unwrap_value();
+                args.push(format!("{arg}: {}", gql.ty(&it.ty, true)));
+            }
+            let args = match args.is_empty() {
+                true => String::new(),
+                false => format!("({})", args.join(", ")),
+            };
+
+            let output = match def.outputs.iter().next() {
+                None => "Boolean".into(),
+                Some((_, value))
+                    if def.outputs.len() == 1 && value.unwrap_value().meta.name.is_none() =>
+                {
+                    gql.ty(&value.unwrap_value().ty, false)
+                },
+                Some(_) => {
+                    let response = gql.prefixed(&format!("{name}Response"), &state.ns.name);
+                    let fields = gql.fields(&def.outputs, false);
+                    schema.types.push((
+                        response.clone(),
+                        state.ns.name.clone(),
+                        format!("type {response} {{\n{fields}}}\n\n"),
+                    ));
+                    format!("{response}!")
+                },
+            };
+
+            let mut description = def.meta.description.clone();
+            if !def.infallible
+                && let Some(error) = &def.error
+            {
+                let error = format!("fails with `{}`", gql.reference(error));
+                description = Some(match description {
+                    Some(it) => format!("{}\n\n{error}", it.trim()),
+                    None => error,
+                });
+            }
+            let mut sdl = self::description(&description, INDENT);
+            writeln!(sdl, "{INDENT}{field}{args}: {output}").unwrap();
+            schema
+                .fields
+                .push((def.kind, field, state.ns.name.clone(), sdl));
+        });
+        Ok(())
+    }
+
+    /// structs are an object type, and an input type of the same fields
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let versioned = state.ns.defs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_schema(state, |gql, schema| {
+            let object = gql.prefixed(&name, &state.ns.name);
+            let input = format!("{object}Input");
+            let describe = self::description(&def.meta.description, "");
+            let fields = gql.fields(&def.fields, false);
+            schema.types.push((
+                object.clone(),
+                state.ns.name.clone(),
+                format!("{describe}type {object} {{\n{fields}}}\n\n"),
+            ));
+            let fields = gql.fields(&def.fields, true);
+            schema.types.push((
+                input.clone(),
+                state.ns.name.clone(),
+                format!("{describe}input {input} {{\n{fields}}}\n\n"),
+            ));
+        });
+        Ok(())
+    }
+
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let versioned = state.ns.enums.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_schema(state, |gql, schema| {
+            let name = gql.prefixed(&name, &state.ns.name);
+            let mut sdl = description(&def.meta.description, "");
+            writeln!(sdl, "enum {name} {{").unwrap();
+            for var in def.variants.values() {
+                sdl.push_str(&description(&var.meta.description, INDENT));
+                writeln!(sdl, "{INDENT}{}", var.meta.name).unwrap();
+            }
+            sdl.push_str("}\n\n");
+            schema
+                .types
+                .push((name, state.ns.name.clone(), sdl));
+        });
+        Ok(())
+    }
+
+    /// one ofs are a union of their variants, and a `@oneOf` input type of the same variants
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let versioned = state.ns.one_ofs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_schema(state, |gql, schema| {
+            let union = gql.prefixed(&name, &state.ns.name);
+            gql.union(schema, &union, &def.meta.description, &def.variants);
+
+            let input = format!("{union}Input");
+            let mut sdl = description(&def.meta.description, "");
+            writeln!(sdl, "input {input} @oneOf {{").unwrap();
+            for (ident, var) in def.variants.iter() {
+                sdl.push_str(&description(&var.description, INDENT));
+                let ty = gql.ty(&var.ty, true);
+                // exactly one variant is given, so each is nullable
+                writeln!(sdl, "{INDENT}{ident}: {}", ty.trim_end_matches('!')).unwrap();
+            }
+            sdl.push_str("}\n\n");
+            schema.one_of = true;
+            schema
+                .types
+                .push((input, state.ns.name.clone(), sdl));
+        });
+        Ok(())
+    }
+
+    /// errors are a union of their variants
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let versioned = state.ns.errors.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_schema(state, |gql, schema| {
+            let union = gql.prefixed(&name, &state.ns.name);
+            gql.union(schema, &union, &def.meta.description, &def.variants);
+        });
+        Ok(())
+    }
+}
+
+impl GraphqlGenerator {
+    fn with_schema(
+        &self,
+        state: &State<'_>,
+        handle: impl FnOnce(&mut Types<'_>, &mut Schema),
+    ) {
+        let types = Types {
+            ns: state.ns,
+            opts: &state.opts.opts,
+            scalars: BTreeSet::new(),
+        };
+        state
+            .state
+            .schema
+            .write(&state.ns.name, types, handle);
+    }
+}
+
+/// names the types of a namespace, noting every scalar they need
+struct Types<'ns> {
+    ns: &'ns Namespace,
+    opts: &'ns GraphqlConfig,
+    scalars: BTreeSet<Scalar>,
+}
+
+impl Types<'_> {
+    /// `name` as declared in the schema by namespace `ns`, led by the prefix of `ns` if any
+    fn prefixed(
+        &self,
+        name: &str,
+        ns: &crate::Ident,
+    ) -> String {
+        match self.opts.prefixes.get(ns) {
+            Some(prefix) => format!("{prefix}{name}"),
+            None => name.into(),
+        }
+    }
+
+    fn fields(
+        &mut self,
+        fields: &FieldsList,
+        input: bool,
+    ) -> String {
+        let mut out = String::new();
+        for (name, field) in fields.iter() {
+            let field = field.unwrap_value();
+            out.push_str(&description(&field.meta.description, INDENT));
+            writeln!(out, "{INDENT}{name}: {}", self.ty(&field.ty, input)).unwrap();
+        }
+        out
+    }
+
+    /// a union of the variants of a one of or error. union members must be distinct object
+    /// types, so every other variant is held by an object type of its own, e.g. `NodeLeaf`.
+    fn union(
+        &mut self,
+        schema: &mut Schema,
+        union: &str,
+        description: &Option<String>,
+        variants: &crate::Named<OneOfVariant>,
+    ) {
+        let objects = variants
+            .values()
+            .filter_map(|var| {
+                match &var.ty {
+                    Type::CompoundType(CompoundType::Struct { to }) => Some(to),
+                    _ => None,
+                }
+            })
+            .fold(BTreeMap::<_, usize>::new(), |mut counts, to| {
+                *counts.entry(to).or_default() += 1;
+                counts
+            });
+
+        let mut members = vec![];
+        for (ident, var) in variants.iter() {
+            match &var.ty {
+                Type::CompoundType(CompoundType::Struct { to }) if objects[to] == 1 => {
+                    members.push(self.reference(to));
+                },
+                ty => {
+                    let object = format!("{union}{}", pascal(&ident.to_string()));
+                    let mut sdl = self::description(&var.description, "");
+                    writeln!(
+                        sdl,
+                        "type {object} {{\n{INDENT}value: {}\n}}\n",
+                        self.ty(ty, false)
+                    )
+                    .unwrap();
+                    schema
+                        .types
+                        .push((object.clone(), self.ns.name.clone(), sdl));
+                    members.push(object);
+                },
+            }
+        }
+
+        let mut sdl = self::description(description, "");
+        writeln!(sdl, "union {union} = {}\n", members.join(" | ")).unwrap();
+        schema
+            .types
+            .push((union.into(), self.ns.name.clone(), sdl));
+    }
+
+    /// the type of a value of `ty`, which is non null unless optional. values of `input`s are of
+    /// the input type of a struct or one of.
+    fn ty(
+        &mut self,
+        ty: &Type,
+        input: bool,
+    ) -> String {
+        let named = match ty {
+            Type::Bool => "Boolean".into(),
+            Type::U8 | Type::U16 | Type::I8 | Type::I16 | Type::I32 => "Int".into(),
+            Type::U32 | Type::U64 | Type::Usize | Type::I64 => self.scalar(Scalar::BigInt),
+            Type::F32 | Type::F64 => "Float".into(),
+            Type::String => "String".into(),
+            Type::DateTime => self.scalar(Scalar::DateTime),
+            Type::Binary => "[Int!]".into(),
+            Type::Complex => self.scalar(Scalar::Json),
+            // graphql has no unit type, so is held by a value which is always null
+            Type::Never => return "Boolean".into(),
+            Type::CompoundType(ty) => {
+                match ty {
+                    CompoundType::Enum { to } => self.reference(to),
+                    CompoundType::OneOf { to } | CompoundType::Struct { to } => {
+                        match input {
+                            true => format!("{}Input", self.reference(to)),
+                            false => self.reference(to),
+                        }
+                    },
+                    CompoundType::Option { ty } => {
+                        return self
+                            .ty(ty, input)
+                            .trim_end_matches('!')
+                            .into();
+                    },
+                    CompoundType::Array { ty } | CompoundType::SizedArray { ty, .. } => {
+                        format!("[{}]", self.ty(ty, input))
+                    },
+                }
+            },
+        };
+        format!("{named}!")
+    }
+
+    fn scalar(
+        &mut self,
+        scalar: Scalar,
+    ) -> String {
+        self.scalars.insert(scalar);
+        scalar.name().into()
+    }
+
+    /// references into another namespace are led by its prefix
+    fn reference(
+        &self,
+        to: &crate::Ident,
+    ) -> String {
+        let (qualifier, name) = to.split_qualified();
+        let name = crate::Ident::new(name);
+        let (name, version) = name.split_version();
+        let name = match version {
+            Some(version) => versioned_name(&Key::new(name, version)),
+            None => pascal(name),
+        };
+        let ns = match qualifier {
+            Some(qualifier) => crate::Ident::new(qualifier),
+            None => self.ns.name.clone(),
+        };
+        self.prefixed(&name, &ns)
+    }
+}
+
+fn root(kind: OperationKind) -> &'static str {
+    match kind {
+        OperationKind::Query => "Query",
+        OperationKind::Mutation => "Mutation",
+    }
+}
+
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn description(
+    description: &Option<String>,
+    indent: &str,
+) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    let description = description.trim();
+    match description.contains(['\n', '"', '\\']) {
+        false => format!("{indent}\"{description}\"\n"),
+        true => {
+            let lines = description
+                .replace("\"\"\"", "\\\"\"\"")
+                .lines()
+                .map(|line| {
+                    match line.trim() {
+                        "" => "\n".to_string(),
+                        line => format!("{indent}{line}\n"),
+                    }
+                })
+                .collect::<String>();
+            format!("{indent}\"\"\"\n{lines}{indent}\"\"\"\n")
+        },
+    }
+}
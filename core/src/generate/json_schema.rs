@@ -0,0 +1,395 @@
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    CompoundType, Enum, ErrorTy, FieldsList, OneOf, OneOfVariant, Operation, StrOrInt, Struct,
+    Type,
+    generate::{
+        GenOpts, Generate, JsonSchemaConfig, LanguageTrait,
+        common::{Modules, def_name, file_name, tag},
+        context::WithNsContext,
+        files::WithFlush,
+    },
+    namespace::{Declarations, Namespace},
+};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+pub struct JsonSchemaGenerator;
+
+impl LanguageTrait for JsonSchemaGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Snake
+    }
+
+    fn file_ext() -> &'static str {
+        "json"
+    }
+}
+
+pub(crate) struct JsonSchemaGenState {
+    /// schemas of every declaration by namespace, written as a single document per namespace
+    /// once every declaration is generated
+    defs: Modules<Map<String, Value>>,
+}
+
+type State<'ns> = WithNsContext<'ns, JsonSchemaGenState, JsonSchemaConfig, JsonSchemaGenerator>;
+
+#[derive(serde::Serialize)]
+struct Document {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    #[serde(rename = "$id")]
+    id: String,
+    title: String,
+    #[serde(rename = "$defs")]
+    defs: Map<String, Value>,
+}
+
+impl Generate<JsonSchemaGenState, JsonSchemaConfig> for JsonSchemaGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<JsonSchemaConfig>,
+    ) -> JsonSchemaGenState {
+        JsonSchemaGenState {
+            defs: Default::default(),
+        }
+    }
+
+    #[allow(unused)]
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<JsonSchemaConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        for ns in ctx.namespaces.values() {
+            let ctx = ctx_ns.get(&ns.name).unwrap();
+            let document = Document {
+                schema: DRAFT,
+                id: file_name::<Self>(&ns.name.to_string()),
+                title: ns.name.to_string(),
+                defs: ctx.state.defs.take(&ns.name),
+            };
+            let document = serde_json::to_string_pretty(&document)?;
+            ctx.with_file_handle(ctx.ns_file(), |w| writeln!(w, "{document}"))?;
+        }
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        Ok(())
+    }
+
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, "#/$defs");
+        insert(state, schemas.struct_name(def), schemas.strct(def));
+        Ok(())
+    }
+
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, "#/$defs");
+        insert(state, schemas.enum_name(def), schemas.enm(def));
+        Ok(())
+    }
+
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, "#/$defs");
+        insert(state, schemas.one_of_name(def), schemas.one_of(def));
+        Ok(())
+    }
+
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, "#/$defs");
+        insert(state, schemas.error_name(def), schemas.error(def));
+        Ok(())
+    }
+}
+
+fn insert(
+    state: &State<'_>,
+    name: String,
+    schema: Value,
+) {
+    state
+        .state
+        .defs
+        .with(&state.ns.name, |defs| {
+            defs.insert(name, schema);
+        });
+}
+
+/// builds json schemas of the declarations of a namespace. declarations are referred to by their
+/// name under `pointer`, within the document of their namespace.
+pub(crate) struct Schemas<'ns> {
+    ns: &'ns Namespace,
+    pointer: &'static str,
+    file_name: fn(&str) -> String,
+}
+
+impl<'ns> Schemas<'ns> {
+    pub fn new<L: LanguageTrait>(
+        ns: &'ns Namespace,
+        pointer: &'static str,
+    ) -> Self {
+        Self {
+            ns,
+            pointer,
+            file_name: file_name::<L>,
+        }
+    }
+
+    pub fn struct_name(
+        &self,
+        def: &Struct,
+    ) -> String {
+        def_name(&def.meta.key(), self.ns.defs.is_versioned(&def.meta.name))
+    }
+
+    pub fn enum_name(
+        &self,
+        def: &Enum,
+    ) -> String {
+        def_name(&def.meta.key(), self.ns.enums.is_versioned(&def.meta.name))
+    }
+
+    pub fn one_of_name(
+        &self,
+        def: &OneOf,
+    ) -> String {
+        def_name(
+            &def.meta.key(),
+            self.ns.one_ofs.is_versioned(&def.meta.name),
+        )
+    }
+
+    pub fn error_name(
+        &self,
+        def: &ErrorTy,
+    ) -> String {
+        def_name(&def.meta.key(), self.ns.errors.is_versioned(&def.meta.name))
+    }
+
+    pub fn strct(
+        &self,
+        def: &Struct,
+    ) -> Value {
+        describe(self.fields(&def.fields), &def.meta.description)
+    }
+
+    /// an object of `fields`, requiring those which are not optional
+    pub fn fields(
+        &self,
+        fields: &FieldsList,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for (name, field) in fields.iter() {
+            let field = field.unwrap_value();
+            if !matches!(field.ty, Type::CompoundType(CompoundType::Option { .. })) {
+                required.push(Value::from(name.to_string()));
+            }
+            properties.insert(
+                name.to_string(),
+                describe(self.ty(&field.ty), &field.meta.description),
+            );
+        }
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    pub fn enm(
+        &self,
+        def: &Enum,
+    ) -> Value {
+        let values: Vec<_> = def
+            .variants
+            .values()
+            .map(|var| {
+                match &var.value {
+                    StrOrInt::String(value) => Value::from(value.clone()),
+                    StrOrInt::Int(value) => Value::from(*value),
+                }
+            })
+            .collect();
+        let ty = match values.first() {
+            Some(Value::String(..)) => "string",
+            _ => "integer",
+        };
+
+        let schema = json!({
+            "type": ty,
+            "enum": values,
+        });
+        describe(schema, &def.meta.description)
+    }
+
+    pub fn one_of(
+        &self,
+        def: &OneOf,
+    ) -> Value {
+        let variants: Vec<_> = def
+            .variants
+            .values()
+            .map(|var| self.variant(var))
+            .collect();
+        describe(json!({ "oneOf": variants }), &def.meta.description)
+    }
+
+    pub fn error(
+        &self,
+        def: &ErrorTy,
+    ) -> Value {
+        let variants: Vec<_> = def
+            .variants
+            .iter()
+            .map(|(name, var)| {
+                let tag = json!({
+                    "type": "object",
+                    "properties": {
+                        "type": { "const": tag(name) },
+                    },
+                    "required": ["type"],
+                });
+                json!({ "allOf": [tag, self.variant(var)] })
+            })
+            .collect();
+        describe(json!({ "oneOf": variants }), &def.meta.description)
+    }
+
+    fn variant(
+        &self,
+        var: &OneOfVariant,
+    ) -> Value {
+        describe(self.ty(&var.ty), &var.description)
+    }
+
+    /// the schema of a value of `ty`
+    pub fn ty(
+        &self,
+        ty: &Type,
+    ) -> Value {
+        match ty {
+            Type::Bool => json!({ "type": "boolean" }),
+
+            Type::U8 => integer(Some(0), Some(u8::MAX as i64)),
+            Type::U16 => integer(Some(0), Some(u16::MAX as i64)),
+            Type::U32 => integer(Some(0), Some(u32::MAX as i64)),
+            Type::U64 | Type::Usize => integer(Some(0), None),
+
+            Type::I8 => integer(Some(i8::MIN as i64), Some(i8::MAX as i64)),
+            Type::I16 => integer(Some(i16::MIN as i64), Some(i16::MAX as i64)),
+            Type::I32 => integer(Some(i32::MIN as i64), Some(i32::MAX as i64)),
+            Type::I64 => integer(None, None),
+
+            Type::F32 | Type::F64 => json!({ "type": "number" }),
+
+            Type::String => json!({ "type": "string" }),
+            Type::DateTime => json!({ "type": "string", "format": "date-time" }),
+            Type::Binary => {
+                json!({ "type": "array", "items": integer(Some(0), Some(u8::MAX as i64)) })
+            },
+
+            Type::Complex => json!({}),
+            Type::Never => json!({ "type": "null" }),
+
+            Type::CompoundType(ty) => {
+                match ty {
+                    CompoundType::Enum { to }
+                    | CompoundType::OneOf { to }
+                    | CompoundType::Struct { to } => json!({ "$ref": self.reference(to) }),
+                    CompoundType::Option { ty } => {
+                        json!({ "anyOf": [self.ty(ty), { "type": "null" }] })
+                    },
+                    CompoundType::Array { ty } => {
+                        json!({ "type": "array", "items": self.ty(ty) })
+                    },
+                    CompoundType::SizedArray { size, ty } => {
+                        json!({
+                            "type": "array",
+                            "items": self.ty(ty),
+                            "minItems": size,
+                            "maxItems": size,
+                        })
+                    },
+                }
+            },
+        }
+    }
+
+    /// a reference to a declaration in the document of its namespace. references are pinned to
+    /// a version exactly when their declaration is held in several versions.
+    pub fn reference(
+        &self,
+        to: &crate::Ident,
+    ) -> String {
+        let (qualifier, name) = to.split_qualified();
+        match qualifier {
+            Some(qualifier) if qualifier != self.ns.name.to_string() => {
+                format!("{}{}/{name}", (self.file_name)(qualifier), self.pointer)
+            },
+            _ => format!("{}/{name}", self.pointer),
+        }
+    }
+}
+
+fn integer(
+    min: Option<i64>,
+    max: Option<i64>,
+) -> Value {
+    let mut schema = Map::new();
+    schema.insert("type".into(), "integer".into());
+    if let Some(min) = min {
+        schema.insert("minimum".into(), min.into());
+    }
+    if let Some(max) = max {
+        schema.insert("maximum".into(), max.into());
+    }
+    Value::Object(schema)
+}
+
+pub(crate) fn describe(
+    mut schema: Value,
+    description: &Option<String>,
+) -> Value {
+    if let (Value::Object(schema), Some(description)) = (&mut schema, description) {
+        schema.insert("description".into(), description.trim().into());
+    }
+    schema
+}
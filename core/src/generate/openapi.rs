@@ -0,0 +1,299 @@
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    Enum, ErrorTy, OneOf, Operation, Struct,
+    generate::{
+        GenOpts, Generate, LanguageTrait, OpenApiConfig,
+        common::{Modules, def_name, tag},
+        context::WithNsContext,
+        files::WithFlush,
+        json_schema::Schemas,
+    },
+    namespace::Declarations,
+};
+
+const VERSION: &str = "3.1.0";
+
+const SCHEMAS: &str = "#/components/schemas";
+
+pub struct OpenApiGenerator;
+
+impl LanguageTrait for OpenApiGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Snake
+    }
+
+    fn file_ext() -> &'static str {
+        "openapi.json"
+    }
+}
+
+#[derive(Default)]
+struct Items {
+    paths: Map<String, Value>,
+    schemas: Map<String, Value>,
+}
+
+pub(crate) struct OpenApiGenState {
+    /// paths and component schemas of every namespace, written as a single document per
+    /// namespace once every declaration is generated
+    items: Modules<Items>,
+}
+
+type State<'ns> = WithNsContext<'ns, OpenApiGenState, OpenApiConfig, OpenApiGenerator>;
+
+#[derive(serde::Serialize)]
+struct Document {
+    openapi: &'static str,
+    info: Info,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    servers: Vec<Server>,
+    paths: Map<String, Value>,
+    components: Components,
+}
+
+#[derive(serde::Serialize)]
+struct Info {
+    title: String,
+    version: String,
+}
+
+#[derive(serde::Serialize)]
+struct Server {
+    url: String,
+}
+
+#[derive(serde::Serialize)]
+struct Components {
+    schemas: Map<String, Value>,
+}
+
+impl OpenApiConfig {
+    /// the path every operation of `ns` is placed under, e.g. `/abc/corp/test`
+    fn prefix(
+        &self,
+        ns: &crate::Ident,
+    ) -> String {
+        match self.prefixes.get(ns) {
+            Some(prefix) => prefix.trim_end_matches('/').to_string(),
+            None => format!("/{}", ns.to_string().replace('.', "/")),
+        }
+    }
+}
+
+impl Generate<OpenApiGenState, OpenApiConfig> for OpenApiGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<OpenApiConfig>,
+    ) -> OpenApiGenState {
+        OpenApiGenState {
+            items: Default::default(),
+        }
+    }
+
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<OpenApiConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        for ns in ctx.namespaces.values() {
+            let ctx = ctx_ns.get(&ns.name).unwrap();
+            let items = ctx.state.items.take(&ns.name);
+            let document = Document {
+                openapi: VERSION,
+                info: Info {
+                    title: opts
+                        .opts
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| ns.name.to_string()),
+                    version: ns.version.to_string(),
+                },
+                servers: opts
+                    .opts
+                    .servers
+                    .iter()
+                    .map(|url| Server { url: url.clone() })
+                    .collect(),
+                paths: items.paths,
+                components: Components {
+                    schemas: items.schemas,
+                },
+            };
+            let document = serde_json::to_string_pretty(&document)?;
+            ctx.with_file_handle(ctx.ns_file(), |w| writeln!(w, "{document}"))?;
+        }
+        Ok(())
+    }
+
+    /// operations are posted their inputs, and respond with their outputs or their error
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, SCHEMAS);
+        let versioned = state.ns.ops.is_versioned(&def.meta.name);
+        let name = def.meta.name.to_string();
+
+        let mut path = state.opts.opts.prefix(&state.ns.name);
+        if versioned {
+            path.push_str(&format!("/v{}", def.meta.version));
+        }
+        path.push_str(&format!("/{name}"));
+
+        let mut responses = Map::new();
+        let mut success = json!({ "description": format!("the outputs of `{name}`") });
+        if !def.outputs.is_empty() {
+            success["content"] = content(schemas.fields(&def.outputs));
+        }
+        responses.insert("200".into(), success);
+
+        if !def.infallible
+            && let Some(error) = &def.error
+        {
+            let variants = variants(state, error)
+                .map(|it| format!(", one of {it}"))
+                .unwrap_or_default();
+            responses.insert(
+                "default".into(),
+                json!({
+                    "description": format!("the error of `{name}`{variants}"),
+                    "content": content(json!({ "$ref": schemas.reference(error) })),
+                }),
+            );
+        }
+
+        let mut post = Map::new();
+        post.insert(
+            "operationId".into(),
+            def_name(&def.meta.key(), versioned).into(),
+        );
+        if let Some(description) = &def.meta.description {
+            post.insert("description".into(), description.trim().into());
+        }
+        if !def.inputs.is_empty() {
+            post.insert(
+                "requestBody".into(),
+                json!({
+                    "required": true,
+                    "content": content(schemas.fields(&def.inputs)),
+                }),
+            );
+        }
+        post.insert("responses".into(), Value::Object(responses));
+
+        self.with_items(state, |items| {
+            items
+                .paths
+                .insert(path, json!({ "post": post }));
+        });
+        Ok(())
+    }
+
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, SCHEMAS);
+        self.schema(state, schemas.struct_name(def), schemas.strct(def));
+        Ok(())
+    }
+
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, SCHEMAS);
+        self.schema(state, schemas.enum_name(def), schemas.enm(def));
+        Ok(())
+    }
+
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, SCHEMAS);
+        self.schema(state, schemas.one_of_name(def), schemas.one_of(def));
+        Ok(())
+    }
+
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let schemas = Schemas::new::<Self>(state.ns, SCHEMAS);
+        self.schema(state, schemas.error_name(def), schemas.error(def));
+        Ok(())
+    }
+}
+
+impl OpenApiGenerator {
+    fn with_items(
+        &self,
+        state: &State<'_>,
+        handle: impl FnOnce(&mut Items),
+    ) {
+        state
+            .state
+            .items
+            .with(&state.ns.name, handle);
+    }
+
+    fn schema(
+        &self,
+        state: &State<'_>,
+        name: String,
+        schema: Value,
+    ) {
+        self.with_items(state, |items| {
+            items.schemas.insert(name, schema);
+        });
+    }
+}
+
+/// the tags of the variants of an error declared in the same namespace
+fn variants(
+    state: &State<'_>,
+    error: &crate::Ident,
+) -> Option<String> {
+    let (qualifier, name) = error.split_qualified();
+    if qualifier.is_some_and(|it| it != state.ns.name.to_string()) {
+        return None;
+    }
+    let name = crate::Ident::new(name);
+    let (name, version) = name.split_version();
+    let (_, error) = state
+        .ns
+        .errors
+        .resolve(&name.into(), version.as_ref())?;
+    Some(
+        error
+            .variants
+            .keys()
+            .map(|it| format!("`{}`", tag(it)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+fn content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
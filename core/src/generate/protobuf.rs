@@ -0,0 +1,720 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    io::Write,
+    path::Path,
+};
+
+use convert_case::Casing;
+
+use crate::{
+    CompoundType, Enum, ErrorTy, FieldsList, Key, Named, OneOf, OneOfVariant, Operation, StrOrInt,
+    Struct, Type,
+    generate::{
+        GenOpts, Generate, LanguageTrait, ProtobufConfig,
+        common::{Module, Modules, file_name, name, pascal, versioned_name},
+        context::WithNsContext,
+        files::WithFlush,
+    },
+    namespace::{Declarations, Namespace},
+};
+
+/// the largest field number protobuf allows
+const MAX_TAG: u32 = (1 << 29) - 1;
+
+/// field numbers reserved by protobuf itself
+const RESERVED_TAGS: std::ops::RangeInclusive<u32> = 19000..=19999;
+
+const INDENT: &str = "  ";
+
+pub struct ProtobufGenerator;
+
+impl LanguageTrait for ProtobufGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Snake
+    }
+
+    fn file_ext() -> &'static str {
+        "proto"
+    }
+}
+
+#[derive(Default)]
+struct File {
+    /// files imported by the declarations of a namespace
+    imports: BTreeSet<String>,
+    body: String,
+    rpcs: String,
+}
+
+impl Module for File {
+    type Refs<'ns> = Types<'ns>;
+
+    fn merge(
+        &mut self,
+        refs: Types<'_>,
+        written: Self,
+    ) {
+        self.imports.extend(refs.imports);
+        self.body.push_str(&written.body);
+        self.rpcs.push_str(&written.rpcs);
+    }
+}
+
+pub(crate) struct ProtobufGenState {
+    /// the file of every namespace, written once every declaration is generated so that imports
+    /// and the service lead and trail it
+    files: Modules<File>,
+}
+
+type State<'ns> = WithNsContext<'ns, ProtobufGenState, ProtobufConfig, ProtobufGenerator>;
+
+impl Generate<ProtobufGenState, ProtobufConfig> for ProtobufGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<ProtobufConfig>,
+    ) -> ProtobufGenState {
+        ProtobufGenState {
+            files: Default::default(),
+        }
+    }
+
+    #[allow(unused)]
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<ProtobufConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        for ns in ctx.namespaces.values() {
+            let ctx = ctx_ns.get(&ns.name).unwrap();
+            let file = ctx.state.files.take(&ns.name);
+
+            let mut out = format!(
+                "// Code generated by operation-api. DO NOT EDIT.\n\nsyntax = \"proto3\";\n\npackage {};\n\n",
+                ns.name
+            );
+            for import in &file.imports {
+                writeln!(out, "import \"{import}\";").unwrap();
+            }
+            if !file.imports.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&file.body);
+            if !file.rpcs.is_empty() {
+                writeln!(out, "service {} {{\n{}}}\n", service_name(ns), file.rpcs).unwrap();
+            }
+
+            ctx.with_file_handle(ctx.ns_file(), |w| writeln!(w, "{}", out.trim_end()))?;
+        }
+        Ok(())
+    }
+
+    /// operations are an rpc of the service of their namespace, taking a request of their inputs
+    /// and returning a response of their outputs
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        let versioned = state.ns.ops.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_file(state, |proto, file| {
+            let inputs = proto.members(&format!("{name}Request"), &def.inputs)?;
+            let outputs = proto.members(&format!("{name}Response"), &def.outputs)?;
+            file.body
+                .push_str(&proto.message(&format!("{name}Request"), &None, &inputs));
+            file.body
+                .push_str(&proto.message(&format!("{name}Response"), &None, &outputs));
+
+            file.rpcs
+                .push_str(&comment(&def.meta.description, INDENT));
+            if !def.infallible
+                && let Some(error) = &def.error
+            {
+                // errors are carried by the details of the status an rpc fails with
+                writeln!(
+                    file.rpcs,
+                    "{INDENT}// fails with {}",
+                    proto.reference(error)
+                )
+                .unwrap();
+            }
+            writeln!(
+                file.rpcs,
+                "{INDENT}rpc {name}({name}Request) returns ({name}Response);"
+            )
+            .unwrap();
+            Ok(())
+        })
+    }
+
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let versioned = state.ns.defs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_file(state, |proto, file| {
+            let members = proto.members(&name, &def.fields)?;
+            file.body
+                .push_str(&proto.message(&name, &def.meta.description, &members));
+            Ok(())
+        })
+    }
+
+    /// enum values are scoped to their package, so are prefixed by the name of their enum. the
+    /// first value of a proto3 enum must be zero, which is added as `UNSPECIFIED` when missing.
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let versioned = state.ns.enums.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        let prefix = name.to_case(convert_case::Case::UpperSnake);
+
+        let mut values = vec![];
+        let mut errors = vec![];
+        for var in def.variants.values() {
+            let value = match var.value {
+                StrOrInt::Int(value) => {
+                    i32::try_from(value).map_err(|_| {
+                        crate::Error::InvalidEnumValue {
+                            variant: var.meta.name.clone(),
+                            site: name.clone(),
+                            value,
+                            reason: "enum values must fit in an int32".into(),
+                        }
+                    })
+                },
+                // string enums are numbered by their tag, as their values are strings
+                StrOrInt::String(..) => {
+                    match var.tag {
+                        Some(tag) => {
+                            i32::try_from(tag).map_err(|_| {
+                                crate::Error::InvalidVariantTag {
+                                    variant: var.meta.name.clone(),
+                                    site: name.clone(),
+                                    tag,
+                                    reason: "enum values must fit in an int32".into(),
+                                }
+                            })
+                        },
+                        None => {
+                            Err(crate::Error::MissingVariantTag {
+                                variant: var.meta.name.clone(),
+                                site: name.clone(),
+                            })
+                        },
+                    }
+                },
+            };
+            match value {
+                Ok(value) => values.push((var, value)),
+                Err(err) => errors.push(err),
+            }
+        }
+        match errors.len() {
+            0 => {},
+            1 => return Err(errors.remove(0)),
+            _ => return Err(crate::Error::Many(errors)),
+        }
+        // the default of a proto3 enum is its first value, which must be zero
+        values.sort_by_key(|(_, value)| *value != 0);
+
+        let mut out = comment(&def.meta.description, "");
+        writeln!(out, "enum {name} {{").unwrap();
+        if !values.iter().any(|(_, value)| *value == 0) {
+            writeln!(out, "{INDENT}{prefix}_UNSPECIFIED = 0;").unwrap();
+        }
+        for (var, value) in values {
+            let variant = var
+                .meta
+                .name
+                .to_string()
+                .to_case(convert_case::Case::UpperSnake);
+            out.push_str(&comment(&var.meta.description, INDENT));
+            writeln!(out, "{INDENT}{prefix}_{variant} = {value};").unwrap();
+        }
+        out.push_str("}\n\n");
+
+        self.with_file(state, |_, file| {
+            file.body.push_str(&out);
+            Ok(())
+        })
+    }
+
+    /// one ofs are a message holding a `oneof` of their variants, numbered by their tag
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let versioned = state.ns.one_ofs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_file(state, |proto, file| {
+            let members = proto.variants(&name, &def.variants)?;
+            file.body
+                .push_str(&proto.one_of(&name, &def.meta.description, &members));
+            Ok(())
+        })
+    }
+
+    /// errors are a message holding a `oneof` of their variants, numbered by their tag
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let versioned = state.ns.errors.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_file(state, |proto, file| {
+            let members = proto.variants(&name, &def.variants)?;
+            file.body
+                .push_str(&proto.one_of(&name, &def.meta.description, &members));
+            Ok(())
+        })
+    }
+}
+
+impl ProtobufGenerator {
+    fn with_file(
+        &self,
+        state: &State<'_>,
+        handle: impl FnOnce(&mut Types<'_>, &mut File) -> super::Result<()>,
+    ) -> super::Result<()> {
+        let types = Types {
+            ns: state.ns,
+            imports: BTreeSet::new(),
+        };
+        state
+            .state
+            .files
+            .write(&state.ns.name, types, handle)
+    }
+}
+
+/// a field of a message, or a variant of a `oneof`
+struct Member<'a> {
+    name: String,
+    ty: &'a Type,
+    tag: u32,
+    description: &'a Option<String>,
+}
+
+/// whether members are the fields of a message or the variants of a `oneof`, as reported when
+/// their tags are missing or invalid
+#[derive(Clone, Copy)]
+enum Of {
+    Field,
+    Variant,
+}
+
+/// a member, along with the tag it declares
+struct Tagged<'a> {
+    ident: &'a crate::Ident,
+    tag: Option<u32>,
+    name: String,
+    ty: &'a Type,
+    description: &'a Option<String>,
+}
+
+/// numbers members by their tag. every missing or invalid tag is reported at once.
+fn numbered<'a>(
+    of: Of,
+    site: &str,
+    tagged: impl Iterator<Item = Tagged<'a>>,
+) -> super::Result<Vec<Member<'a>>> {
+    let mut members = vec![];
+    let mut errors = vec![];
+    let mut seen = BTreeMap::new();
+    for it in tagged {
+        let Some(tag) = it.tag else {
+            errors.push(match of {
+                Of::Field => {
+                    crate::Error::MissingFieldTag {
+                        field: it.ident.clone(),
+                        site: site.into(),
+                    }
+                },
+                Of::Variant => {
+                    crate::Error::MissingVariantTag {
+                        variant: it.ident.clone(),
+                        site: site.into(),
+                    }
+                },
+            });
+            continue;
+        };
+        let reason = if tag > MAX_TAG {
+            Some(format!("field numbers may be at most {MAX_TAG}"))
+        } else if RESERVED_TAGS.contains(&tag) {
+            Some(format!(
+                "field numbers {} through {} are reserved",
+                RESERVED_TAGS.start(),
+                RESERVED_TAGS.end()
+            ))
+        } else {
+            seen.insert(tag, it.ident)
+                .map(|other| format!("it is already the number of {other}"))
+        };
+        if let Some(reason) = reason {
+            errors.push(match of {
+                Of::Field => {
+                    crate::Error::InvalidFieldTag {
+                        field: it.ident.clone(),
+                        site: site.into(),
+                        tag,
+                        reason,
+                    }
+                },
+                Of::Variant => {
+                    crate::Error::InvalidVariantTag {
+                        variant: it.ident.clone(),
+                        site: site.into(),
+                        tag,
+                        reason,
+                    }
+                },
+            });
+            continue;
+        }
+        members.push(Member {
+            name: it.name,
+            ty: it.ty,
+            tag,
+            description: it.description,
+        });
+    }
+
+    match errors.len() {
+        0 => Ok(members),
+        1 => Err(errors.remove(0)),
+        _ => Err(crate::Error::Many(errors)),
+    }
+}
+
+/// a field as declared in protobuf, along with any message nested to hold it
+struct Declared {
+    label: Option<&'static str>,
+    ty: String,
+    nested: Option<String>,
+}
+
+/// names the types of a namespace, noting every file they import
+struct Types<'ns> {
+    ns: &'ns Namespace,
+    imports: BTreeSet<String>,
+}
+
+impl Types<'_> {
+    /// the members of a message of `fields`, numbered by their tag
+    fn members<'a>(
+        &self,
+        site: &str,
+        fields: &'a FieldsList,
+    ) -> super::Result<Vec<Member<'a>>> {
+        let tagged = fields.iter().map(|(ident, field)| {
+            let field = field.unwrap_value();
+            // the output of an operation returning a single value has no declaration to tag
+            let tag = match (field.tag, &field.meta.name) {
+                (None, None) if fields.len() == 1 => Some(1),
+                (tag, _) => tag,
+            };
+            Tagged {
+                ident,
+                tag,
+                name: ident.to_string(),
+                ty: &field.ty,
+                description: &field.meta.description,
+            }
+        });
+        numbered(Of::Field, site, tagged)
+    }
+
+    /// the members of the `oneof` of `variants`, numbered by their tag
+    fn variants<'a>(
+        &self,
+        site: &str,
+        variants: &'a Named<OneOfVariant>,
+    ) -> super::Result<Vec<Member<'a>>> {
+        let tagged = variants.iter().map(|(ident, var)| {
+            Tagged {
+                ident,
+                tag: var.tag,
+                name: ident
+                    .to_string()
+                    .to_case(convert_case::Case::Snake),
+                ty: &var.ty,
+                description: &var.description,
+            }
+        });
+        numbered(Of::Variant, site, tagged)
+    }
+
+    fn message(
+        &mut self,
+        name: &str,
+        description: &Option<String>,
+        members: &[Member<'_>],
+    ) -> String {
+        let mut fields = String::new();
+        let mut nested = String::new();
+        for member in members {
+            let declared = self.declare(&member.name, member.ty, false);
+            fields.push_str(&comment(member.description, INDENT));
+            let label = declared
+                .label
+                .map(|it| format!("{it} "))
+                .unwrap_or_default();
+            writeln!(
+                fields,
+                "{INDENT}{label}{} {} = {};",
+                declared.ty, member.name, member.tag
+            )
+            .unwrap();
+            if let Some(message) = declared.nested {
+                nested.push_str(&indent(&message));
+            }
+        }
+
+        let mut out = comment(description, "");
+        match (fields.is_empty(), nested.is_empty()) {
+            (true, _) => writeln!(out, "message {name} {{}}\n").unwrap(),
+            (false, true) => writeln!(out, "message {name} {{\n{fields}}}\n").unwrap(),
+            (false, false) => {
+                writeln!(
+                    out,
+                    "message {name} {{\n{fields}\n{}\n}}\n",
+                    nested.trim_end()
+                )
+                .unwrap()
+            },
+        }
+        out
+    }
+
+    fn one_of(
+        &mut self,
+        name: &str,
+        description: &Option<String>,
+        members: &[Member<'_>],
+    ) -> String {
+        let mut variants = String::new();
+        let mut nested = String::new();
+        for member in members {
+            let declared = self.declare(&member.name, member.ty, true);
+            variants.push_str(&comment(member.description, &INDENT.repeat(2)));
+            writeln!(
+                variants,
+                "{INDENT}{INDENT}{} {} = {};",
+                declared.ty, member.name, member.tag
+            )
+            .unwrap();
+            if let Some(message) = declared.nested {
+                nested.push_str(&indent(&message));
+            }
+        }
+
+        let mut out = comment(description, "");
+        write!(
+            out,
+            "message {name} {{\n{INDENT}oneof value {{\n{variants}{INDENT}}}\n"
+        )
+        .unwrap();
+        if !nested.is_empty() {
+            write!(out, "\n{}\n", nested.trim_end()).unwrap();
+        }
+        out.push_str("}\n\n");
+        out
+    }
+
+    /// declares a field `name` holding `ty`. repeated fields may be neither optional nor
+    /// repeated themselves, and `oneof`s may hold neither, so these are held by a message nested
+    /// in the declaring one.
+    fn declare(
+        &mut self,
+        name: &str,
+        ty: &Type,
+        in_one_of: bool,
+    ) -> Declared {
+        match ty {
+            Type::CompoundType(CompoundType::Option { ty }) => {
+                let mut declared = self.declare(name, ty, in_one_of);
+                // every message and `oneof` variant has presence already, and a missing
+                // repeated field reads as empty
+                if declared.label.is_none() && !in_one_of {
+                    declared.label = Some("optional");
+                }
+                declared
+            },
+            Type::CompoundType(
+                CompoundType::Array { ty: item } | CompoundType::SizedArray { ty: item, .. },
+            ) => {
+                let item = strip_option(item);
+                match (in_one_of, item) {
+                    (
+                        false,
+                        Type::CompoundType(
+                            CompoundType::Array { .. } | CompoundType::SizedArray { .. },
+                        ),
+                    )
+                    | (true, _) => {
+                        let wrapper = format!("{}Items", name.to_case(convert_case::Case::Pascal));
+                        // a `oneof` variant is held as a whole, while each item of a
+                        // repeated field is held on its own
+                        let members = [Member {
+                            name: "items".into(),
+                            ty: match in_one_of {
+                                true => ty,
+                                false => item,
+                            },
+                            tag: 1,
+                            description: &None,
+                        }];
+                        let message = self.message(&wrapper, &None, &members);
+                        Declared {
+                            label: (!in_one_of).then_some("repeated"),
+                            ty: wrapper,
+                            nested: Some(message),
+                        }
+                    },
+                    (false, item) => {
+                        Declared {
+                            label: Some("repeated"),
+                            ty: self.ty(item),
+                            nested: None,
+                        }
+                    },
+                }
+            },
+            ty => {
+                Declared {
+                    label: None,
+                    ty: self.ty(ty),
+                    nested: None,
+                }
+            },
+        }
+    }
+
+    /// the type of a singular value of `ty`
+    fn ty(
+        &mut self,
+        ty: &Type,
+    ) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::U8 | Type::U16 | Type::U32 => "uint32".into(),
+            Type::U64 | Type::Usize => "uint64".into(),
+            Type::I8 | Type::I16 | Type::I32 => "int32".into(),
+            Type::I64 => "int64".into(),
+            Type::F32 => "float".into(),
+            Type::F64 => "double".into(),
+            Type::String => "string".into(),
+            Type::Binary => "bytes".into(),
+            Type::DateTime => self.well_known("Timestamp", "timestamp"),
+            Type::Complex => self.well_known("Value", "struct"),
+            Type::Never => self.well_known("Empty", "empty"),
+            Type::CompoundType(ty) => {
+                match ty {
+                    CompoundType::Enum { to }
+                    | CompoundType::OneOf { to }
+                    | CompoundType::Struct { to } => self.reference(to),
+                    CompoundType::Option { ty }
+                    | CompoundType::Array { ty }
+                    | CompoundType::SizedArray { ty, .. } => self.ty(ty),
+                }
+            },
+        }
+    }
+
+    fn well_known(
+        &mut self,
+        name: &str,
+        file: &str,
+    ) -> String {
+        self.imports
+            .insert(format!("google/protobuf/{file}.proto"));
+        format!("google.protobuf.{name}")
+    }
+
+    /// references into another namespace are fully qualified by its package, so that they may
+    /// not resolve to a field of the same name, and import its file
+    fn reference(
+        &mut self,
+        to: &crate::Ident,
+    ) -> String {
+        let (qualifier, name) = to.split_qualified();
+        let name = crate::Ident::new(name);
+        let (name, version) = name.split_version();
+        let name = match version {
+            Some(version) => versioned_name(&Key::new(name, version)),
+            None => pascal(name),
+        };
+        match qualifier {
+            Some(qualifier) if qualifier != self.ns.name.to_string() => {
+                self.imports
+                    .insert(file_name::<ProtobufGenerator>(qualifier));
+                format!(".{qualifier}.{name}")
+            },
+            _ => name,
+        }
+    }
+}
+
+fn strip_option(ty: &Type) -> &Type {
+    match ty {
+        Type::CompoundType(CompoundType::Option { ty }) => strip_option(ty),
+        ty => ty,
+    }
+}
+
+/// the service holding the operations of `ns`, named after its last segment
+fn service_name(ns: &Namespace) -> String {
+    let name = ns.name.to_string();
+    let last = name.rsplit('.').next().unwrap_or(&name);
+    format!("{}Service", pascal(last))
+}
+
+fn indent(out: &str) -> String {
+    out.lines()
+        .map(|line| {
+            match line {
+                "" => "\n".into(),
+                line => format!("{INDENT}{line}\n"),
+            }
+        })
+        .collect()
+}
+
+fn comment(
+    description: &Option<String>,
+    indent: &str,
+) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    description
+        .trim()
+        .lines()
+        .map(|line| {
+            match line.trim() {
+                "" => format!("{indent}//\n"),
+                line => format!("{indent}// {line}\n"),
+            }
+        })
+        .collect()
+}
@@ -1 +1,524 @@
-//
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    io::Write,
+    path::Path,
+};
+
+use convert_case::Casing;
+
+use crate::{
+    CompoundType, Enum, ErrorTy, FieldsList, Key, OneOf, Operation, StrOrInt, Struct, Type,
+    generate::{
+        GenOpts, Generate, LanguageTrait, PythonConfig,
+        common::{
+            Module, Modules, inline, inline_variant, latest, literal, name, pascal, tag,
+            versioned_name,
+        },
+        context::WithNsContext,
+        files::WithFlush,
+    },
+    namespace::{Declarations, Namespace},
+};
+
+const HEADER: &str = r#"from __future__ import annotations
+
+from datetime import datetime
+from enum import Enum, IntEnum
+from typing import Annotated, Any, Literal, Optional, Union
+
+from pydantic import BaseModel, ConfigDict, Field, model_serializer, model_validator
+"#;
+
+/// the base of error variants holding a model, as these are serialized with its fields next to
+/// their `type`
+const FLATTENED: &str = r#"class _Flattened(BaseModel):
+    """holds a model as `value`, serialized with its fields next to the `type` of its variant"""
+
+    @model_validator(mode="before")
+    @classmethod
+    def _nest(cls, data: Any) -> Any:
+        if isinstance(data, dict) and ("value" not in data or data.keys() - {"type", "value"}):
+            value = {key: it for key, it in data.items() if key != "type"}
+            data = {**({"type": data["type"]} if "type" in data else {}), "value": value}
+        return data
+
+    @model_serializer(mode="wrap")
+    def _flatten(self, handler: Any) -> Any:
+        data = handler(self)
+        return {"type": data["type"], **data["value"]}
+"#;
+
+pub struct PythonGenerator;
+
+impl LanguageTrait for PythonGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Snake
+    }
+
+    fn file_ext() -> &'static str {
+        "py"
+    }
+}
+
+#[derive(Default)]
+struct PyModule {
+    /// namespaces referred to, imported as sibling modules
+    imports: BTreeSet<String>,
+    /// whether an error variant holds a model
+    flattened: bool,
+    body: String,
+}
+
+impl Module for PyModule {
+    type Refs<'ns> = Types<'ns>;
+
+    fn merge(
+        &mut self,
+        refs: Types<'_>,
+        written: Self,
+    ) {
+        self.imports.extend(refs.imports);
+        self.flattened |= refs.flattened;
+        self.body.push_str(&written.body);
+    }
+}
+
+pub(crate) struct PythonGenState {
+    /// the module of every namespace, written once every declaration is generated so that
+    /// imports of other namespaces lead it
+    modules: Modules<PyModule>,
+}
+
+type State<'ns> = WithNsContext<'ns, PythonGenState, PythonConfig, PythonGenerator>;
+
+impl Generate<PythonGenState, PythonConfig> for PythonGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<PythonConfig>,
+    ) -> PythonGenState {
+        PythonGenState {
+            modules: Default::default(),
+        }
+    }
+
+    #[allow(unused)]
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<PythonConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        for ns in ctx.namespaces.values() {
+            let ctx = ctx_ns.get(&ns.name).unwrap();
+            let mut module = ctx.state.modules.take(&ns.name);
+
+            let keys = latest(&ns.defs)
+                .chain(latest(&ns.enums))
+                .chain(latest(&ns.one_ofs))
+                .chain(latest(&ns.errors));
+            for key in keys {
+                let name = pascal(&key.name.to_string());
+                writeln!(module.body, "{name} = {}\n", versioned_name(key)).unwrap();
+            }
+
+            let mut out = String::from(HEADER);
+            if module.flattened {
+                write!(out, "\n\n{FLATTENED}").unwrap();
+            }
+            write!(out, "\n\n{}\n", module.body.trim_end()).unwrap();
+            // references are resolved once every model is declared, so that namespaces may
+            // import each other
+            if !module.imports.is_empty() {
+                out.push_str(
+                    "\n\n# namespaces are imported last, as they may import this one in turn\n",
+                );
+            }
+            for import in &module.imports {
+                writeln!(out, "from . import {import}").unwrap();
+            }
+
+            ctx.with_file_handle(ctx.ns_file(), |w| write!(w, "{out}"))?;
+        }
+
+        // namespaces import each other as modules of a package
+        if let Some(ctx) = ctx_ns.values().next() {
+            ctx.with_file_handle(ctx.path_for_file("__init__"), |_| Ok(()))?;
+        }
+        Ok(())
+    }
+
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        let versioned = state.ns.ops.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |py, out| {
+            py.model(
+                out,
+                &format!("{name}Request"),
+                &def.meta.description,
+                None,
+                &def.inputs,
+            );
+            py.model(
+                out,
+                &format!("{name}Response"),
+                &def.meta.description,
+                None,
+                &def.outputs,
+            );
+        });
+        Ok(())
+    }
+
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let versioned = state.ns.defs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        // the fields of an error variant declared inline are tagged by the variant, so that the
+        // model is itself the variant
+        let tag = inline_variant(state.ns, def);
+        self.with_module(state, |py, out| {
+            py.model(out, &name, &def.meta.description, tag, &def.fields);
+        });
+        Ok(())
+    }
+
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let versioned = state.ns.enums.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        let base = match def
+            .variants
+            .values()
+            .all(|it| matches!(it.value, StrOrInt::Int(..)))
+        {
+            true => "IntEnum",
+            false => "str, Enum",
+        };
+        self.with_module(state, |_, out| {
+            writeln!(out, "class {name}({base}):").unwrap();
+            out.push_str(&docstring(&def.meta.description, "    "));
+            for var in def.variants.values() {
+                let value = match &var.value {
+                    StrOrInt::Int(value) => value.to_string(),
+                    StrOrInt::String(value) => literal(value),
+                };
+                let member = var
+                    .meta
+                    .name
+                    .to_string()
+                    .to_case(convert_case::Case::UpperSnake);
+                writeln!(out, "    {member} = {value}").unwrap();
+                out.push_str(&docstring(&var.meta.description, "    "));
+            }
+            out.push_str("\n\n");
+        });
+        Ok(())
+    }
+
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let versioned = state.ns.one_ofs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |py, out| {
+            py.quote = true;
+            let variants = def
+                .variants
+                .values()
+                .map(|var| py.ty(&var.ty))
+                .collect::<Vec<_>>()
+                .join(", ");
+            out.push_str(&comment(&def.meta.description));
+            writeln!(out, "{name} = Union[{variants}]\n\n").unwrap();
+        });
+        Ok(())
+    }
+
+    /// each variant holds its value as `value`, flattened where it is a model, and the union of
+    /// them is raised within an exception of its own
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let versioned = state.ns.errors.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |py, out| {
+            let mut variants = vec![];
+            for (ident, var) in def.variants.iter() {
+                let mut variant = format!("{name}{}", pascal(&ident.to_string()));
+                if inline(var).is_some() {
+                    variants.push(variant);
+                    continue;
+                }
+                let ty = py.ty(&var.ty);
+                // a model may be named as the variant holding it, e.g. `TooLarge(FailureTooLarge)`
+                if ty == variant {
+                    variant.push_str("Variant");
+                }
+                let tag = literal(&tag(ident));
+                // models are not extended, as they may be declared later or in a namespace which
+                // is not yet imported
+                let base = match &var.ty {
+                    Type::CompoundType(CompoundType::Struct { .. }) => {
+                        py.flattened = true;
+                        "_Flattened"
+                    },
+                    _ => "BaseModel",
+                };
+                writeln!(out, "class {variant}({base}):").unwrap();
+                out.push_str(&docstring(&var.description, "    "));
+                writeln!(out, "    type: Literal[{tag}] = {tag}").unwrap();
+                writeln!(out, "    value: {ty}").unwrap();
+                out.push_str("\n\n");
+                variants.push(variant);
+            }
+
+            out.push_str(&comment(&def.meta.description));
+            writeln!(
+                out,
+                "{name} = Annotated[Union[{}], Field(discriminator=\"type\")]\n\n",
+                variants.join(", ")
+            )
+            .unwrap();
+
+            writeln!(out, "class {name}Error(Exception):").unwrap();
+            writeln!(out, "    \"\"\"raised with a `{name}`\"\"\"\n").unwrap();
+            writeln!(out, "    def __init__(self, error: {name}) -> None:").unwrap();
+            writeln!(out, "        super().__init__(error)").unwrap();
+            writeln!(out, "        self.error = error\n\n").unwrap();
+        });
+        Ok(())
+    }
+}
+
+impl PythonGenerator {
+    fn with_module(
+        &self,
+        state: &State<'_>,
+        handle: impl FnOnce(&mut Types<'_>, &mut String),
+    ) {
+        let types = Types {
+            ns: state.ns,
+            imports: BTreeSet::new(),
+            flattened: false,
+            quote: false,
+        };
+        state
+            .state
+            .modules
+            .write(&state.ns.name, types, |types, module| {
+                handle(types, &mut module.body)
+            });
+    }
+}
+
+/// names the types of a namespace, noting every other namespace they refer to
+struct Types<'ns> {
+    ns: &'ns Namespace,
+    imports: BTreeSet<String>,
+    flattened: bool,
+    /// references are quoted within aliases, as these are evaluated before later declarations
+    quote: bool,
+}
+
+impl Types<'_> {
+    fn model(
+        &mut self,
+        out: &mut String,
+        name: &str,
+        description: &Option<String>,
+        tag: Option<String>,
+        fields: &FieldsList,
+    ) {
+        writeln!(out, "class {name}(BaseModel):").unwrap();
+        let doc = docstring(description, "    ");
+        out.push_str(&doc);
+        if let Some(tag) = &tag {
+            let tag = literal(tag);
+            writeln!(out, "    type: Literal[{tag}] = {tag}").unwrap();
+        }
+
+        let keywords = fields
+            .keys()
+            .any(|it| KEYWORDS.contains(&it.to_string().as_str()));
+        if keywords {
+            writeln!(
+                out,
+                "    model_config = ConfigDict(populate_by_name=True)\n"
+            )
+            .unwrap();
+        }
+
+        for (name, field) in fields.iter() {
+            let field = field.unwrap_value();
+            let name = name.to_string();
+            let ty = self.ty(&field.ty);
+            // keywords are suffixed, and read and written by their alias
+            let (attr, alias) = match KEYWORDS.contains(&name.as_str()) {
+                true => (format!("{name}_"), Some(literal(&name))),
+                false => (name, None),
+            };
+            let optional = matches!(field.ty, Type::CompoundType(CompoundType::Option { .. }));
+            match (optional, alias) {
+                (false, None) => writeln!(out, "    {attr}: {ty}"),
+                (true, None) => writeln!(out, "    {attr}: {ty} = None"),
+                (false, Some(alias)) => writeln!(out, "    {attr}: {ty} = Field(alias={alias})"),
+                (true, Some(alias)) => {
+                    writeln!(out, "    {attr}: {ty} = Field(None, alias={alias})")
+                },
+            }
+            .unwrap();
+            out.push_str(&docstring(&field.meta.description, "    "));
+        }
+        if fields.is_empty() && doc.is_empty() && !keywords && tag.is_none() {
+            writeln!(out, "    pass").unwrap();
+        }
+        out.push_str("\n\n");
+    }
+
+    /// the type of a value of `ty`
+    fn ty(
+        &mut self,
+        ty: &Type,
+    ) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::U8
+            | Type::U16
+            | Type::U32
+            | Type::U64
+            | Type::Usize
+            | Type::I8
+            | Type::I16
+            | Type::I32
+            | Type::I64 => "int".into(),
+            Type::F32 | Type::F64 => "float".into(),
+            Type::String => "str".into(),
+            Type::DateTime => "datetime".into(),
+            Type::Binary => "list[int]".into(),
+            Type::Complex => "Any".into(),
+            Type::Never => "None".into(),
+            Type::CompoundType(ty) => {
+                match ty {
+                    CompoundType::Enum { to }
+                    | CompoundType::OneOf { to }
+                    | CompoundType::Struct { to } => self.reference(to),
+                    CompoundType::Option { ty } => format!("Optional[{}]", self.ty(ty)),
+                    CompoundType::Array { ty } => format!("list[{}]", self.ty(ty)),
+                    CompoundType::SizedArray { size, ty } => {
+                        format!(
+                            "Annotated[list[{}], Field(min_length={size}, max_length={size})]",
+                            self.ty(ty)
+                        )
+                    },
+                }
+            },
+        }
+    }
+
+    /// references into another namespace go through the import of its module
+    fn reference(
+        &mut self,
+        to: &crate::Ident,
+    ) -> String {
+        let (qualifier, name) = to.split_qualified();
+        let name = crate::Ident::new(name);
+        let (name, version) = name.split_version();
+        let name = match version {
+            Some(version) => versioned_name(&Key::new(name, version)),
+            None => pascal(name),
+        };
+        let reference = match qualifier {
+            Some(qualifier) if qualifier != self.ns.name.to_string() => {
+                let module = crate::namespace::normalized_path::<PythonGenerator>(qualifier);
+                let reference = format!("{module}.{name}");
+                self.imports.insert(module);
+                reference
+            },
+            _ => name,
+        };
+        match self.quote {
+            true => literal(&reference),
+            false => reference,
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+fn docstring(
+    description: &Option<String>,
+    indent: &str,
+) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    let description = description
+        .trim()
+        .replace('\\', "\\\\")
+        .replace("\"\"\"", "\\\"\\\"\\\"");
+    let lines = description
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    match lines.as_slice() {
+        [line] => format!("{indent}\"\"\"{line}\"\"\"\n"),
+        lines => {
+            let mut out = format!("{indent}\"\"\"\n");
+            for line in lines {
+                match line.is_empty() {
+                    true => out.push('\n'),
+                    false => writeln!(out, "{indent}{line}").unwrap(),
+                }
+            }
+            writeln!(out, "{indent}\"\"\"").unwrap();
+            out
+        },
+    }
+}
+
+/// type aliases hold no docstring, so are described in a comment
+fn comment(description: &Option<String>) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    description
+        .trim()
+        .lines()
+        .map(|line| {
+            match line.trim() {
+                "" => "#\n".to_string(),
+                line => format!("# {line}\n"),
+            }
+        })
+        .collect()
+}
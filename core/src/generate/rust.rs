@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, io::Write, path::Path};
+use std::{collections::BTreeMap, io::Write, path::Path, sync::Mutex};
 
 use convert_case::Casing;
 use proc_macro2::TokenStream;
@@ -6,12 +6,18 @@ use quote::{ToTokens, quote};
 use syn::{Ident, Lit, LitInt};
 
 use crate::{
-    Contiguous, EnumValueType, ErrorTy, Operation, StrOrInt, Struct,
+    Contiguous, EnumValueType, ErrorTy, Key, Operation, StrOrInt, Struct, Version,
     generate::{
         GenOpts, Generate, LanguageTrait, RustConfig, context::WithNsContext, files::WithFlush,
     },
+    namespace::Declarations,
 };
 
+mod convert;
+mod server;
+
+/// the generated rust types define how every value is serialized, which the other languages
+/// follow
 pub struct RustGenerator;
 
 impl LanguageTrait for RustGenerator {
@@ -24,7 +30,13 @@ impl LanguageTrait for RustGenerator {
     }
 }
 
-pub(crate) struct RustGenState {}
+pub(crate) struct RustGenState {
+    /// items of declarations held in several versions, by namespace and version. these are
+    /// written into a module of their version once every item is generated.
+    versions: Mutex<BTreeMap<(crate::Ident, Version), String>>,
+}
+
+type State<'ns> = WithNsContext<'ns, RustGenState, RustConfig, RustGenerator>;
 
 impl Generate<RustGenState, RustConfig> for RustGenerator {
     #[allow(unused)]
@@ -46,21 +58,46 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
         for ns in ctx.namespaces.values() {
             let ctx = ctx_ns.get(&ns.name).unwrap();
             let mut tt = quote!();
+            let mut modules = quote!();
+
+            let keys = keys(&ctx.ns.defs)
+                .chain(keys(&ctx.ns.enums))
+                .chain(keys(&ctx.ns.one_ofs))
+                .chain(keys(&ctx.ns.errors));
+            for (key, versioned, latest) in keys {
+                let created = def_ident(key.name.clone());
+                if !versioned {
+                    tt.extend(quote!(#created,));
+                    continue;
+                }
 
-            let mut keys = ctx.ns.defs.keys().collect::<Vec<_>>();
-
-            keys.append(&mut ctx.ns.enums.keys().collect());
-            keys.append(&mut ctx.ns.one_ofs.keys().collect());
-            keys.append(&mut ctx.ns.errors.keys().collect());
-            for it in keys {
-                let created = def_ident(it.clone());
-                tt.extend(quote!(
-                    #created,
-                ))
+                let module = version_module(&key.version);
+                tt.extend(quote!(#module::#created,));
+                if latest {
+                    // unpinned references name the latest version
+                    let vis = ctx.opts.opts.vis.as_rust(ctx, &key.name);
+                    modules.extend(quote!(#vis use #module::#created;));
+                }
             }
+
+            let versions = ctx.state.versions.lock().unwrap();
+            let first = (ns.name.clone(), Version::new(0));
+            let versions = versions
+                .range(first..)
+                .take_while(|((it, _), _)| it == &ns.name)
+                .map(|((_, version), items)| {
+                    format!("pub mod {} {{ {items} }}", version_module(version))
+                })
+                .collect::<String>();
+
+            let conversions = convert::conversions(ctx.ns);
             let ns = ns.name.to_string();
-            tt = quote!(operation_api_sdk::namespace! { #ns { #tt }});
-            ctx.with_file_handle(ctx.ns_file(), |w| write!(w, "{tt}"))?;
+            tt = quote!(
+                #modules
+                #conversions
+                operation_api_sdk::namespace! { #ns { #tt }}
+            );
+            ctx.with_file_handle(ctx.ns_file(), |w| write!(w, "{versions}{tt}"))?;
         }
         Ok(())
     }
@@ -70,7 +107,9 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
         &self,
         opts: &GenOpts<RustConfig>,
     ) -> RustGenState {
-        RustGenState {}
+        RustGenState {
+            versions: Default::default(),
+        }
     }
 
     #[allow(unused)]
@@ -89,6 +128,19 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
         Ok(())
     }
 
+    fn gen_server(
+        &self,
+        state: &WithNsContext<'_, RustGenState, RustConfig, Self>,
+    ) -> super::Result<()> {
+        if state.ns.ops.is_empty() {
+            return Ok(());
+        }
+
+        let tt = server::server(state);
+        state.with_file_handle(state.ns_file(), |w| write!(w, "{tt}"))?;
+        Ok(())
+    }
+
     #[allow(unused)]
     fn gen_struct(
         &self,
@@ -98,11 +150,8 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
         let ns_file = state.ns_file();
         let mut tt = quote::quote!();
 
-        let vis = state
-            .opts
-            .opts
-            .vis
-            .as_rust(state, &def.meta.name);
+        let versioned = state.ns.defs.is_versioned(&def.meta.name);
+        let vis = vis(state, &def.meta.name, versioned);
         let desc_comment = def.meta.doc_comment();
         let iden = def.meta.ident_as_pascal();
         let version = def.meta.version();
@@ -120,7 +169,7 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
             let atts = field.ty.rust_attrs();
             fields.extend({
                 let comment = field.meta.doc_comment();
-                let ty = field.ty.ty(&state.opts.opts);
+                let ty = member_ty(state, &def.meta.key(), field_name, &field.ty, versioned);
                 quote!(
                     #[serde(rename = #name)]
 
@@ -143,11 +192,7 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
 
         tracing::info!("writing {} to '{}'", def.meta.name, ns_file.display());
 
-        state.with_file_handle(ns_file, |w| {
-            write!(w, "{tt}")?;
-            Ok(())
-        })?;
-        Ok(())
+        write_item(state, &def.meta.key(), versioned, tt)
     }
 
     fn gen_enum(
@@ -155,15 +200,11 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
         state: &WithNsContext<'_, RustGenState, RustConfig, Self>,
         def: &crate::Enum,
     ) -> super::Result<()> {
-        let ns_file = state.ns_file();
         let mut tt = quote!();
 
         let name = def.meta.ident_as_pascal();
-        let vis = state
-            .opts
-            .opts
-            .vis
-            .as_rust(state, &def.meta.name);
+        let versioned = state.ns.enums.is_versioned(&def.meta.name);
+        let vis = vis(state, &def.meta.name, versioned);
 
         let doc_comment = def.meta.doc_comment();
         let version = def.meta.version();
@@ -228,26 +269,19 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
             }
         });
 
-        state.with_file_handle(ns_file, |w| {
-            write!(w, "{tt}")?;
-            Ok(())
-        })?;
-        Ok(())
+        write_item(state, &def.meta.key(), versioned, tt)
     }
 
+    /// one ofs are untagged, so that each variant is held as its value alone
     fn gen_one_of(
         &self,
         state: &WithNsContext<'_, RustGenState, RustConfig, Self>,
         def: &crate::OneOf,
     ) -> super::Result<()> {
-        let ns_file = state.ns_file();
         let mut tt = quote!();
 
-        let vis = state
-            .opts
-            .opts
-            .vis
-            .as_rust(state, &def.meta.name);
+        let versioned = state.ns.one_ofs.is_versioned(&def.meta.name);
+        let vis = vis(state, &def.meta.name, versioned);
         let name = def.meta.ident_as_pascal();
         let doc_comment = def.meta.doc_comment();
         let version = def.meta.version();
@@ -260,7 +294,7 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
                     iden.to_string()
                         .to_case(convert_case::Case::Pascal),
                 );
-                let ty = var.ty.ty(&state.opts.opts);
+                let ty = member_ty(state, &def.meta.key(), iden, &var.ty, versioned);
                 let vdoc = crate::generate::rust::comment(&var.description);
                 if matches!(var.ty, crate::ty::Type::Never) {
                     quote!( #vdoc #iden_pascal, )
@@ -281,26 +315,19 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
             }
         });
 
-        state.with_file_handle(ns_file, |w| {
-            write!(w, "{tt}")?;
-            Ok(())
-        })?;
-        Ok(())
+        write_item(state, &def.meta.key(), versioned, tt)
     }
 
+    /// errors are tagged by the `type` of their variant, next to the fields of its value
     fn gen_error(
         &self,
         state: &WithNsContext<'_, RustGenState, RustConfig, Self>,
         def: &ErrorTy,
     ) -> super::Result<()> {
-        let ns_file = state.ns_file();
         let mut tt = quote!();
 
-        let vis = state
-            .opts
-            .opts
-            .vis
-            .as_rust(state, &def.meta.name);
+        let versioned = state.ns.errors.is_versioned(&def.meta.name);
+        let vis = vis(state, &def.meta.name, versioned);
         let name = def.meta.ident_as_pascal();
         let doc_comment = def.meta.doc_comment();
         let version = def.meta.version();
@@ -314,7 +341,7 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
                         .to_string()
                         .to_case(convert_case::Case::Pascal),
                 );
-                let ty = variant.ty.ty(&state.opts.opts);
+                let ty = member_ty(state, &def.meta.key(), ident, &variant.ty, versioned);
                 let variant = &variant;
                 let vdoc = crate::generate::rust::comment(&variant.description);
                 quote! { #vdoc #name(#ty), }
@@ -331,12 +358,76 @@ impl Generate<RustGenState, RustConfig> for RustGenerator {
             }
         });
 
-        state.with_file_handle(ns_file, |w| {
-            write!(w, "{tt}")?;
-            Ok(())
-        })?;
-        Ok(())
+        write_item(state, &def.meta.key(), versioned, tt)
+    }
+}
+
+/// members on a reference cycle are boxed, so that the types stay finitely sized
+fn member_ty(
+    state: &State<'_>,
+    parent: &Key,
+    member: &crate::Ident,
+    ty: &crate::Type,
+    versioned: bool,
+) -> TokenStream {
+    let depth = usize::from(versioned);
+    if state.ns.is_recursive(parent, member) {
+        ty.boxed_ty(&state.opts.opts, depth)
+    } else {
+        ty.ty_at(&state.opts.opts, depth)
+    }
+}
+
+/// items in the module of their version stay visible to the module of their namespace
+fn vis(
+    state: &State<'_>,
+    name: &crate::Ident,
+    versioned: bool,
+) -> TokenStream {
+    let vis = state.opts.opts.vis.as_rust(state, name);
+    match versioned && vis.is_empty() {
+        true => quote!(pub(super)),
+        false => vis,
+    }
+}
+
+/// writes an item into the file of its namespace, or holds it for the module of its version
+/// when its declaration is held in several versions
+fn write_item(
+    state: &State<'_>,
+    key: &Key,
+    versioned: bool,
+    tt: TokenStream,
+) -> super::Result<()> {
+    if versioned {
+        state
+            .state
+            .versions
+            .lock()
+            .unwrap()
+            .entry((state.ns.name.clone(), key.version.clone()))
+            .or_default()
+            .push_str(&tt.to_string());
+        return Ok(());
     }
+    state.with_file_handle(state.ns_file(), |w| write!(w, "{tt}"))?;
+    Ok(())
+}
+
+/// every key of a kind of declaration, along with whether it is one of several versions and,
+/// if so, whether it is the latest of them
+fn keys<T>(decls: &BTreeMap<Key, T>) -> impl Iterator<Item = (&Key, bool, bool)> {
+    decls.keys().map(|key| {
+        let latest = decls
+            .versions(&key.name)
+            .last()
+            .is_some_and(|(it, _)| it == key);
+        (key, decls.is_versioned(&key.name), latest)
+    })
+}
+
+fn version_module(version: &Version) -> Ident {
+    ident(format!("v{version}"))
 }
 
 fn def_ident(def: crate::Ident) -> Ident {
@@ -350,6 +441,35 @@ pub(crate) fn ident<D: AsRef<str>>(s: D) -> Ident {
     Ident::new(s.as_ref(), proc_macro2::Span::call_site())
 }
 
+/// a referenced type, pathed through its sibling namespace module when qualified with another
+/// namespace, and through the module of its version when pinned to one. `depth` is the number of
+/// modules below the module of the referring namespace it is named from.
+pub(crate) fn reference(
+    to: &crate::Ident,
+    depth: usize,
+) -> TokenStream {
+    let (ns, name) = to.split_qualified();
+    let name = crate::Ident::new(name);
+    let (name, version) = name.split_version();
+    let name = ident(name);
+
+    let mut path = match version {
+        Some(version) => {
+            let module = version_module(&version);
+            quote!(#module::#name)
+        },
+        None => quote!(#name),
+    };
+    if let Some(ns) = ns {
+        let module = ident(crate::namespace::normalized_path::<RustGenerator>(ns));
+        path = quote!(super::#module::#path);
+    }
+    for _ in 0..depth {
+        path = quote!(super::#path);
+    }
+    path
+}
+
 pub(crate) fn lit(value: String) -> TokenStream {
     let lit = Lit::Int(LitInt::new(&value, proc_macro2::Span::call_site()));
     quote! {#lit}
@@ -372,4 +492,18 @@ mod test {
     fn test_ident() {
         super::ident("SomeStruct");
     }
+
+    #[test_case::test_case("KnownError", 0, "KnownError"; "local")]
+    #[test_case::test_case("abc.corp.test::KnownError", 0, "super :: abc_corp_test :: KnownError"; "qualified")]
+    #[test_case::test_case("KnownError@1", 0, "v1 :: KnownError"; "pinned")]
+    #[test_case::test_case("abc.corp.test::KnownError@2", 0, "super :: abc_corp_test :: v2 :: KnownError"; "qualified pinned")]
+    #[test_case::test_case("KnownError", 1, "super :: KnownError"; "within version")]
+    #[test_case::test_case("abc.corp.test::KnownError@2", 1, "super :: super :: abc_corp_test :: v2 :: KnownError"; "qualified within version")]
+    fn test_reference(
+        to: &str,
+        depth: usize,
+        expect: &str,
+    ) {
+        assert_eq!(super::reference(&to.into(), depth).to_string(), expect);
+    }
 }
@@ -0,0 +1,424 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use convert_case::Casing;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::{def_ident, ident, version_module};
+use crate::{
+    CompoundType, Enum, Key, Struct, Type,
+    diff::integer,
+    namespace::{Declarations, Namespace},
+};
+
+/// how the values of one version of a declaration convert to the next
+enum Conversion {
+    /// every value converts, with `From`
+    Total(TokenStream),
+    /// some values may not convert, with `TryFrom`
+    Partial(TokenStream),
+    /// no value converts mechanically, with a `TryFrom` which always fails
+    Never(String),
+}
+
+/// a pair of consecutive versions of a declaration
+struct Pair<'ns, T> {
+    prev: (&'ns Key, &'ns T),
+    next: (&'ns Key, &'ns T),
+}
+
+fn pairs<T>(decls: &BTreeMap<Key, T>) -> Vec<Pair<'_, T>> {
+    let names = decls
+        .keys()
+        .map(|key| &key.name)
+        .collect::<BTreeSet<_>>();
+    names
+        .into_iter()
+        .flat_map(|name| {
+            let versions = decls.versions(name).collect::<Vec<_>>();
+            versions
+                .windows(2)
+                .map(|it| {
+                    Pair {
+                        prev: it[0],
+                        next: it[1],
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// conversions between consecutive versions of the structs and enums of a namespace, written
+/// into the module of the namespace.
+///
+/// added optional fields are `None`, removed fields are dropped and renamed fields are taken from
+/// their alias. anything else which can not be converted mechanically gets a `TryFrom` which fails.
+pub(super) fn conversions(ns: &Namespace) -> TokenStream {
+    let structs = pairs(&ns.defs);
+    let enums = pairs(&ns.enums);
+
+    // a conversion may lean on the conversion of a referenced declaration, so whether each is
+    // total is settled by dropping those which are not until none change
+    let mut total = structs
+        .iter()
+        .map(|it| ("struct", it.prev.0.clone()))
+        .chain(
+            enums
+                .iter()
+                .map(|it| ("enum", it.prev.0.clone())),
+        )
+        .collect::<BTreeSet<_>>();
+    loop {
+        let converter = Converter { ns, total: &total };
+        let settled = structs
+            .iter()
+            .filter(|it| matches!(converter.structs(it), Conversion::Total(..)))
+            .map(|it| ("struct", it.prev.0.clone()))
+            .chain(
+                enums
+                    .iter()
+                    .filter(|it| matches!(converter.enums(it), Conversion::Total(..)))
+                    .map(|it| ("enum", it.prev.0.clone())),
+            )
+            .collect::<BTreeSet<_>>();
+        if settled == total {
+            break;
+        }
+        total = settled;
+    }
+
+    let converter = Converter { ns, total: &total };
+    let mut tt = quote!();
+    for pair in &structs {
+        tt.extend(converter.emit(pair, converter.structs(pair)));
+    }
+    for pair in &enums {
+        tt.extend(converter.emit(pair, converter.enums(pair)));
+    }
+    tt
+}
+
+struct Converter<'a> {
+    ns: &'a Namespace,
+    /// the earlier version of each pair, by kind, which converts with `From`
+    total: &'a BTreeSet<(&'static str, Key)>,
+}
+
+impl Converter<'_> {
+    fn site(
+        &self,
+        key: &Key,
+    ) -> String {
+        format!("{}.{key}", self.ns.name)
+    }
+
+    fn path(key: &Key) -> TokenStream {
+        let module = version_module(&key.version);
+        let name = def_ident(key.name.clone());
+        quote!(#module::#name)
+    }
+
+    fn error(
+        &self,
+        pair: &Pair<'_, impl Sized>,
+        reason: &str,
+    ) -> TokenStream {
+        let (from, to) = (self.site(pair.prev.0), self.site(pair.next.0));
+        quote!(operation_api_sdk::ConversionError::new(#from, #to, #reason))
+    }
+
+    fn emit<T>(
+        &self,
+        pair: &Pair<'_, T>,
+        conversion: Conversion,
+    ) -> TokenStream {
+        let (prev, next) = (Self::path(pair.prev.0), Self::path(pair.next.0));
+        match conversion {
+            Conversion::Total(body) => {
+                quote! {
+                    impl From<#prev> for #next {
+                        fn from(value: #prev) -> Self {
+                            #body
+                        }
+                    }
+                }
+            },
+            Conversion::Partial(body) => {
+                quote! {
+                    impl TryFrom<#prev> for #next {
+                        type Error = operation_api_sdk::ConversionError;
+
+                        fn try_from(value: #prev) -> Result<Self, Self::Error> {
+                            #body
+                        }
+                    }
+                }
+            },
+            Conversion::Never(reason) => {
+                let error = self.error(pair, &reason);
+                quote! {
+                    impl TryFrom<#prev> for #next {
+                        type Error = operation_api_sdk::ConversionError;
+
+                        fn try_from(_: #prev) -> Result<Self, Self::Error> {
+                            Err(#error)
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    fn structs(
+        &self,
+        pair: &Pair<'_, Struct>,
+    ) -> Conversion {
+        let ((prev_key, prev), (next_key, next)) = (pair.prev, pair.next);
+        let mut fields = quote!();
+        let mut partial = false;
+        for (name, field) in next.fields.iter() {
+            let field = field.unwrap_value();
+            let member = snake(name);
+            let source = field.alias.as_ref().unwrap_or(name);
+            let value = match prev.fields.get(source) {
+                Some(from) => {
+                    let from = from.unwrap_value();
+                    let src = snake(source);
+                    let boxed = (
+                        self.ns.is_recursive(prev_key, source),
+                        self.ns.is_recursive(next_key, name),
+                    );
+                    match boxed {
+                        (false, false) => {
+                            let error = |reason: String| self.error(pair, &reason);
+                            self.value(
+                                &from.ty,
+                                &field.ty,
+                                quote!(value.#src),
+                                Some((name.to_string(), &error)),
+                            )
+                        },
+                        (true, true) if from.ty == field.ty => Some((quote!(value.#src), false)),
+                        (false, true) if from.ty == field.ty => {
+                            Some((boxing(&field.ty, quote!(value.#src), true), false))
+                        },
+                        (true, false) if from.ty == field.ty => {
+                            Some((boxing(&field.ty, quote!(value.#src), false), false))
+                        },
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        format!("field `{name}` changed from {} to {}", from.ty, field.ty)
+                    })
+                },
+                None if field.optional => Ok((quote!(None), false)),
+                None => {
+                    Err(format!(
+                        "required field `{name}` has no counterpart in {}",
+                        self.site(prev_key)
+                    ))
+                },
+            };
+            match value {
+                Ok((value, fallible)) => {
+                    partial |= fallible;
+                    fields.extend(quote!(#member: #value,));
+                },
+                Err(reason) => return Conversion::Never(reason),
+            }
+        }
+
+        let body = quote!(Self { #fields });
+        match partial {
+            true => Conversion::Partial(quote!(Ok(#body))),
+            false => Conversion::Total(body),
+        }
+    }
+
+    fn enums(
+        &self,
+        pair: &Pair<'_, Enum>,
+    ) -> Conversion {
+        let ((prev_key, prev), (_, next)) = (pair.prev, pair.next);
+        let path = Self::path(prev_key);
+        let mut arms = quote!();
+        let mut partial = false;
+        for (name, variant) in prev.variants.iter() {
+            let from = variant.meta.ident_as_pascal();
+            match next.variants.get(name) {
+                Some(to) => {
+                    let to = to.meta.ident_as_pascal();
+                    arms.extend(quote!(#path::#from => Self::#to,));
+                },
+                None => {
+                    partial = true;
+                    let error = self.error(pair, &format!("variant `{name}` was removed"));
+                    arms.extend(quote!(#path::#from => return Err(#error),));
+                },
+            }
+        }
+
+        let body = quote!(match value { #arms });
+        match partial {
+            true => Conversion::Partial(quote!(Ok(#body))),
+            false => Conversion::Total(body),
+        }
+    }
+
+    /// converts `expr` from one type to the other, and whether the conversion may fail. only the
+    /// outermost conversion of a field, given its name and how to describe a failure, may fail.
+    fn value(
+        &self,
+        from: &Type,
+        to: &Type,
+        expr: TokenStream,
+        fallible: Option<(String, &dyn Fn(String) -> TokenStream)>,
+    ) -> Option<(TokenStream, bool)> {
+        if from == to {
+            return Some((expr, false));
+        }
+        if widens(from, to) {
+            return Some((quote!(#expr.into()), false));
+        }
+
+        let option = |ty: &Type| {
+            match ty {
+                Type::CompoundType(CompoundType::Option { ty }) => Some(ty.as_ref().clone()),
+                _ => None,
+            }
+        };
+        match (option(from), option(to)) {
+            (Some(from), Some(to)) => {
+                let (inner, _) = self.value(&from, &to, quote!(it), None)?;
+                return Some((quote!(#expr.map(|it| #inner)), false));
+            },
+            // a value which may now not be absent
+            (Some(from), None) => {
+                let (name, error) = fallible?;
+                let error = error(format!("field `{name}` is absent"));
+                let expr = quote!(#expr.ok_or(#error)?);
+                return Some((self.value(&from, to, expr, None)?.0, true));
+            },
+            // a value which may now be absent
+            (None, Some(to)) => {
+                let (inner, failed) = self.value(from, &to, expr, fallible)?;
+                return Some((quote!(Some(#inner)), failed));
+            },
+            (None, None) => {},
+        }
+
+        match (from, to) {
+            (
+                Type::CompoundType(CompoundType::Array { ty: from }),
+                Type::CompoundType(CompoundType::Array { ty: to }),
+            ) => {
+                let (inner, _) = self.value(from, to, quote!(it), None)?;
+                Some((quote!(#expr.into_iter().map(|it| #inner).collect()), false))
+            },
+            (
+                Type::CompoundType(CompoundType::Struct { to: from }),
+                Type::CompoundType(CompoundType::Struct { to }),
+            ) => self.reference("struct", from, to, expr, fallible.is_some()),
+            (
+                Type::CompoundType(CompoundType::Enum { to: from }),
+                Type::CompoundType(CompoundType::Enum { to }),
+            ) => self.reference("enum", from, to, expr, fallible.is_some()),
+            _ => None,
+        }
+    }
+
+    /// converts a reference to one version of a declaration of the namespace into a reference to
+    /// the next, through the conversion generated between them
+    fn reference(
+        &self,
+        tag: &'static str,
+        from: &crate::Ident,
+        to: &crate::Ident,
+        expr: TokenStream,
+        fallible: bool,
+    ) -> Option<(TokenStream, bool)> {
+        if from.split_qualified().0.is_some() || to.split_qualified().0.is_some() {
+            return None;
+        }
+        let ((name, prev), (to, next)) = (from.split_version(), to.split_version());
+        let (prev, next) = (prev?, next?);
+        let name = crate::Ident::new(name);
+        let versions = match tag {
+            "struct" => {
+                self.ns
+                    .defs
+                    .versions(&name)
+                    .map(|(key, _)| &key.version)
+                    .collect::<Vec<_>>()
+            },
+            _ => {
+                self.ns
+                    .enums
+                    .versions(&name)
+                    .map(|(key, _)| &key.version)
+                    .collect()
+            },
+        };
+        let consecutive = versions
+            .windows(2)
+            .any(|it| it[0] == &prev && it[1] == &next);
+        if name.to_string() != to || !consecutive {
+            return None;
+        }
+
+        match self
+            .total
+            .contains(&(tag, Key::new(name, prev)))
+        {
+            true => Some((quote!(#expr.into()), false)),
+            false if fallible => Some((quote!(#expr.try_into()?), true)),
+            false => None,
+        }
+    }
+}
+
+/// boxes or unboxes a member which lies on a reference cycle in only one of two versions. options
+/// keep the box inside.
+fn boxing(
+    ty: &Type,
+    expr: TokenStream,
+    boxed: bool,
+) -> TokenStream {
+    match (ty, boxed) {
+        (Type::CompoundType(CompoundType::Option { .. }), true) => quote!(#expr.map(Box::new)),
+        (Type::CompoundType(CompoundType::Option { .. }), false) => quote!(#expr.map(|it| *it)),
+        (_, true) => quote!(Box::new(#expr)),
+        (_, false) => quote!(*#expr),
+    }
+}
+
+fn snake(name: &crate::Ident) -> syn::Ident {
+    ident(
+        name.to_string()
+            .to_case(convert_case::Case::Snake),
+    )
+}
+
+/// whether rust converts every value of one scalar type into the other with `From`
+fn widens(
+    from: &Type,
+    to: &Type,
+) -> bool {
+    match (from, to) {
+        (Type::F32, Type::F64) => true,
+        (Type::U8 | Type::U16, Type::Usize) => true,
+        (Type::Usize, _) | (_, Type::Usize) => false,
+        (from, to) => {
+            match (integer(from), integer(to)) {
+                (Some((from_signed, from_bits)), Some((to_signed, to_bits))) => {
+                    match (from_signed, to_signed) {
+                        (false, true) => from_bits < to_bits,
+                        (from_signed, to_signed) => from_signed == to_signed && from_bits < to_bits,
+                    }
+                },
+                _ => false,
+            }
+        },
+    }
+}
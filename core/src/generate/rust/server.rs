@@ -0,0 +1,285 @@
+use convert_case::Casing;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::{State, comment, ident, reference};
+use crate::{FieldsList, Operation, namespace::Declarations};
+
+/// the trait of the operations of a namespace, named after its last segment, along with the
+/// request and response of each operation and a dispatcher routing requests to the trait.
+///
+/// operations held in several versions are routed as `name@version`, and the latest of them as
+/// `name` too.
+pub(super) fn server(state: &State<'_>) -> TokenStream {
+    let ns = state.ns;
+    let vis = state.opts.opts.vis.default.as_rust();
+    let name = ns.name.to_string();
+    let last = name.rsplit('.').next().unwrap_or(&name);
+    let server = ident(format!(
+        "{}Server",
+        last.to_case(convert_case::Case::Pascal)
+    ));
+    let doc = format!("the operations of `{name}`");
+
+    let mut items = quote!();
+    let mut methods = quote!();
+    let mut routes = quote!();
+    for (key, def) in ns.ops.iter() {
+        let versioned = ns.ops.is_versioned(&key.name);
+        let latest = ns
+            .ops
+            .versions(&key.name)
+            .last()
+            .is_some_and(|(it, _)| it == key);
+        let op = Op::new(state, def, versioned);
+        items.extend(op.messages());
+        methods.extend(op.method());
+
+        let route = op.route();
+        let mut patterns = quote!(#route);
+        if versioned && latest {
+            let name = key.name.to_string();
+            patterns.extend(quote!(| #name));
+        }
+        let call = op.call();
+        routes.extend(quote!(#patterns => { #call }));
+    }
+
+    quote! {
+        #items
+
+        #[doc = #doc]
+        #vis trait #server: Send + Sync {
+            #methods
+        }
+
+        /// routes the serialized request of the operation named `operation` to `server`
+        #vis async fn dispatch<S: #server>(
+            server: &S,
+            operation: &str,
+            request: operation_api_sdk::serde_json::Value,
+        ) -> Result<operation_api_sdk::serde_json::Value, operation_api_sdk::DispatchError> {
+            match operation {
+                #routes
+                _ => Err(operation_api_sdk::DispatchError::UnknownOperation(operation.to_string())),
+            }
+        }
+    }
+}
+
+/// what the method of an operation returns
+enum Output {
+    /// nothing
+    Unit,
+    /// its only output, which is not named
+    Value(crate::Ident),
+    /// its response
+    Response,
+}
+
+struct Op<'a, 'ns> {
+    state: &'a State<'ns>,
+    def: &'a Operation,
+    name: String,
+    method: syn::Ident,
+    output: Output,
+}
+
+impl<'a, 'ns> Op<'a, 'ns> {
+    fn new(
+        state: &'a State<'ns>,
+        def: &'a Operation,
+        versioned: bool,
+    ) -> Self {
+        let mut name = def
+            .meta
+            .name
+            .to_string()
+            .to_case(convert_case::Case::Pascal);
+        let mut method = def
+            .meta
+            .name
+            .to_string()
+            .to_case(convert_case::Case::Snake);
+        if versioned {
+            name.push_str(&format!("V{}", def.meta.version));
+            method.push_str(&format!("_v{}", def.meta.version));
+        }
+
+        let output = match def.outputs.iter().next() {
+            None => Output::Unit,
+            Some((field, value))
+                if def.outputs.len() == 1 && value.unwrap_value().meta.name.is_none() =>
+            {
+                Output::Value(field.clone())
+            },
+            Some(_) => Output::Response,
+        };
+
+        Self {
+            state,
+            def,
+            name,
+            method: ident(method),
+            output,
+        }
+    }
+
+    /// operations are a pair of the request of their inputs and the response of their outputs
+    fn request(&self) -> syn::Ident {
+        ident(format!("{}Request", self.name))
+    }
+
+    fn response(&self) -> syn::Ident {
+        ident(format!("{}Response", self.name))
+    }
+
+    fn route(&self) -> String {
+        let name = self.def.meta.name.to_string();
+        match self
+            .state
+            .ns
+            .ops
+            .is_versioned(&self.def.meta.name)
+        {
+            true => format!("{name}@{}", self.def.meta.version),
+            false => name,
+        }
+    }
+
+    fn error(&self) -> Option<TokenStream> {
+        match &self.def.error {
+            Some(error) if !self.def.infallible => Some(reference(error, 0)),
+            _ => None,
+        }
+    }
+
+    /// the request and response of the operation, as they are serialized
+    fn messages(&self) -> TokenStream {
+        let vis = self
+            .state
+            .opts
+            .opts
+            .vis
+            .as_rust(self.state, &self.def.meta.name);
+        let doc = comment(&self.def.meta.description);
+        let request = self.request();
+        let response = self.response();
+        let inputs = self.fields(&self.def.inputs, &vis);
+        let outputs = self.fields(&self.def.outputs, &vis);
+        quote! {
+            #[derive(serde::Serialize, serde::Deserialize)]
+            #doc
+            #vis struct #request {
+                #inputs
+            }
+
+            #[derive(serde::Serialize, serde::Deserialize)]
+            #doc
+            #vis struct #response {
+                #outputs
+            }
+        }
+    }
+
+    fn fields(
+        &self,
+        fields: &FieldsList,
+        vis: &TokenStream,
+    ) -> TokenStream {
+        fields
+            .iter()
+            .map(|(name, field)| {
+                let field = field.unwrap_value();
+                let f = member(name);
+                let name = name.to_string();
+                let doc = field.meta.doc_comment();
+                let ty = field.ty.ty(&self.state.opts.opts);
+                quote!(
+                    #[serde(rename = #name)]
+                    #doc
+                    #vis #f: #ty,
+                )
+            })
+            .collect()
+    }
+
+    fn method(&self) -> TokenStream {
+        let doc = comment(&self.def.meta.description);
+        let method = &self.method;
+        let args: TokenStream = self
+            .def
+            .inputs
+            .iter()
+            .map(|(name, field)| {
+                let f = member(name);
+                let ty = field
+                    .unwrap_value()
+                    .ty
+                    .ty(&self.state.opts.opts);
+                quote!(#f: #ty,)
+            })
+            .collect();
+
+        let mut ret = match &self.output {
+            Output::Unit => quote!(()),
+            Output::Value(name) => {
+                self.def.outputs[name]
+                    .unwrap_value()
+                    .ty
+                    .ty(&self.state.opts.opts)
+            },
+            Output::Response => {
+                let response = self.response();
+                quote!(#response)
+            },
+        };
+        if let Some(error) = self.error() {
+            ret = quote!(Result<#ret, #error>);
+        }
+
+        quote! {
+            #doc
+            fn #method(&self, #args) -> impl std::future::Future<Output = #ret> + Send;
+        }
+    }
+
+    /// decodes the request, calls the method and encodes its response
+    fn call(&self) -> TokenStream {
+        let route = self.route();
+        let request = self.request();
+        let response = self.response();
+        let method = &self.method;
+        let members: Vec<_> = self.def.inputs.keys().map(member).collect();
+
+        let mut call = quote!(server.#method(#(#members),*).await);
+        if self.error().is_some() {
+            call = quote!(
+                #call.map_err(|error| operation_api_sdk::DispatchError::failed(#route, error))?
+            );
+        }
+        let respond = match &self.output {
+            Output::Unit => quote!(#call; let response = #response {};),
+            Output::Value(name) => {
+                let value = member(name);
+                quote!(let response = #response { #value: #call };)
+            },
+            Output::Response => quote!(let response: #response = #call;),
+        };
+
+        quote! {
+            let #request { #(#members),* } = operation_api_sdk::serde_json::from_value(request)
+                .map_err(|source| operation_api_sdk::DispatchError::Request { operation: #route, source })?;
+            #respond
+            operation_api_sdk::serde_json::to_value(response)
+                .map_err(|source| operation_api_sdk::DispatchError::Response { operation: #route, source })
+        }
+    }
+}
+
+fn member(name: &crate::Ident) -> syn::Ident {
+    ident(
+        name.to_string()
+            .to_case(convert_case::Case::Snake),
+    )
+}
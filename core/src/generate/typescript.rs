@@ -0,0 +1,443 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    io::Write,
+    path::Path,
+};
+
+use crate::{
+    CompoundType, Enum, ErrorTy, FieldsList, Key, OneOf, Operation, StrOrInt, Struct, Type,
+    generate::{
+        GenOpts, Generate, LanguageTrait, TypescriptConfig, TypescriptDateTime,
+        common::{Module, Modules, latest, literal, name, pascal, tag, versioned_name},
+        context::WithNsContext,
+        files::WithFlush,
+    },
+    namespace::{Declarations, Namespace},
+};
+
+pub struct TypescriptGenerator;
+
+impl LanguageTrait for TypescriptGenerator {
+    fn file_case() -> convert_case::Case<'static> {
+        convert_case::Case::Snake
+    }
+
+    fn file_ext() -> &'static str {
+        "ts"
+    }
+}
+
+#[derive(Default)]
+struct TsModule {
+    /// namespaces referred to, imported by their module name
+    imports: BTreeSet<String>,
+    body: String,
+}
+
+impl Module for TsModule {
+    type Refs<'ns> = Types<'ns>;
+
+    fn merge(
+        &mut self,
+        refs: Types<'_>,
+        written: Self,
+    ) {
+        self.imports.extend(refs.imports);
+        self.body.push_str(&written.body);
+    }
+}
+
+pub(crate) struct TypescriptGenState {
+    /// the module of every namespace, written once every declaration is generated so that
+    /// imports of other namespaces lead it
+    modules: Modules<TsModule>,
+}
+
+type State<'ns> = WithNsContext<'ns, TypescriptGenState, TypescriptConfig, TypescriptGenerator>;
+
+impl Generate<TypescriptGenState, TypescriptConfig> for TypescriptGenerator {
+    #[allow(unused)]
+    fn on_create(
+        state: &State<'_>,
+        fname: &Path,
+        f: &mut Box<dyn WithFlush>,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    #[allow(unused)]
+    fn new_state(
+        &self,
+        opts: &GenOpts<TypescriptConfig>,
+    ) -> TypescriptGenState {
+        TypescriptGenState {
+            modules: Default::default(),
+        }
+    }
+
+    #[allow(unused)]
+    fn with_all_namespaces(
+        &self,
+        ctx: &crate::context::Context,
+        opts: &GenOpts<TypescriptConfig>,
+        ctx_ns: BTreeMap<crate::Ident, State<'_>>,
+    ) -> super::Result<()> {
+        for ns in ctx.namespaces.values() {
+            let ctx = ctx_ns.get(&ns.name).unwrap();
+            let mut module = ctx.state.modules.take(&ns.name);
+
+            let keys = latest(&ns.defs)
+                .chain(latest(&ns.enums))
+                .chain(latest(&ns.one_ofs))
+                .chain(latest(&ns.errors));
+            for key in keys {
+                let name = pascal(&key.name.to_string());
+                let versioned = versioned_name(key);
+                writeln!(module.body, "export type {name} = {versioned};\n").unwrap();
+            }
+
+            let mut out = String::new();
+            for import in &module.imports {
+                writeln!(out, "import type * as {import} from \"./{import}\";").unwrap();
+            }
+            if !module.imports.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(module.body.trim_end());
+            out.push('\n');
+
+            ctx.with_file_handle(ctx.ns_file(), |w| write!(w, "{out}"))?;
+        }
+        Ok(())
+    }
+
+    fn gen_operation(
+        &self,
+        state: &State<'_>,
+        def: &Operation,
+    ) -> super::Result<()> {
+        let versioned = state.ns.ops.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |ts, out| {
+            write!(out, "{}", doc(&def.meta.description, "")).unwrap();
+            writeln!(
+                out,
+                "export interface {name}Request {}\n",
+                ts.fields(&def.inputs)
+            )
+            .unwrap();
+            write!(out, "{}", doc(&def.meta.description, "")).unwrap();
+            writeln!(
+                out,
+                "export interface {name}Response {}\n",
+                ts.fields(&def.outputs)
+            )
+            .unwrap();
+            if !def.infallible
+                && let Some(error) = &def.error
+            {
+                writeln!(out, "export type {name}Error = {};\n", ts.reference(error)).unwrap();
+            }
+        });
+        Ok(())
+    }
+
+    fn gen_struct(
+        &self,
+        state: &State<'_>,
+        def: &Struct,
+    ) -> super::Result<()> {
+        let versioned = state.ns.defs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |ts, out| {
+            write!(out, "{}", doc(&def.meta.description, "")).unwrap();
+            writeln!(out, "export interface {name} {}\n", ts.fields(&def.fields)).unwrap();
+        });
+        Ok(())
+    }
+
+    /// integer enums are const enums, while string enums are a union of their values
+    fn gen_enum(
+        &self,
+        state: &State<'_>,
+        def: &Enum,
+    ) -> super::Result<()> {
+        let versioned = state.ns.enums.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        let is_int = def
+            .variants
+            .values()
+            .all(|it| matches!(it.value, StrOrInt::Int(..)));
+        self.with_module(state, |_, out| {
+            write!(out, "{}", doc(&def.meta.description, "")).unwrap();
+            if is_int {
+                writeln!(out, "export const enum {name} {{").unwrap();
+                for var in def.variants.values() {
+                    write!(out, "{}", doc(&var.meta.description, "\t")).unwrap();
+                    let value = match &var.value {
+                        StrOrInt::Int(value) => value.to_string(),
+                        StrOrInt::String(value) => literal(value),
+                    };
+                    writeln!(out, "\t{} = {value},", pascal(&var.meta.name.to_string())).unwrap();
+                }
+                writeln!(out, "}}\n").unwrap();
+            } else {
+                let values = def
+                    .variants
+                    .values()
+                    .map(|var| {
+                        match &var.value {
+                            StrOrInt::String(value) => literal(value),
+                            StrOrInt::Int(value) => value.to_string(),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                writeln!(out, "export type {name} = {values};\n").unwrap();
+            }
+        });
+        Ok(())
+    }
+
+    fn gen_one_of(
+        &self,
+        state: &State<'_>,
+        def: &OneOf,
+    ) -> super::Result<()> {
+        let versioned = state.ns.one_ofs.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |ts, out| {
+            write!(out, "{}", doc(&def.meta.description, "")).unwrap();
+            let variants = def
+                .variants
+                .values()
+                .map(|var| (&var.description, ts.ty(&var.ty)));
+            union(out, &name, variants);
+        });
+        Ok(())
+    }
+
+    fn gen_error(
+        &self,
+        state: &State<'_>,
+        def: &ErrorTy,
+    ) -> super::Result<()> {
+        let versioned = state.ns.errors.is_versioned(&def.meta.name);
+        let name = name(&def.meta.key(), versioned);
+        self.with_module(state, |ts, out| {
+            write!(out, "{}", doc(&def.meta.description, "")).unwrap();
+            let variants = def.variants.iter().map(|(ident, var)| {
+                let tag = literal(&tag(ident));
+                (
+                    &var.description,
+                    format!("({{ type: {tag} }} & {})", ts.ty(&var.ty)),
+                )
+            });
+            union(out, &name, variants);
+        });
+        Ok(())
+    }
+}
+
+impl TypescriptGenerator {
+    fn with_module(
+        &self,
+        state: &State<'_>,
+        handle: impl FnOnce(&mut Types<'_>, &mut String),
+    ) {
+        let types = Types {
+            ns: state.ns,
+            opts: &state.opts.opts,
+            imports: BTreeSet::new(),
+        };
+        state
+            .state
+            .modules
+            .write(&state.ns.name, types, |types, module| {
+                handle(types, &mut module.body)
+            });
+    }
+}
+
+/// names the types of a namespace, noting every other namespace they refer to
+struct Types<'ns> {
+    ns: &'ns Namespace,
+    opts: &'ns TypescriptConfig,
+    imports: BTreeSet<String>,
+}
+
+impl Types<'_> {
+    fn fields(
+        &mut self,
+        fields: &FieldsList,
+    ) -> String {
+        if fields.is_empty() {
+            return "{}".into();
+        }
+        let mut out = String::from("{\n");
+        for (name, field) in fields.iter() {
+            let field = field.unwrap_value();
+            out.push_str(&doc(&field.meta.description, "\t"));
+            let optional = match &field.ty {
+                Type::CompoundType(CompoundType::Option { .. }) => "?",
+                _ => "",
+            };
+            writeln!(
+                out,
+                "\t{}{optional}: {};",
+                property(&name.to_string()),
+                self.ty(&field.ty)
+            )
+            .unwrap();
+        }
+        out.push('}');
+        out
+    }
+
+    /// the type of a value of `ty`
+    fn ty(
+        &mut self,
+        ty: &Type,
+    ) -> String {
+        match ty {
+            Type::Bool => "boolean".into(),
+            Type::U8
+            | Type::U16
+            | Type::U32
+            | Type::U64
+            | Type::Usize
+            | Type::I8
+            | Type::I16
+            | Type::I32
+            | Type::I64
+            | Type::F32
+            | Type::F64 => "number".into(),
+            Type::String => "string".into(),
+            Type::DateTime => {
+                match self.opts.datetime {
+                    TypescriptDateTime::String => "string".into(),
+                    TypescriptDateTime::Date => "Date".into(),
+                }
+            },
+            Type::Binary => "number[]".into(),
+            Type::Complex => "unknown".into(),
+            Type::Never => "null".into(),
+            Type::CompoundType(ty) => {
+                match ty {
+                    CompoundType::Enum { to }
+                    | CompoundType::OneOf { to }
+                    | CompoundType::Struct { to } => self.reference(to),
+                    CompoundType::Option { ty } => format!("{} | null", self.ty(ty)),
+                    CompoundType::Array { ty } => format!("{}[]", self.element(ty)),
+                    CompoundType::SizedArray { size, ty } => {
+                        let ty = self.element(ty);
+                        match *size <= MAX_TUPLE {
+                            true => format!("[{}]", vec![ty; *size].join(", ")),
+                            false => format!("{ty}[]"),
+                        }
+                    },
+                }
+            },
+        }
+    }
+
+    /// the type of the elements of an array, which must be parenthesized when a union
+    fn element(
+        &mut self,
+        ty: &Type,
+    ) -> String {
+        let inner = self.ty(ty);
+        match ty {
+            Type::CompoundType(CompoundType::Option { .. }) => format!("({inner})"),
+            _ => inner,
+        }
+    }
+
+    /// references into another namespace go through the import of its module
+    fn reference(
+        &mut self,
+        to: &crate::Ident,
+    ) -> String {
+        let (qualifier, name) = to.split_qualified();
+        let name = crate::Ident::new(name);
+        let (name, version) = name.split_version();
+        let name = match version {
+            Some(version) => versioned_name(&Key::new(name, version)),
+            None => pascal(name),
+        };
+        match qualifier {
+            Some(qualifier) if qualifier != self.ns.name.to_string() => {
+                let module = crate::namespace::normalized_path::<TypescriptGenerator>(qualifier);
+                let reference = format!("{module}.{name}");
+                self.imports.insert(module);
+                reference
+            },
+            _ => name,
+        }
+    }
+}
+
+/// writes a union of `variants`, each on a line of its own
+fn union<'a>(
+    out: &mut String,
+    name: &str,
+    variants: impl Iterator<Item = (&'a Option<String>, String)>,
+) {
+    writeln!(out, "export type {name} =").unwrap();
+    let variants = variants.collect::<Vec<_>>();
+    for (i, (description, ty)) in variants.iter().enumerate() {
+        out.push_str(&doc(description, "\t"));
+        let end = if i + 1 == variants.len() {
+            ";"
+        } else {
+            ""
+        };
+        writeln!(out, "\t| {ty}{end}").unwrap();
+    }
+    out.push('\n');
+}
+
+/// sized arrays up to this size are held as tuples
+const MAX_TUPLE: usize = 16;
+
+/// property names which are not identifiers are quoted
+fn property(name: &str) -> String {
+    let mut chars = name.chars();
+    let ident = chars
+        .next()
+        .is_some_and(|it| it.is_ascii_alphabetic() || it == '_' || it == '$')
+        && chars.all(|it| it.is_ascii_alphanumeric() || it == '_' || it == '$');
+    match ident {
+        true => name.into(),
+        false => literal(name),
+    }
+}
+
+fn doc(
+    description: &Option<String>,
+    indent: &str,
+) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    let lines = description
+        .trim()
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    match lines.as_slice() {
+        [line] => format!("{indent}/** {line} */\n"),
+        lines => {
+            let mut out = format!("{indent}/**\n");
+            for line in lines {
+                match line.is_empty() {
+                    true => writeln!(out, "{indent} *").unwrap(),
+                    false => writeln!(out, "{indent} * {line}").unwrap(),
+                }
+            }
+            writeln!(out, "{indent} */").unwrap();
+            out
+        },
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    CompoundType, FieldOrRef, Ident, Key, Named, OneOfVariant, Type,
+    namespace::{Declarations, Namespace},
+};
+
+/// a struct, one_of or error, keyed by its namespace, name and version
+pub type Node = (Ident, Key);
+
+/// a member holding a referenced type by value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub from: Node,
+    /// the field or variant holding the reference
+    pub member: Ident,
+    pub to: Node,
+    /// set when every value of `from` must hold a `to`, i.e. the member is not optional and
+    /// there is no other variant to choose
+    pub required: bool,
+}
+
+/// references between structs, one_ofs and errors of a context.
+///
+/// references through arrays are left out, as an empty array always terminates them and
+/// the array already provides the indirection a recursive type needs.
+#[derive(Debug, Default)]
+pub struct RefGraph {
+    pub edges: Vec<Edge>,
+}
+
+impl RefGraph {
+    pub fn new(namespaces: &BTreeMap<Ident, Namespace>) -> Self {
+        let mut this = Self::default();
+        for ns in namespaces.values() {
+            let mut builder = Builder {
+                graph: &mut this,
+                namespaces,
+                ns,
+            };
+            for (key, def) in &ns.defs {
+                for (member, field) in def.fields.iter() {
+                    // field refs are resolved by `Namespace::check`
+                    if let FieldOrRef::Value(field) = field {
+                        builder.edges_of(key, member, &field.ty, !field.optional);
+                    }
+                }
+            }
+            for (key, def) in &ns.one_ofs {
+                builder.variants(key, &def.variants);
+            }
+            for (key, def) in &ns.errors {
+                builder.variants(key, &def.variants);
+            }
+        }
+        this
+    }
+
+    /// edges lying on a cycle. a type holding these by value would be infinitely sized.
+    pub fn recursive(&self) -> Vec<&Edge> {
+        let components = components(&self.edges.iter().collect::<Vec<_>>());
+        self.edges
+            .iter()
+            .filter(|edge| components.get(&edge.from) == components.get(&edge.to))
+            .collect()
+    }
+
+    /// cycles made only of required edges. no finite value of the types on them exists.
+    pub fn unrepresentable(&self) -> Vec<Vec<Node>> {
+        let required = self
+            .edges
+            .iter()
+            .filter(|edge| edge.required)
+            .collect::<Vec<_>>();
+        let components = components(&required);
+
+        let mut cycles = BTreeMap::<usize, BTreeSet<Node>>::new();
+        for edge in &required {
+            if let Some(component) = components.get(&edge.from)
+                && components.get(&edge.to) == Some(component)
+            {
+                let cycle = cycles.entry(*component).or_default();
+                cycle.insert(edge.from.clone());
+                cycle.insert(edge.to.clone());
+            }
+        }
+        cycles
+            .into_values()
+            .map(|cycle| cycle.into_iter().collect())
+            .collect()
+    }
+}
+
+struct Builder<'g> {
+    graph: &'g mut RefGraph,
+    namespaces: &'g BTreeMap<Ident, Namespace>,
+    ns: &'g Namespace,
+}
+
+impl Builder<'_> {
+    fn variants(
+        &mut self,
+        key: &Key,
+        variants: &Named<OneOfVariant>,
+    ) {
+        let required = variants.len() == 1;
+        for (member, variant) in variants.iter() {
+            self.edges_of(key, member, &variant.ty, required);
+        }
+    }
+
+    fn edges_of(
+        &mut self,
+        key: &Key,
+        member: &Ident,
+        ty: &Type,
+        required: bool,
+    ) {
+        let Type::CompoundType(ty) = ty else {
+            return;
+        };
+        let to = match ty {
+            CompoundType::Option { ty } => return self.edges_of(key, member, ty, false),
+            CompoundType::SizedArray { size, ty } => {
+                return self.edges_of(key, member, ty, required && *size > 0);
+            },
+            CompoundType::Array { .. } | CompoundType::Enum { .. } => return,
+            CompoundType::Struct { to } | CompoundType::OneOf { to } => to,
+        };
+        if let Some(to) = self.node(to) {
+            self.graph.edges.push(Edge {
+                from: (self.ns.name.clone(), key.clone()),
+                member: member.clone(),
+                to,
+                required,
+            });
+        }
+    }
+
+    /// the struct or one_of a reference points at, if it resolves
+    fn node(
+        &self,
+        to: &Ident,
+    ) -> Option<Node> {
+        let (qualifier, name) = to.split_qualified();
+        let ns = match qualifier {
+            Some(qualifier) => self.namespaces.get(&Ident::new(qualifier))?,
+            None => self.ns,
+        };
+        let name = Ident::new(name);
+        let (bare, version) = name.split_version();
+        let bare = Ident::new(bare);
+        let key = ns
+            .defs
+            .resolve(&bare, version.as_ref())
+            .map(|(key, _)| key)
+            .or_else(|| {
+                ns.one_ofs
+                    .resolve(&bare, version.as_ref())
+                    .map(|(key, _)| key)
+            })?;
+        Some((ns.name.clone(), key.clone()))
+    }
+}
+
+/// strongly connected components, numbering each node by the component it is in
+fn components(edges: &[&Edge]) -> BTreeMap<Node, usize> {
+    let mut adjacent = BTreeMap::<&Node, Vec<&Node>>::new();
+    for edge in edges {
+        adjacent
+            .entry(&edge.from)
+            .or_default()
+            .push(&edge.to);
+        adjacent.entry(&edge.to).or_default();
+    }
+
+    let mut tarjan = Tarjan {
+        adjacent: &adjacent,
+        index: BTreeMap::new(),
+        low: BTreeMap::new(),
+        stack: vec![],
+        on_stack: BTreeSet::new(),
+        components: BTreeMap::new(),
+        next_component: 0,
+    };
+    for node in adjacent.keys() {
+        if !tarjan.index.contains_key(node) {
+            tarjan.visit(node);
+        }
+    }
+    tarjan
+        .components
+        .into_iter()
+        .map(|(node, component)| (node.clone(), component))
+        .collect()
+}
+
+struct Tarjan<'g> {
+    adjacent: &'g BTreeMap<&'g Node, Vec<&'g Node>>,
+    index: BTreeMap<&'g Node, usize>,
+    low: BTreeMap<&'g Node, usize>,
+    stack: Vec<&'g Node>,
+    on_stack: BTreeSet<&'g Node>,
+    components: BTreeMap<&'g Node, usize>,
+    next_component: usize,
+}
+
+impl<'g> Tarjan<'g> {
+    fn visit(
+        &mut self,
+        node: &'g Node,
+    ) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.low.insert(node, index);
+        self.stack.push(node);
+        self.on_stack.insert(node);
+
+        for next in &self.adjacent[node] {
+            if !self.index.contains_key(next) {
+                self.visit(next);
+                let low = self.low[node].min(self.low[next]);
+                self.low.insert(node, low);
+            } else if self.on_stack.contains(next) {
+                let low = self.low[node].min(self.index[next]);
+                self.low.insert(node, low);
+            }
+        }
+
+        if self.low[node] == self.index[node] {
+            let component = self.next_component;
+            self.next_component += 1;
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                self.components.insert(member, component);
+                if member == node {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RefGraph;
+    use crate::{context::Context, lower::Lowering};
+
+    fn graph(source: &str) -> RefGraph {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_source("test.pld", source)
+            .unwrap();
+        let mut ctx = Context::new();
+        ctx.with_definitions(lowering.finish().unwrap())
+            .unwrap();
+        RefGraph::new(&ctx.namespaces)
+    }
+
+    #[test_case::test_case("struct A { a?: A };", &["A.a"], 0; "optional self")]
+    #[test_case::test_case("struct A { a: A[] };", &[], 0; "array self")]
+    #[test_case::test_case("struct A { a: A };", &["A.a"], 1; "required self")]
+    #[test_case::test_case("struct A { b: B };\nstruct B { a?: A, c: C };\nstruct C { a: i32 };", &["A.b", "B.a"], 0; "mutual")]
+    #[test_case::test_case("struct A { b: B };\noneof B { a(A), c(i32) };", &["A.b", "B.a"], 0; "through oneof")]
+    #[test_case::test_case("struct A { b: B };\noneof B { a(A) };", &["A.b", "B.a"], 1; "single variant oneof")]
+    #[test_case::test_case("struct A { a: i32[2] };\nstruct B { b: B[2] };", &["B.b"], 1; "sized array")]
+    fn test_cycles(
+        source: &str,
+        recursive: &[&str],
+        unrepresentable: usize,
+    ) {
+        let graph = graph(&format!("namespace test;\n{source}"));
+        let edges = graph
+            .recursive()
+            .into_iter()
+            .map(|edge| format!("{}.{}", edge.from.1.name, edge.member))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, recursive);
+        assert_eq!(graph.unrepresentable().len(), unrepresentable);
+    }
+}
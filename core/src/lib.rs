@@ -1,9 +1,14 @@
 #![allow(clippy::iter_kv_map)]
 
 pub mod context;
+pub mod diff;
 
 pub mod checks;
+pub mod graph;
+pub mod lower;
 pub mod namespace;
+pub mod resolve;
+pub mod source;
 pub mod ty;
 pub(crate) mod utils;
 use std::marker::PhantomData;
@@ -55,12 +60,109 @@ pub enum Error {
     #[error("{name} is not found in {ns}")]
     NameNotFound { name: Ident, ns: Ident },
 
+    #[error("{tag} {name} referenced by {site} is not found in {ns}{}", did_you_mean(.suggestion))]
+    UnresolvedReference {
+        tag: &'static str,
+        name: Ident,
+        ns: Ident,
+        site: String,
+        suggestion: Option<Ident>,
+    },
+
+    #[error("namespace {ns} referenced by {site} is not found{}", did_you_mean(.suggestion))]
+    UnresolvedNamespace {
+        ns: Ident,
+        site: String,
+        suggestion: Option<Ident>,
+    },
+
+    #[error("{name} referenced by {site} is declared as {found} in {ns}, expected {tag}")]
+    MismatchedReference {
+        tag: &'static str,
+        found: &'static str,
+        name: Ident,
+        ns: Ident,
+        site: String,
+    },
+
     #[error("config error: {0}")]
     Config(#[from] ::config::ConfigError),
 
     #[error("[{src}] {error}")]
     SourceFile { error: Box<Self>, src: String },
 
+    #[error("{cycle} is recursive through required members only, so no value of it can exist")]
+    UnrepresentableCycle { cycle: String },
+
+    #[error("{kind} {name} should be {case}, rename it to {rename}")]
+    Misnamed {
+        kind: &'static str,
+        name: Ident,
+        case: &'static str,
+        rename: Ident,
+    },
+
+    #[error("{kind}s {names} in {scope} collide once converted to {case}")]
+    CaseCollision {
+        kind: &'static str,
+        names: String,
+        scope: String,
+        case: &'static str,
+    },
+
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    Many(Vec<Self>),
+
+    #[error("{error}")]
+    Fixable {
+        error: Box<Self>,
+        edits: Vec<checks::fix::Edit>,
+    },
+
+    #[error("{count} breaking change(s) without a version bump")]
+    UnversionedBreakingChanges { count: usize },
+
+    #[error("checks failed with {errors} error(s) and {warnings} warning(s)")]
+    ChecksFailed { errors: usize, warnings: usize },
+
+    #[error("{kind} {name} is generated for both {first} and {second}")]
+    NameCollision {
+        kind: &'static str,
+        name: String,
+        first: Ident,
+        second: Ident,
+    },
+
+    #[error("field {field} of {site} declares no #[tag(..)] to number it by")]
+    MissingFieldTag { field: Ident, site: String },
+
+    #[error("field {field} of {site} can not be numbered {tag}: {reason}")]
+    InvalidFieldTag {
+        field: Ident,
+        site: String,
+        tag: u32,
+        reason: String,
+    },
+
+    #[error("variant {variant} of {site} declares no #[tag(..)] to number it by")]
+    MissingVariantTag { variant: Ident, site: String },
+
+    #[error("variant {variant} of {site} can not be numbered {tag}: {reason}")]
+    InvalidVariantTag {
+        variant: Ident,
+        site: String,
+        tag: u32,
+        reason: String,
+    },
+
+    #[error("variant {variant} of {site} can not be numbered by its value {value}: {reason}")]
+    InvalidEnumValue {
+        variant: Ident,
+        site: String,
+        value: usize,
+        reason: String,
+    },
+
     #[error("'{ident}' is not contiguous with {desc}")]
     ContiguousError { ident: Ident, desc: String },
 
@@ -80,6 +182,13 @@ pub enum Error {
     Miette(miette::Error),
 }
 
+fn did_you_mean(suggestion: &Option<Ident>) -> String {
+    match suggestion {
+        Some(it) => format!(", did you mean {it}?"),
+        None => String::new(),
+    }
+}
+
 impl From<miette::Error> for Error {
     fn from(value: miette::Error) -> Self {
         Self::Miette(value)
@@ -104,6 +213,43 @@ impl Error {
     pub fn from_with_source_init<E: Into<Self>>(src: String) -> impl FnOnce(E) -> Self {
         |err| Self::with_source_init(src)(err.into())
     }
+
+    /// attaches the edits which would fix this error, applied by `op check --fix`
+    pub fn with_edits(
+        self,
+        edits: Vec<checks::fix::Edit>,
+    ) -> Self {
+        if edits.is_empty() {
+            return self;
+        }
+        Self::Fixable {
+            error: Box::new(self),
+            edits,
+        }
+    }
+
+    /// collects several errors into one, or none if there are none
+    pub fn many(errors: impl IntoIterator<Item = Self>) -> Result<()> {
+        let mut errors = errors.into_iter().collect::<Vec<_>>();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(Self::Many(errors)),
+        }
+    }
+
+    /// the individual errors this error is made of
+    pub fn flatten(self) -> Vec<Self> {
+        match self {
+            Self::Many(errors) => {
+                errors
+                    .into_iter()
+                    .flat_map(Self::flatten)
+                    .collect()
+            },
+            err => vec![err],
+        }
+    }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -136,12 +282,12 @@ mod test {
 
     use crate::{
         CompoundType, Definitions, Enum, Field, FieldOrRef, FieldsList, Meta, Named, Operation,
-        Struct, Type, Typed, VariantKind, Version, map, ty,
+        Struct, Type, Typed, VariantKind, Version, map,
     };
 
-    const BASIC_STRUCT: &'static str = include_str!("../../samples/basic-struct.toml");
-    const BASIC_OP: &'static str = include_str!("../../samples/basic-op.toml");
-    const BASIC_ENUM: &'static str = include_str!("../../samples/basic-enum.toml");
+    const BASIC_STRUCT: &str = include_str!("../../samples/basic-struct.toml");
+    const BASIC_OP: &str = include_str!("../../samples/basic-op.toml");
+    const BASIC_ENUM: &str = include_str!("../../samples/basic-enum.toml");
 
     fn empty_meta() -> Meta<Option<crate::Ident>, Option<crate::Ident>, Option<Version>> {
         Meta::builder()
@@ -154,13 +300,14 @@ mod test {
     #[test]
     fn test_de_basic_struct() {
         let namespace = crate::Ident::new("abc.corp.namespace");
-        let s: Definitions = toml::from_str(&BASIC_STRUCT).unwrap();
+        let s: Definitions = toml::from_str(BASIC_STRUCT).unwrap();
         let s2 = Definitions::StructV1(Struct {
             meta: Meta {
                 name: "some_struct".into(),
                 description: None,
                 namespace: namespace.clone(),
                 version: 2_usize.into(),
+                span: None,
             },
             fields: FieldsList::new(map!({
                 a: Field::builder().ty(Type::I32).optional(false).meta(empty_meta()).build(),
@@ -178,7 +325,7 @@ mod test {
 
     #[test]
     fn test_de_basic_op() {
-        let s: Definitions = toml::from_str(&BASIC_OP).unwrap();
+        let s: Definitions = toml::from_str(BASIC_OP).unwrap();
         let expect = Definitions::OperationV1(
             Operation::builder()
             .meta(Meta::builder().name("add".into())
@@ -199,7 +346,7 @@ mod test {
 
     #[test]
     fn test_de_basic_enum() {
-        let s: Definitions = toml::from_str(&BASIC_ENUM).unwrap();
+        let s: Definitions = toml::from_str(BASIC_ENUM).unwrap();
         let expect = Definitions::EnumV1(
             Enum::builder()
                 .meta(
@@ -220,6 +367,7 @@ mod test {
                                 .version(None)
                                 .build(),
                             value: crate::StrOrInt::Int(1),
+                            tag: None,
                         },
                     ),
                     (
@@ -232,6 +380,7 @@ mod test {
                                 .version(None)
                                 .build(),
                             value: crate::StrOrInt::Int(2),
+                            tag: None,
                         },
                     ),
                 ]))
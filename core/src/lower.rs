@@ -0,0 +1,1896 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use convert_case::{Case, Casing};
+use operation_api_parser::{
+    ast::{
+        AstStream,
+        anonymous::AnonymousStruct,
+        array::Array,
+        comment::CommentStream,
+        enm,
+        items::Items,
+        meta::{IntMeta, ItemMeta, ItemMetaItem},
+        one_of::AnonymousOneOf,
+        strct::{Arg, Sep},
+        ty::{self as ast_ty, Builtin, PathOrIdent, Pin},
+        union::{IdentOrUnion, Union},
+        variadic::Variant,
+    },
+    ctx::{Import, ImportTarget, Resolver, SchemaFile},
+    defs::{Span, Spanned},
+    tokens::Repeated,
+};
+
+use crate::{
+    CompoundType, Definitions, Enum, ErrorTy, Field, FieldOrRef, FieldsList, Ident, Meta, Named,
+    OneOf, OneOfVariant, Operation, OperationKind, StrOrInt, Struct, Type, VariantKind, Version,
+    namespace::Namespace,
+    source::{SourceFile, SourceSpan},
+};
+
+pub const SCHEMA_EXT: &str = "pld";
+
+pub fn is_schema(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == SCHEMA_EXT)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Struct,
+    Enum,
+    OneOf,
+    Error,
+    Operation,
+    Alias,
+}
+
+impl Kind {
+    fn tag(&self) -> &'static str {
+        match self {
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::OneOf => "one_of",
+            Self::Error => "error",
+            Self::Operation => "operation",
+            Self::Alias => "type",
+        }
+    }
+}
+
+struct Decl {
+    file: Arc<SourceFile>,
+    imports: Arc<Imports>,
+    ns: Ident,
+    version: Option<Version>,
+    error: Option<Ident>,
+    op_kind: Option<OperationKind>,
+    item: Items,
+}
+
+struct Symbol {
+    kind: Kind,
+    /// every version of the declaration
+    decls: Vec<usize>,
+}
+
+#[derive(Default)]
+struct NsMeta {
+    version: Option<(Version, SourceSpan)>,
+    error: Option<(Ident, SourceSpan)>,
+}
+
+/// names bound by a file's `use` items
+#[derive(Default)]
+struct Imports {
+    items: BTreeMap<Ident, (Ident, Ident)>,
+    namespaces: BTreeMap<String, String>,
+}
+
+impl From<Vec<Import>> for Imports {
+    fn from(imports: Vec<Import>) -> Self {
+        let mut this = Self::default();
+        for import in imports {
+            match import.target {
+                ImportTarget::Files => {},
+                ImportTarget::Namespace { alias, namespace } => {
+                    this.namespaces
+                        .entry(alias)
+                        .or_insert(namespace);
+                },
+                ImportTarget::Item { namespace, name } => {
+                    this.items
+                        .entry(name.clone().into())
+                        .or_insert((namespace.into(), name.into()));
+                },
+            }
+        }
+        this
+    }
+}
+
+#[derive(Default)]
+struct ItemAttrs {
+    version: Option<Version>,
+    error: Option<Ident>,
+    kind: Option<OperationKind>,
+}
+
+/// lowers parsed `.pld` schemas into core [`Definitions`].
+///
+/// files are collected first, following their `use` items, so that references may point at
+/// declarations from any file in the package graph. they are then lowered together in
+/// [`Lowering::finish`].
+#[derive(Default)]
+pub struct Lowering {
+    resolver: Resolver,
+    decls: Vec<Decl>,
+    symbols: BTreeMap<(Ident, Ident), Symbol>,
+    namespaces: BTreeMap<Ident, NsMeta>,
+}
+
+impl Lowering {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> crate::Result<()> {
+        Ok(self.resolver.load(path)?)
+    }
+
+    pub fn add_source(
+        &mut self,
+        path: impl AsRef<Path>,
+        source: impl Into<Arc<str>>,
+    ) -> crate::Result<()> {
+        Ok(self.resolver.load_source(path, source)?)
+    }
+
+    /// adds a package's `schema/lib.pld`, along with everything it imports
+    pub fn add_package(
+        &mut self,
+        root: impl AsRef<Path>,
+    ) -> crate::Result<()> {
+        Ok(self.resolver.load_package(root)?)
+    }
+
+    fn add_schema(
+        &mut self,
+        schema: SchemaFile,
+    ) -> crate::Result<()> {
+        let file = SourceFile::new(schema.path, schema.source);
+        let imports = Arc::new(Imports::from(schema.imports));
+        self.add_ast(&file, &imports, schema.ast, None)
+    }
+
+    fn add_ast(
+        &mut self,
+        file: &Arc<SourceFile>,
+        imports: &Arc<Imports>,
+        ast: AstStream,
+        scope: Option<Ident>,
+    ) -> crate::Result<()> {
+        let nested = scope.is_some();
+        let mut current = scope;
+        let mut module_meta = Some(ast.module_meta);
+        if let Some(ns) = &current {
+            self.ns_attrs(file, ns, module_meta.take().unwrap())?;
+        }
+
+        for node in ast.nodes {
+            match node.value {
+                Items::Namespace(def) => {
+                    if nested || current.is_some() {
+                        return Err(file
+                            .span(&def.def.span)
+                            .error(operation_api_parser::Error::NsConflict.to_string()));
+                    }
+                    let ns: Ident = def.def.name.borrow_string().into();
+                    if let Some(meta) = module_meta.take() {
+                        self.ns_attrs(file, &ns, meta)?;
+                    }
+                    self.ns_attrs(file, &ns, def.meta)?;
+                    current = Some(ns);
+                },
+                Items::SpannedNamespace(def) => {
+                    let name = def.def.value.name.borrow_string();
+                    let ns: Ident = match &current {
+                        Some(parent) => format!("{parent}.{name}").into(),
+                        None => name.clone().into(),
+                    };
+                    self.ns_attrs(file, &ns, def.meta)?;
+                    self.add_ast(file, imports, def.def.value.ast.value, Some(ns))?;
+                },
+                Items::Meta(def) => {
+                    let ns = self.expect_ns(file, &current, &node.span)?;
+                    self.ns_attrs(file, &ns, def.meta)?;
+                },
+                // imports were resolved while loading the package graph
+                Items::Use(..) => {},
+                // schemas are parsed strictly, recovered placeholders never reach lowering
+                Items::Invalid(..) => {},
+                item => {
+                    let ns = self.expect_ns(file, &current, &node.span)?;
+                    self.declare(file, imports, ns, item)?;
+                },
+            }
+        }
+
+        if let Some(meta) = module_meta
+            && !meta.meta.is_empty()
+        {
+            return Err(file
+                .span(&meta.span)
+                .error(operation_api_parser::Error::NsNotDeclared.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn expect_ns(
+        &mut self,
+        file: &Arc<SourceFile>,
+        current: &Option<Ident>,
+        span: &Span,
+    ) -> crate::Result<Ident> {
+        match current {
+            Some(ns) => {
+                self.namespaces
+                    .entry(ns.clone())
+                    .or_default();
+                Ok(ns.clone())
+            },
+            None => {
+                Err(file
+                    .span(span)
+                    .error(operation_api_parser::Error::NsNotDeclared.to_string()))
+            },
+        }
+    }
+
+    fn ns_attrs(
+        &mut self,
+        file: &Arc<SourceFile>,
+        ns: &Ident,
+        meta: Spanned<ItemMeta>,
+    ) -> crate::Result<()> {
+        let attrs = item_attrs(file, &meta)?;
+        let entry = self
+            .namespaces
+            .entry(ns.clone())
+            .or_default();
+        let span = file.span(&meta.span);
+        if attrs.kind.is_some() {
+            return Err(span.error("only operations may declare a kind"));
+        }
+        if let Some(version) = attrs.version {
+            match &entry.version {
+                Some((existing, _)) if existing != &version => {
+                    return Err(span.error(format!(
+                        "namespace {ns} is declared with conflicting versions"
+                    )));
+                },
+                _ => entry.version = Some((version, span.clone())),
+            }
+        }
+        if let Some(error) = attrs.error {
+            match &entry.error {
+                Some((existing, _)) if existing != &error => {
+                    return Err(span.error(format!(
+                        "namespace {ns} is declared with conflicting error types"
+                    )));
+                },
+                _ => entry.error = Some((error, span)),
+            }
+        }
+        Ok(())
+    }
+
+    fn declare(
+        &mut self,
+        file: &Arc<SourceFile>,
+        imports: &Arc<Imports>,
+        ns: Ident,
+        item: Items,
+    ) -> crate::Result<()> {
+        let (kind, name, meta) = match &item {
+            Items::Struct(def) => (Kind::Struct, &def.def.name, &def.meta),
+            Items::Enum(def) => {
+                (
+                    Kind::Enum,
+                    match &def.def.value {
+                        enm::Enum::Int(e) => &e.name,
+                        enm::Enum::Str(e) => &e.name,
+                    },
+                    &def.meta,
+                )
+            },
+            Items::OneOf(def) => (Kind::OneOf, &def.def.name, &def.meta),
+            Items::Error(def) => (Kind::Error, &def.def.name, &def.meta),
+            Items::Operation(def) => (Kind::Operation, &def.def.name, &def.meta),
+            Items::Type(def) => {
+                let kind = match &def.def.ty.value {
+                    ast_ty::Type::Struct { .. } | ast_ty::Type::Union { .. } => Kind::Struct,
+                    ast_ty::Type::OneOf { .. } => Kind::OneOf,
+                    _ => Kind::Alias,
+                };
+                (kind, &def.def.name, &def.meta)
+            },
+            Items::Use(..)
+            | Items::Namespace(..)
+            | Items::SpannedNamespace(..)
+            | Items::Meta(..)
+            | Items::Invalid(..) => {
+                unreachable!("handled while walking the ast")
+            },
+        };
+
+        let ident: Ident = name.borrow_string().into();
+        let attrs = item_attrs(file, meta)?;
+        if attrs.error.is_some() && kind != Kind::Operation {
+            return Err(file
+                .span(&meta.span)
+                .error("only operations may declare an error type"));
+        }
+        if attrs.kind.is_some() && kind != Kind::Operation {
+            return Err(file
+                .span(&meta.span)
+                .error("only operations may declare a kind"));
+        }
+
+        let key = (ns.clone(), ident.clone());
+        let decl = self.decls.len();
+        match self.symbols.get_mut(&key) {
+            // versions of a declaration may be held side by side, so long as each is explicitly
+            // versioned
+            Some(existing)
+                if existing.kind == kind
+                    && attrs.version.is_some()
+                    && existing.decls.iter().all(|it| {
+                        let version = &self.decls[*it].version;
+                        version.is_some() && version != &attrs.version
+                    }) =>
+            {
+                existing.decls.push(decl);
+            },
+            Some(existing) => {
+                return Err(file.span(&name.span).error(
+                    crate::Error::NamespaceConflict {
+                        name: ident,
+                        tag: existing.kind.tag(),
+                        ns,
+                    }
+                    .to_string(),
+                ));
+            },
+            None => {
+                self.symbols.insert(
+                    key,
+                    Symbol {
+                        kind,
+                        decls: vec![decl],
+                    },
+                );
+            },
+        }
+        self.decls.push(Decl {
+            file: file.clone(),
+            imports: imports.clone(),
+            ns,
+            version: attrs.version,
+            error: attrs.error,
+            op_kind: attrs.kind,
+            item,
+        });
+        Ok(())
+    }
+
+    fn version(
+        &self,
+        decl: &Decl,
+    ) -> Version {
+        decl.version
+            .clone()
+            .or_else(|| {
+                self.namespaces
+                    .get(&decl.ns)
+                    .and_then(|ns| ns.version.as_ref())
+                    .map(|(version, _)| version.clone())
+            })
+            .unwrap_or_default()
+    }
+
+    /// the latest version of a declaration, which unpinned references point at
+    fn latest(
+        &self,
+        symbol: &Symbol,
+    ) -> &Decl {
+        symbol
+            .decls
+            .iter()
+            .map(|it| &self.decls[*it])
+            .max_by_key(|decl| self.version(decl))
+            .expect("symbols hold at least one declaration")
+    }
+
+    /// lowers all collected files into one [`Definitions::NamespaceV1`] per declared namespace
+    pub fn finish(mut self) -> crate::Result<Vec<Definitions>> {
+        for schema in std::mem::take(&mut self.resolver).finish()? {
+            self.add_schema(schema)?;
+        }
+
+        let mut lower = Lower {
+            lowering: &self,
+            namespaces: self
+                .namespaces
+                .iter()
+                .map(|(name, meta)| {
+                    let mut ns = Namespace::new(name.clone());
+                    if let Some((version, _)) = &meta.version {
+                        ns.version = version.clone();
+                    }
+                    (name.clone(), ns)
+                })
+                .collect(),
+            synthesized: BTreeSet::new(),
+            aliases: vec![],
+        };
+
+        for decl in &self.decls {
+            lower.decl(decl)?;
+            for key in decl.imports.items.values() {
+                lower.reference(decl, key, None);
+            }
+        }
+
+        Ok(lower
+            .namespaces
+            .into_values()
+            .map(Definitions::NamespaceV1)
+            .collect())
+    }
+}
+
+struct Lower<'a> {
+    lowering: &'a Lowering,
+    namespaces: BTreeMap<Ident, Namespace>,
+    synthesized: BTreeSet<(Ident, Ident)>,
+    aliases: Vec<(Ident, Ident)>,
+}
+
+impl<'a> Lower<'a> {
+    fn decl(
+        &mut self,
+        decl: &Decl,
+    ) -> crate::Result<()> {
+        let def = match &decl.item {
+            Items::Struct(def) => {
+                let name = def.def.name.borrow_string();
+                let fields = self.fields(decl, name, def.def.args.values.iter())?;
+                Definitions::StructV1(Struct {
+                    meta: self.meta(decl, &def.def.name, &def.comments),
+                    fields,
+                })
+            },
+            Items::Enum(def) => {
+                Definitions::EnumV1(match &def.def.value {
+                    enm::Enum::Int(e) => {
+                        let mut next = 0;
+                        let variants = self.variants(decl, &e.variants.value, false, |v| {
+                            let value = match &v.value {
+                                Some(value) => *value.value.borrow_i32() as usize,
+                                None => next,
+                            };
+                            next = value + 1;
+                            StrOrInt::Int(value)
+                        })?;
+                        Enum {
+                            meta: self.meta(decl, &e.name, &def.comments),
+                            variants,
+                        }
+                    },
+                    enm::Enum::Str(e) => {
+                        let variants = self.variants(decl, &e.variants.value, true, |v| {
+                            StrOrInt::String(match &v.value {
+                                Some(value) => value.value.borrow_string().clone(),
+                                None => v.name.borrow_string().clone(),
+                            })
+                        })?;
+                        Enum {
+                            meta: self.meta(decl, &e.name, &def.comments),
+                            variants,
+                        }
+                    },
+                })
+            },
+            Items::OneOf(def) => {
+                let name = def.def.name.borrow_string();
+                Definitions::OneOfV1(OneOf {
+                    meta: self.meta(decl, &def.def.name, &def.comments),
+                    variants: self.variadic(decl, name, &def.def.variants)?,
+                })
+            },
+            Items::Error(def) => {
+                let name = def.def.name.borrow_string();
+                Definitions::ErrorV1(ErrorTy {
+                    meta: self.meta(decl, &def.def.name, &def.comments),
+                    variants: self.variadic(decl, name, &def.def.variants)?,
+                })
+            },
+            Items::Operation(def) => Definitions::OperationV1(self.operation(decl, def)?),
+            Items::Type(def) => {
+                let name = def.def.name.borrow_string();
+                let meta = self.meta(decl, &def.def.name, &def.comments);
+                match &def.def.ty.value {
+                    ast_ty::Type::Struct { ty } => {
+                        Definitions::StructV1(Struct {
+                            meta,
+                            fields: self.fields(decl, name, ty.fields.values.iter())?,
+                        })
+                    },
+                    ast_ty::Type::OneOf { ty } => {
+                        Definitions::OneOfV1(OneOf {
+                            meta,
+                            variants: self.anonymous_variants(decl, name, ty)?,
+                        })
+                    },
+                    ast_ty::Type::Union { ty } => {
+                        let fields = self.with_alias(decl, &def.def.name, |this| {
+                            this.intersect(decl, &ty.value)
+                        })?;
+                        Definitions::StructV1(Struct { meta, fields })
+                    },
+                    ty => {
+                        // plain aliases are inlined where they are referenced, but are still
+                        // lowered here so that errors surface even when unused
+                        let span = decl.file.span(&def.def.ty.span);
+                        self.with_alias(decl, &def.def.name, |this| {
+                            this.ty(decl, ty, &span, name)
+                        })?;
+                        return Ok(());
+                    },
+                }
+            },
+            Items::Use(..)
+            | Items::Namespace(..)
+            | Items::SpannedNamespace(..)
+            | Items::Meta(..)
+            | Items::Invalid(..) => {
+                unreachable!("not declared")
+            },
+        };
+
+        self.emit(&decl.ns, def)
+    }
+
+    fn emit(
+        &mut self,
+        ns: &Ident,
+        def: Definitions,
+    ) -> crate::Result<()> {
+        let span = match &def {
+            Definitions::StructV1(v) => v.meta.span.clone(),
+            Definitions::OperationV1(v) => v.meta.span.clone(),
+            Definitions::EnumV1(v) => v.meta.span.clone(),
+            Definitions::OneOfV1(v) => v.meta.span.clone(),
+            Definitions::ErrorV1(v) => v.meta.span.clone(),
+            _ => None,
+        };
+        let res = self
+            .namespaces
+            .get_mut(ns)
+            .expect("namespace is registered while declaring")
+            .with_definition(def);
+        match (res, span) {
+            (Err(err), Some(span)) => Err(span.error(err.to_string())),
+            (res, _) => res,
+        }
+    }
+
+    /// emits a definition which was derived from an anonymous type. these may be produced more
+    /// than once when an alias is inlined, so only the first is kept.
+    fn synthesize(
+        &mut self,
+        ns: &Ident,
+        def: Definitions,
+    ) -> crate::Result<()> {
+        if self
+            .synthesized
+            .insert((ns.clone(), def.name().clone()))
+        {
+            self.emit(ns, def)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn with_alias<T>(
+        &mut self,
+        decl: &Decl,
+        name: &Spanned<operation_api_parser::tokens::IdentToken>,
+        f: impl FnOnce(&mut Self) -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let key = (decl.ns.clone(), Ident::from(name.borrow_string()));
+        if self.aliases.contains(&key) {
+            let chain = self
+                .aliases
+                .iter()
+                .skip_while(|it| *it != &key)
+                .chain(std::iter::once(&key))
+                .map(|(_, name)| name.to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(decl
+                .file
+                .span(&name.span)
+                .error(format!("type alias {} is cyclic: {chain}", key.1)));
+        }
+        self.aliases.push(key);
+        let res = f(self);
+        self.aliases.pop();
+        res
+    }
+
+    fn version(
+        &self,
+        decl: &Decl,
+    ) -> Version {
+        self.lowering.version(decl)
+    }
+
+    fn meta(
+        &self,
+        decl: &Decl,
+        name: &Spanned<operation_api_parser::tokens::IdentToken>,
+        comments: &CommentStream,
+    ) -> Meta<Ident, Ident, Version> {
+        Meta {
+            name: name.borrow_string().into(),
+            namespace: decl.ns.clone(),
+            description: comments.description(),
+            version: self.version(decl),
+            span: Some(decl.file.span(&name.span)),
+        }
+    }
+
+    fn synthesized_meta(
+        &self,
+        decl: &Decl,
+        name: &str,
+        span: &Span,
+    ) -> Meta<Ident, Ident, Version> {
+        Meta {
+            name: name.into(),
+            namespace: decl.ns.clone(),
+            description: None,
+            version: self.version(decl),
+            span: Some(decl.file.span(span)),
+        }
+    }
+
+    fn fields<'b>(
+        &mut self,
+        decl: &Decl,
+        parent: &str,
+        args: impl Iterator<Item = &'b operation_api_parser::tokens::RepeatedItem<Arg, Comma>>,
+    ) -> crate::Result<FieldsList> {
+        let mut fields: FieldsList = Named::default();
+        for arg in args {
+            let arg = &arg.value;
+            let name = arg.name.borrow_string();
+            let span = decl.file.span(&arg.name.span);
+            let optional = matches!(arg.sep.value, Sep::Optional { .. });
+
+            let mut ty = self.ty(decl, &arg.typ, &span, &format!("{parent}_{name}"))?;
+            if optional {
+                ty = Type::CompoundType(CompoundType::Option { ty: Box::new(ty) });
+            }
+
+            let attrs = field_attrs(&decl.file, &arg.meta)?;
+            if let Some(tag) = attrs.tag
+                && let Some((other, _)) = fields
+                    .iter()
+                    .find(|(_, it)| it.unwrap_value().tag == Some(tag))
+            {
+                return Err(span.error(format!(
+                    "field {name} declares tag {tag}, which is already declared by {other}"
+                )));
+            }
+            let field = Field {
+                meta: Meta {
+                    name: Some(name.into()),
+                    namespace: Some(decl.ns.clone()),
+                    description: arg.comments.description(),
+                    version: None,
+                    span: Some(span.clone()),
+                },
+                ty,
+                optional,
+                alias: attrs.alias,
+                tag: attrs.tag,
+            };
+            if fields
+                .insert(name.into(), FieldOrRef::Value(field))
+                .is_some()
+            {
+                return Err(span.error(format!("field {name} is declared multiple times")));
+            }
+        }
+        Ok(fields)
+    }
+
+    fn variants<V: operation_api_parser::Parse + operation_api_parser::Peek>(
+        &self,
+        decl: &Decl,
+        variants: &Repeated<enm::EnumVariant<V>, Comma>,
+        tagged: bool,
+        mut value: impl FnMut(&enm::EnumVariant<V>) -> StrOrInt,
+    ) -> crate::Result<Named<VariantKind>> {
+        let mut out: Named<VariantKind> = Named::default();
+        for variant in &variants.values {
+            let variant = &variant.value.value;
+            let name = variant.name.borrow_string();
+            let span = decl.file.span(&variant.name.span);
+            let tag = variant_tag(&decl.file, &variant.meta, tagged)?;
+            if let Some(tag) = tag
+                && let Some((other, _)) = out
+                    .iter()
+                    .find(|(_, it)| it.tag == Some(tag))
+            {
+                return Err(span.error(format!(
+                    "variant {name} declares tag {tag}, which is already declared by {other}"
+                )));
+            }
+            let kind = VariantKind {
+                meta: Meta {
+                    name: name.into(),
+                    namespace: Some(decl.ns.clone()),
+                    description: variant.comments.description(),
+                    version: None,
+                    span: Some(span.clone()),
+                },
+                value: value(variant),
+                tag,
+            };
+            if out.insert(name.into(), kind).is_some() {
+                return Err(span.error(format!("variant {name} is declared multiple times")));
+            }
+        }
+        Ok(out)
+    }
+
+    fn variadic(
+        &mut self,
+        decl: &Decl,
+        parent: &str,
+        variants: &Repeated<Variant, Comma>,
+    ) -> crate::Result<Named<OneOfVariant>> {
+        let mut out: Named<OneOfVariant> = Named::default();
+        for variant in &variants.values {
+            let (name, comments, meta, ty, inline) = match &variant.value.value {
+                Variant::Tuple {
+                    comments,
+                    meta,
+                    name,
+                    inner,
+                    ..
+                } => {
+                    let span = decl.file.span(&name.span);
+                    let hint = format!("{parent}_{}", name.borrow_string());
+                    (
+                        name,
+                        comments,
+                        meta,
+                        self.ty(decl, inner, &span, &hint)?,
+                        false,
+                    )
+                },
+                Variant::LocalStruct {
+                    comments,
+                    meta,
+                    name,
+                    inner,
+                } => {
+                    let hint = format!("{parent}_{}", name.borrow_string());
+                    (
+                        name,
+                        comments,
+                        meta,
+                        self.anonymous_struct(decl, &inner.value, &name.span, &hint)?,
+                        true,
+                    )
+                },
+            };
+
+            let span = decl.file.span(&name.span);
+            let ident: Ident = name.borrow_string().into();
+            let tag = variant_tag(&decl.file, meta, true)?;
+            if let Some(tag) = tag
+                && let Some((other, _)) = out
+                    .iter()
+                    .find(|(_, it)| it.tag == Some(tag))
+            {
+                return Err(span.error(format!(
+                    "variant {ident} declares tag {tag}, which is already declared by {other}"
+                )));
+            }
+            let variant = OneOfVariant {
+                name: ident.clone(),
+                description: comments.description(),
+                ty,
+                tag,
+                inline,
+                span: Some(span.clone()),
+            };
+            if out.insert(ident.clone(), variant).is_some() {
+                return Err(span.error(format!("variant {ident} is declared multiple times")));
+            }
+        }
+        Ok(out)
+    }
+
+    fn anonymous_variants(
+        &mut self,
+        decl: &Decl,
+        parent: &str,
+        one_of: &AnonymousOneOf,
+    ) -> crate::Result<Named<OneOfVariant>> {
+        let mut out = Named::default();
+        for variant in &one_of.variants.value.values {
+            let span = decl.file.span(&variant.value.span);
+            let name = variant_name(&variant.value.value);
+            let ty = self.ty(
+                decl,
+                &variant.value.value,
+                &span,
+                &format!("{parent}_{name}"),
+            )?;
+            let ident: Ident = name.into();
+            let variant = OneOfVariant {
+                name: ident.clone(),
+                description: None,
+                ty,
+                tag: None,
+                inline: false,
+                span: Some(span.clone()),
+            };
+            if out.insert(ident.clone(), variant).is_some() {
+                return Err(span.error(format!("variant {ident} is declared multiple times")));
+            }
+        }
+        Ok(out)
+    }
+
+    fn anonymous_struct(
+        &mut self,
+        decl: &Decl,
+        def: &AnonymousStruct,
+        span: &Span,
+        hint: &str,
+    ) -> crate::Result<Type> {
+        let name = hint.to_case(Case::Pascal);
+        let fields = self.fields(decl, &name, def.fields.values.iter())?;
+        self.synthesize(
+            &decl.ns,
+            Definitions::StructV1(Struct {
+                meta: self.synthesized_meta(decl, &name, span),
+                fields,
+            }),
+        )?;
+        Ok(Type::CompoundType(CompoundType::Struct { to: name.into() }))
+    }
+
+    fn operation(
+        &mut self,
+        decl: &Decl,
+        def: &operation_api_parser::ast::items::OperationDef,
+    ) -> crate::Result<Operation> {
+        let op = &def.def.value;
+        let name = op.name.borrow_string();
+        let inputs = match &op.args {
+            Some(args) => self.fields(decl, name, args.value.values.iter())?,
+            None => Named::default(),
+        };
+
+        let (infallible, ret, ret_span) = match &op.return_type.value {
+            ast_ty::Type::Result { ty, .. } => (false, ty.value.as_ref(), &ty.span),
+            ty => (true, ty, &op.return_type.span),
+        };
+
+        let output = format!("{name}_output");
+        let outputs = match ret {
+            ast_ty::Type::Struct { ty } => self.fields(decl, &output, ty.fields.values.iter())?,
+            ast_ty::Type::Builtin { ty } if matches!(ty.value, Builtin::Never(..)) => {
+                Named::default()
+            },
+            ty => {
+                let span = decl.file.span(ret_span);
+                let ty = self.ty(decl, ty, &span, &output)?;
+                let mut outputs = Named::default();
+                outputs.insert(
+                    "value".into(),
+                    FieldOrRef::Value(Field {
+                        meta: Meta {
+                            name: None,
+                            namespace: None,
+                            description: None,
+                            version: None,
+                            span: Some(span),
+                        },
+                        ty,
+                        optional: false,
+                        alias: None,
+                        tag: None,
+                    }),
+                );
+                outputs
+            },
+        };
+
+        let error = if infallible {
+            None
+        } else {
+            decl.error
+                .clone()
+                .or_else(|| {
+                    self.lowering
+                        .namespaces
+                        .get(&decl.ns)
+                        .and_then(|ns| ns.error.as_ref())
+                        .map(|(error, _)| error.clone())
+                })
+                .map(|error| {
+                    let segments = error
+                        .to_string()
+                        .split("::")
+                        .map(String::from)
+                        .collect::<Vec<_>>();
+                    self.key(decl, &segments)
+                })
+        };
+        if let Some(key) = &error {
+            self.reference(decl, key, None);
+        }
+        let error = error.map(|key| qualified(decl, &key));
+
+        Ok(Operation {
+            meta: self.meta(decl, &op.name, &def.comments),
+            infallible,
+            error,
+            kind: decl.op_kind.unwrap_or_default(),
+            inputs,
+            outputs,
+        })
+    }
+
+    fn ty(
+        &mut self,
+        decl: &Decl,
+        ty: &ast_ty::Type,
+        span: &SourceSpan,
+        hint: &str,
+    ) -> crate::Result<Type> {
+        Ok(match ty {
+            ast_ty::Type::Builtin { ty } => builtin(&decl.file, ty)?,
+            ast_ty::Type::Ident { to, pin } => self.resolve(decl, to, pin.as_ref())?,
+            ast_ty::Type::OneOf { ty } => {
+                let name = hint.to_case(Case::Pascal);
+                let variants = self.anonymous_variants(decl, &name, &ty.value)?;
+                self.synthesize(
+                    &decl.ns,
+                    Definitions::OneOfV1(OneOf {
+                        meta: self.synthesized_meta(decl, &name, &ty.span),
+                        variants,
+                    }),
+                )?;
+                Type::CompoundType(CompoundType::OneOf { to: name.into() })
+            },
+            ast_ty::Type::Array { ty } => {
+                match &ty.value {
+                    Array::Unsized { ty, .. } => {
+                        Type::CompoundType(CompoundType::Array {
+                            ty: Box::new(self.ty(decl, &ty.value, span, hint)?),
+                        })
+                    },
+                    Array::Sized { ty, size, .. } => {
+                        Type::CompoundType(CompoundType::SizedArray {
+                            size: *size.borrow_i32() as usize,
+                            ty: Box::new(self.ty(decl, &ty.value, span, hint)?),
+                        })
+                    },
+                }
+            },
+            ast_ty::Type::Paren { ty, .. } => self.ty(decl, &ty.value, span, hint)?,
+            ast_ty::Type::Struct { ty } => {
+                self.anonymous_struct(decl, &ty.value, &ty.span, hint)?
+            },
+            ast_ty::Type::Union { ty } => {
+                let name = hint.to_case(Case::Pascal);
+                let fields = self.intersect(decl, &ty.value)?;
+                self.synthesize(
+                    &decl.ns,
+                    Definitions::StructV1(Struct {
+                        meta: self.synthesized_meta(decl, &name, &ty.span),
+                        fields,
+                    }),
+                )?;
+                Type::CompoundType(CompoundType::Struct { to: name.into() })
+            },
+            ast_ty::Type::Result { ex, .. } => {
+                return Err(decl
+                    .file
+                    .span(&ex.span)
+                    .error("result types may only be returned from operations"));
+            },
+        })
+        .map_err(|err: crate::Error| {
+            match err {
+                crate::Error::Miette(..) => err,
+                err => span.error(err.to_string()),
+            }
+        })
+    }
+
+    fn lookup<'p>(
+        &self,
+        decl: &Decl,
+        to: &'p PathOrIdent,
+    ) -> crate::Result<((Ident, Ident), &'a Symbol, &'p Span)> {
+        let (key, span) = match to {
+            PathOrIdent::Ident(ident) => {
+                (
+                    self.key(decl, std::slice::from_ref(ident.borrow_string())),
+                    &ident.span,
+                )
+            },
+            PathOrIdent::Path(path) => {
+                (
+                    self.key(decl, path.borrow_path_inner().segments()),
+                    &path.span,
+                )
+            },
+        };
+
+        let lowering: &'a Lowering = self.lowering;
+        match lowering.symbols.get(&key) {
+            Some(symbol) => Ok((key, symbol, span)),
+            None => {
+                let (ns, name) = key;
+                Err(decl
+                    .file
+                    .span(span)
+                    .error(crate::Error::NameNotFound { name, ns }.to_string()))
+            },
+        }
+    }
+
+    /// records a reference to a declaration, by the span of the last segment of its path
+    fn reference(
+        &mut self,
+        decl: &Decl,
+        (ns, name): &(Ident, Ident),
+        span: Option<&Span>,
+    ) {
+        let Some(ns) = self.namespaces.get_mut(ns) else {
+            return;
+        };
+        let references = ns
+            .references
+            .entry(name.clone())
+            .or_default();
+        match span {
+            Some(span) => {
+                let start = span.end - name.to_string().len();
+                references
+                    .spans
+                    .push(decl.file.span(&Span::new(start, span.end)));
+            },
+            None => references.untracked = true,
+        }
+    }
+
+    /// the namespace and name a path points at, following the imports of the declaring file
+    fn key(
+        &self,
+        decl: &Decl,
+        segments: &[String],
+    ) -> (Ident, Ident) {
+        match segments.split_last() {
+            Some((name, [])) => {
+                let name = Ident::from(name);
+                let local = (decl.ns.clone(), name.clone());
+                match decl.imports.items.get(&name) {
+                    Some(imported) if !self.lowering.symbols.contains_key(&local) => {
+                        imported.clone()
+                    },
+                    _ => local,
+                }
+            },
+            Some((name, ns)) => {
+                let ns = match decl.imports.namespaces.get(&ns[0]) {
+                    Some(alias) => {
+                        std::iter::once(alias.as_str())
+                            .chain(ns[1..].iter().map(String::as_str))
+                            .collect::<Vec<_>>()
+                            .join(".")
+                    },
+                    None => ns.join("."),
+                };
+                (ns.into(), name.into())
+            },
+            None => unreachable!("paths have at least one segment"),
+        }
+    }
+
+    /// the declaration a reference points at, which is its latest version unless pinned to one
+    fn target(
+        &self,
+        decl: &Decl,
+        (_, name): &(Ident, Ident),
+        symbol: &'a Symbol,
+        pin: Option<&Pin>,
+    ) -> crate::Result<&'a Decl> {
+        let lowering: &'a Lowering = self.lowering;
+        let Some(pin) = pin else {
+            return Ok(lowering.latest(symbol));
+        };
+        let version = Version::new(*pin.version.value.borrow_i32() as usize);
+        symbol
+            .decls
+            .iter()
+            .map(|it| &lowering.decls[*it])
+            .find(|it| lowering.version(it) == version)
+            .ok_or_else(|| {
+                decl.file
+                    .span(&pin.version.span)
+                    .error(format!("{name} has no version {version}"))
+            })
+    }
+
+    fn resolve(
+        &mut self,
+        decl: &Decl,
+        to: &PathOrIdent,
+        pin: Option<&Pin>,
+    ) -> crate::Result<Type> {
+        let (key, symbol, span) = self.lookup(decl, to)?;
+        if matches!(symbol.kind, Kind::Struct | Kind::Enum | Kind::OneOf) {
+            self.reference(decl, &key, Some(span));
+        }
+
+        let target = self.target(decl, &key, symbol, pin)?;
+        let to = match pin {
+            Some(..) => {
+                format!(
+                    "{}@{}",
+                    qualified(decl, &key),
+                    self.lowering.version(target)
+                )
+                .into()
+            },
+            None => qualified(decl, &key),
+        };
+        Ok(match symbol.kind {
+            Kind::Struct => Type::CompoundType(CompoundType::Struct { to }),
+            Kind::Enum => Type::CompoundType(CompoundType::Enum { to }),
+            Kind::OneOf => Type::CompoundType(CompoundType::OneOf { to }),
+            Kind::Alias => {
+                let Items::Type(def) = &target.item else {
+                    unreachable!("aliases are declared from type items")
+                };
+                let alias_span = target.file.span(&def.def.ty.span);
+                let name = def.def.name.borrow_string();
+                self.with_alias(target, &def.def.name, |this| {
+                    this.ty(target, &def.def.ty.value, &alias_span, name)
+                })?
+            },
+            kind @ (Kind::Error | Kind::Operation) => {
+                return Err(decl.file.span(span).error(format!(
+                    "{} is declared as an {}, and cannot be used as a type",
+                    key.1,
+                    kind.tag()
+                )));
+            },
+        })
+    }
+
+    /// merges the fields of each operand, giving priority to the left-most operand
+    fn intersect(
+        &mut self,
+        decl: &Decl,
+        union: &Union,
+    ) -> crate::Result<FieldsList> {
+        let mut fields = Named::default();
+        for operand in &union.types.values {
+            let merged = match &operand.value.value {
+                IdentOrUnion::Ident(to) => self.operand_fields(decl, to, None)?,
+                IdentOrUnion::Union { inner, .. } => self.intersect(decl, &inner.value)?,
+            };
+            for (name, field) in merged.iter() {
+                if !fields.contains_key(name) {
+                    fields.insert(name.clone(), field.clone());
+                }
+            }
+        }
+        Ok(fields)
+    }
+
+    fn operand_fields(
+        &mut self,
+        decl: &Decl,
+        to: &PathOrIdent,
+        pin: Option<&Pin>,
+    ) -> crate::Result<FieldsList> {
+        let (key, symbol, span) = self.lookup(decl, to)?;
+        let target = self.target(decl, &key, symbol, pin)?;
+        match &target.item {
+            Items::Struct(def) => {
+                let name = def.def.name.borrow_string();
+                self.fields(target, name, def.def.args.values.iter())
+            },
+            Items::Type(def) => {
+                let name = def.def.name.borrow_string();
+                let ty_span = &def.def.ty.span;
+                self.with_alias(target, &def.def.name, |this| {
+                    this.alias_fields(target, &def.def.ty.value, ty_span, name)
+                })
+            },
+            _ => {
+                Err(decl.file.span(span).error(format!(
+                    "{} is declared as an {}, only structs may be intersected",
+                    key.1,
+                    symbol.kind.tag()
+                )))
+            },
+        }
+    }
+
+    fn alias_fields(
+        &mut self,
+        decl: &Decl,
+        ty: &ast_ty::Type,
+        span: &Span,
+        name: &str,
+    ) -> crate::Result<FieldsList> {
+        match ty {
+            ast_ty::Type::Ident { to, pin } => self.operand_fields(decl, to, pin.as_ref()),
+            ast_ty::Type::Paren { ty, .. } => self.alias_fields(decl, &ty.value, &ty.span, name),
+            ast_ty::Type::Struct { ty } => self.fields(decl, name, ty.fields.values.iter()),
+            ast_ty::Type::Union { ty } => self.intersect(decl, &ty.value),
+            _ => {
+                Err(decl.file.span(span).error(format!(
+                    "{name} is not a struct, only structs may be intersected"
+                )))
+            },
+        }
+    }
+}
+
+type Comma = operation_api_parser::tokens::CommaToken;
+
+fn item_attrs(
+    file: &Arc<SourceFile>,
+    meta: &Spanned<ItemMeta>,
+) -> crate::Result<ItemAttrs> {
+    let mut attrs = ItemAttrs::default();
+    let mut versions = vec![];
+    for item in &meta.meta {
+        match item {
+            ItemMetaItem::Version(version) => {
+                let value = *version.value.value.borrow_i32() as usize;
+                versions.push((value, version.span.clone()));
+                attrs.version = Some(value.into());
+            },
+            ItemMetaItem::Error(error) => {
+                attrs.error = Some(path_or_ident(&error.value.value));
+            },
+            ItemMetaItem::Alias(alias) => {
+                return Err(file
+                    .span(&alias.span)
+                    .error("only fields may declare an alias"));
+            },
+            ItemMetaItem::Tag(tag) => {
+                return Err(file
+                    .span(&tag.span)
+                    .error("only fields and variants may declare a tag"));
+            },
+            ItemMetaItem::Kind(kind) if attrs.kind.is_none() => {
+                let value = &kind.value.value;
+                attrs.kind = match &value.value {
+                    PathOrIdent::Ident(ident) if ident.borrow_string() == "query" => {
+                        Some(OperationKind::Query)
+                    },
+                    PathOrIdent::Ident(ident) if ident.borrow_string() == "mutation" => {
+                        Some(OperationKind::Mutation)
+                    },
+                    _ => {
+                        return Err(file
+                            .span(&value.span)
+                            .error("the kind of an operation is either query or mutation"));
+                    },
+                };
+            },
+            ItemMetaItem::Kind(kind) => {
+                return Err(file
+                    .span(&kind.span)
+                    .error("an operation may only declare one kind"));
+            },
+        }
+    }
+
+    if versions.len() > 1 {
+        let err = operation_api_parser::Error::VersionConflict {
+            values: versions.iter().map(|(v, _)| *v).collect(),
+            spans: versions
+                .iter()
+                .map(|(_, span)| (span.start, span.end))
+                .collect(),
+        };
+        return Err(file
+            .span(&versions[1].1)
+            .error(err.to_string()));
+    }
+
+    Ok(attrs)
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    alias: Option<Ident>,
+    tag: Option<u32>,
+}
+
+/// the name a field had in the previous version of its struct and its number, if it declares them
+fn field_attrs(
+    file: &Arc<SourceFile>,
+    meta: &Spanned<ItemMeta>,
+) -> crate::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for item in &meta.meta {
+        match item {
+            ItemMetaItem::Alias(meta) if attrs.alias.is_none() => {
+                let value = &meta.value.value;
+                match &value.value {
+                    PathOrIdent::Ident(ident) => attrs.alias = Some(ident.borrow_string().into()),
+                    PathOrIdent::Path(..) => {
+                        return Err(file
+                            .span(&value.span)
+                            .error("an alias must name a field"));
+                    },
+                }
+            },
+            ItemMetaItem::Alias(meta) => {
+                return Err(file
+                    .span(&meta.span)
+                    .error("a field may only declare one alias"));
+            },
+            ItemMetaItem::Tag(meta) if attrs.tag.is_none() => {
+                attrs.tag = Some(tag(file, meta)?);
+            },
+            ItemMetaItem::Tag(meta) => {
+                return Err(file
+                    .span(&meta.span)
+                    .error("a field may only declare one tag"));
+            },
+            ItemMetaItem::Version(Spanned { span, .. })
+            | ItemMetaItem::Error(Spanned { span, .. })
+            | ItemMetaItem::Kind(Spanned { span, .. }) => {
+                return Err(file
+                    .span(span)
+                    .error("fields may only declare an alias or a tag"));
+            },
+        }
+    }
+    Ok(attrs)
+}
+
+/// the number a variant declares, where its kind of variant is numbered by a tag
+fn variant_tag(
+    file: &Arc<SourceFile>,
+    meta: &Spanned<ItemMeta>,
+    tagged: bool,
+) -> crate::Result<Option<u32>> {
+    let mut out = None;
+    for item in &meta.meta {
+        match item {
+            ItemMetaItem::Tag(meta) if !tagged => {
+                return Err(file
+                    .span(&meta.span)
+                    .error("variants of int enums are numbered by their value, not a tag"));
+            },
+            ItemMetaItem::Tag(meta) if out.is_none() => out = Some(tag(file, meta)?),
+            ItemMetaItem::Tag(meta) => {
+                return Err(file
+                    .span(&meta.span)
+                    .error("a variant may only declare one tag"));
+            },
+            ItemMetaItem::Version(Spanned { span, .. })
+            | ItemMetaItem::Error(Spanned { span, .. })
+            | ItemMetaItem::Alias(Spanned { span, .. })
+            | ItemMetaItem::Kind(Spanned { span, .. }) => {
+                return Err(file
+                    .span(span)
+                    .error("variants may only declare a tag"));
+            },
+        }
+    }
+    Ok(out)
+}
+
+fn tag(
+    file: &Arc<SourceFile>,
+    meta: &Spanned<IntMeta>,
+) -> crate::Result<u32> {
+    let value = *meta.value.value.borrow_i32();
+    if value < 1 {
+        return Err(file
+            .span(&meta.value.value.span)
+            .error(format!("a tag must be positive, found {value}")));
+    }
+    Ok(value as u32)
+}
+
+/// refs are local to their namespace in core definitions, unless they point into another one
+fn qualified(
+    decl: &Decl,
+    (ns, name): &(Ident, Ident),
+) -> Ident {
+    if ns == &decl.ns {
+        name.clone()
+    } else {
+        format!("{ns}::{name}").into()
+    }
+}
+
+fn path_or_ident(value: &PathOrIdent) -> Ident {
+    match value {
+        PathOrIdent::Ident(ident) => ident.borrow_string().into(),
+        PathOrIdent::Path(path) => path.borrow_path_inner().to_string().into(),
+    }
+}
+
+fn variant_name(ty: &ast_ty::Type) -> String {
+    match ty {
+        ast_ty::Type::Builtin { ty } => {
+            let mut p = operation_api_parser::fmt::Printer::default();
+            p.write(&ty.value);
+            p.buf.to_case(Case::Pascal)
+        },
+        ast_ty::Type::Ident { to, .. } => {
+            match to {
+                PathOrIdent::Ident(ident) => ident.borrow_string().clone(),
+                PathOrIdent::Path(path) => {
+                    path.borrow_path_inner()
+                        .segments()
+                        .last()
+                        .cloned()
+                        .unwrap_or_default()
+                },
+            }
+        },
+        ast_ty::Type::Array { ty } => {
+            let inner = match &ty.value {
+                Array::Unsized { ty, .. } | Array::Sized { ty, .. } => &ty.value,
+            };
+            format!("{}Array", variant_name(inner))
+        },
+        ast_ty::Type::Paren { ty, .. } => variant_name(&ty.value),
+        ast_ty::Type::Struct { .. } => "Struct".into(),
+        ast_ty::Type::OneOf { .. } => "OneOf".into(),
+        ast_ty::Type::Union { .. } => "Union".into(),
+        ast_ty::Type::Result { ty, .. } => variant_name(&ty.value),
+    }
+}
+
+fn builtin(
+    file: &Arc<SourceFile>,
+    ty: &Spanned<Builtin>,
+) -> crate::Result<Type> {
+    Ok(match &ty.value {
+        Builtin::I8(..) => Type::I8,
+        Builtin::I16(..) => Type::I16,
+        Builtin::I32(..) => Type::I32,
+        Builtin::I64(..) => Type::I64,
+        Builtin::U8(..) => Type::U8,
+        Builtin::U16(..) => Type::U16,
+        Builtin::U32(..) => Type::U32,
+        Builtin::U64(..) => Type::U64,
+        Builtin::Usize(..) => Type::Usize,
+        Builtin::F32(..) => Type::F32,
+        Builtin::F64(..) => Type::F64,
+        Builtin::Bool(..) => Type::Bool,
+        Builtin::Str(..) => Type::String,
+        Builtin::DateTime(..) => Type::DateTime,
+        Builtin::Complex(..) => Type::Complex,
+        Builtin::Binary(..) => Type::Binary,
+        Builtin::Never(..) => Type::Never,
+        Builtin::F16(..) => {
+            return Err(file
+                .span(&ty.span)
+                .error("f16 is not supported by code generation"));
+        },
+    })
+}
+
+/// lowers a single schema file
+pub fn load_from_path(path: PathBuf) -> crate::Result<Vec<Definitions>> {
+    let mut lowering = Lowering::new();
+    lowering.add_file(path)?;
+    lowering.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::{
+        CompoundType, Definitions, FieldOrRef, Ident, Key, StrOrInt, Type, namespace::Namespace,
+    };
+
+    use super::Lowering;
+
+    fn lower(source: &str) -> crate::Result<Vec<Namespace>> {
+        let mut lowering = Lowering::new();
+        lowering.add_source("test.pld", source)?;
+        Ok(lowering
+            .finish()?
+            .into_iter()
+            .map(|def| {
+                match def {
+                    Definitions::NamespaceV1(ns) => ns,
+                    _ => panic!("expected namespace"),
+                }
+            })
+            .collect())
+    }
+
+    fn lower_one(source: &str) -> Namespace {
+        let mut namespaces = lower(source).unwrap();
+        assert_eq!(namespaces.len(), 1);
+        namespaces.remove(0)
+    }
+
+    #[test_case::test_case("array"; "arrays")]
+    #[test_case::test_case("bench_enum"; "bench enum")]
+    #[test_case::test_case("bench_struct"; "bench struct")]
+    #[test_case::test_case("complex_union"; "complex union")]
+    #[test_case::test_case("enum"; "enums")]
+    #[test_case::test_case("error"; "errors")]
+    #[test_case::test_case("message_with_enum"; "message with enum")]
+    #[test_case::test_case("mod"; "module decl")]
+    #[test_case::test_case("ns"; "namespace")]
+    #[test_case::test_case("op"; "operation")]
+    #[test_case::test_case("versions"; "versions")]
+    fn test_lower_samples(name: &str) {
+        let path = PathBuf::from(format!("../parser/samples/{name}.pld"));
+        let defs = super::load_from_path(path).unwrap();
+        operation_api_testing::insta_test!(|| {
+            operation_api_testing::assert_yaml_snapshot!(format!("lower_{name}"), defs);
+        });
+    }
+
+    #[test]
+    fn test_lower_package() {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_package("../parser/samples/abc-corp")
+            .unwrap();
+        let defs = lowering.finish().unwrap();
+        operation_api_testing::insta_test!(|| {
+            operation_api_testing::assert_yaml_snapshot!(defs);
+        });
+    }
+
+    #[test]
+    fn test_lower_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.pld"),
+            "namespace a;\nuse b;\nuse b::B;\nstruct A { b: B, c: b::C };",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.pld"),
+            "namespace b;\nstruct B { a: i32 };\nenum C { A };",
+        )
+        .unwrap();
+
+        let mut lowering = Lowering::new();
+        lowering
+            .add_file(dir.path().join("lib.pld"))
+            .unwrap();
+        let defs = lowering.finish().unwrap();
+        assert_eq!(defs.len(), 2);
+        let Definitions::NamespaceV1(a) = &defs[0] else {
+            panic!("expected namespace")
+        };
+        let fields = &a.defs.get(&"A".into()).unwrap().fields;
+        assert_eq!(
+            fields
+                .get(&Ident::new("b"))
+                .unwrap()
+                .unwrap_value()
+                .ty,
+            Type::CompoundType(CompoundType::Struct { to: "b::B".into() })
+        );
+        assert_eq!(
+            fields
+                .get(&Ident::new("c"))
+                .unwrap()
+                .unwrap_value()
+                .ty,
+            Type::CompoundType(CompoundType::Enum { to: "b::C".into() })
+        );
+    }
+
+    #[test]
+    fn test_lower_across_files() {
+        let mut lowering = Lowering::new();
+        lowering
+            .add_source("a.pld", "namespace test;\nstruct A { b: B };")
+            .unwrap();
+        lowering
+            .add_source(
+                "b.pld",
+                "#![version(2)]\nnamespace test;\nstruct B { a: i32 };",
+            )
+            .unwrap();
+        let defs = lowering.finish().unwrap();
+        assert_eq!(defs.len(), 1);
+        let Definitions::NamespaceV1(ns) = &defs[0] else {
+            panic!("expected namespace")
+        };
+        let a = ns.defs.get(&Key::new("A", 2_usize)).unwrap();
+        assert_eq!(a.meta.version, 2_usize.into());
+        assert_eq!(
+            a.fields
+                .get(&Ident::new("b"))
+                .unwrap()
+                .unwrap_value()
+                .ty,
+            Type::CompoundType(CompoundType::Struct { to: "B".into() })
+        );
+    }
+
+    #[test]
+    fn test_lower_versions() {
+        let ns = lower_one(
+            "namespace test;\n#[version(1)]\nstruct A { a: i32 };\n#[version(2)]\nstruct A { a: i32, b: str };\nstruct B { a: A };",
+        );
+        assert_eq!(
+            ns.defs.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                Key::new("A", 1_usize),
+                Key::new("A", 2_usize),
+                Key::new("B", 1_usize)
+            ]
+        );
+        let a = ns.defs.get(&Key::new("A", 2_usize)).unwrap();
+        assert_eq!(a.fields.len(), 2);
+    }
+
+    #[test_case::test_case("A", "A"; "latest")]
+    #[test_case::test_case("A@1", "A@1"; "pinned")]
+    #[test_case::test_case("A@2", "A@2"; "pinned latest")]
+    #[test_case::test_case("A@1[]", "A@1"; "pinned array")]
+    fn test_lower_pinned(
+        ty: &str,
+        expect: &str,
+    ) {
+        let ns = lower_one(&format!(
+            "namespace test;\n#[version(1)]\nstruct A {{ a: i32 }};\n#[version(2)]\nstruct A {{ a: i32, b: str }};\nstruct B {{ a: {ty} }};"
+        ));
+        let b = ns.defs.get(&Key::new("B", 1_usize)).unwrap();
+        let mut ty = &b.fields[&Ident::new("a")].unwrap_value().ty;
+        if let Type::CompoundType(CompoundType::Array { ty: inner }) = ty {
+            ty = inner;
+        }
+        assert_eq!(
+            ty,
+            &Type::CompoundType(CompoundType::Struct { to: expect.into() })
+        );
+    }
+
+    #[test]
+    fn test_lower_anonymous() {
+        let ns = lower_one(
+            "namespace test;\ntype Alias = i32[];\nstruct A { b?: { c: Alias }, d: oneof str | i64 };",
+        );
+        let a = ns.defs.get(&"A".into()).unwrap();
+        let b = a
+            .fields
+            .get(&Ident::new("b"))
+            .unwrap()
+            .unwrap_value();
+        assert!(b.optional);
+        assert_eq!(
+            b.ty,
+            Type::CompoundType(CompoundType::Option {
+                ty: Box::new(Type::CompoundType(CompoundType::Struct { to: "AB".into() }))
+            })
+        );
+        let ab = ns.defs.get(&"AB".into()).unwrap();
+        assert_eq!(
+            ab.fields
+                .get(&Ident::new("c"))
+                .unwrap()
+                .unwrap_value()
+                .ty,
+            Type::CompoundType(CompoundType::Array {
+                ty: Box::new(Type::I32)
+            })
+        );
+        let d = ns.one_ofs.get(&"AD".into()).unwrap();
+        assert_eq!(
+            d.variants
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![Ident::from("Str"), Ident::from("I64")]
+        );
+    }
+
+    #[test]
+    fn test_lower_intersection() {
+        let ns = lower_one(
+            "namespace test;
+struct Foo { a: i32, b: str };
+struct Baz { a: i64, c: bool };
+type Audit = { d?: u8 };
+type Qux = Foo & (Baz & Audit);
+struct Holder { q: Qux, inline: Baz & Audit };",
+        );
+
+        let field_ty = |s: &str, f: &str| {
+            ns.defs
+                .get(&s.into())
+                .unwrap()
+                .fields
+                .get(&Ident::new(f))
+                .map(|f| f.unwrap_value().ty.clone())
+        };
+
+        assert_eq!(
+            ns.defs
+                .get(&"Qux".into())
+                .unwrap()
+                .fields
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![
+                Ident::from("a"),
+                Ident::from("b"),
+                Ident::from("c"),
+                Ident::from("d")
+            ]
+        );
+        assert_eq!(field_ty("Qux", "a"), Some(Type::I32));
+        assert_eq!(
+            field_ty("Holder", "q"),
+            Some(Type::CompoundType(CompoundType::Struct {
+                to: "Qux".into()
+            }))
+        );
+        assert_eq!(
+            field_ty("Holder", "inline"),
+            Some(Type::CompoundType(CompoundType::Struct {
+                to: "HolderInline".into()
+            }))
+        );
+        assert_eq!(field_ty("HolderInline", "a"), Some(Type::I64));
+    }
+
+    #[test]
+    fn test_lower_one_of_alias() {
+        let ns = lower_one(
+            "namespace test;\ntype Abc = oneof str | i32;\ntype Def = Abc;\nstruct A { a: Def };",
+        );
+        assert!(ns.one_ofs.contains_key(&"Abc".into()));
+        assert_eq!(
+            ns.defs
+                .get(&"A".into())
+                .unwrap()
+                .fields
+                .get(&Ident::new("a"))
+                .unwrap()
+                .unwrap_value()
+                .ty,
+            Type::CompoundType(CompoundType::OneOf { to: "Abc".into() })
+        );
+    }
+
+    #[test]
+    fn test_lower_enum_values() {
+        let ns = lower_one("namespace test;\nenum E { A, B = 5, C };");
+        let values = ns
+            .enums
+            .get(&"E".into())
+            .unwrap()
+            .variants
+            .values()
+            .map(|v| v.value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![StrOrInt::Int(0), StrOrInt::Int(5), StrOrInt::Int(6)]
+        );
+    }
+
+    #[test]
+    fn test_lower_declaration_order() {
+        let ns = lower_one(
+            "namespace test;\nstruct S { z: i32, a: i32, m: i32 };\nenum E { Foo = 1, Baz = 2 };\noneof O { b(i32), a(str) };",
+        );
+        let names = |keys: Vec<&Ident>| {
+            keys.into_iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<_>>()
+        };
+        let s = ns.defs.get(&"S".into()).unwrap();
+        assert_eq!(names(s.fields.keys().collect()), vec!["z", "a", "m"]);
+        let e = ns.enums.get(&"E".into()).unwrap();
+        assert_eq!(names(e.variants.keys().collect()), vec!["Foo", "Baz"]);
+        let o = ns.one_ofs.get(&"O".into()).unwrap();
+        assert_eq!(names(o.variants.keys().collect()), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_lower_operation() {
+        let ns = lower_one(
+            "namespace test;\n#![error(E)]\nerror E { Code(i32) };\noperation a(x: i32) -> { y: i32 }!;\noperation b() -> never;",
+        );
+        let a = ns.ops.get(&"a".into()).unwrap();
+        assert!(!a.infallible);
+        assert_eq!(a.error, Some("E".into()));
+        assert!(matches!(
+            a.outputs.get(&Ident::new("y")),
+            Some(FieldOrRef::Value(..))
+        ));
+        let b = ns.ops.get(&"b".into()).unwrap();
+        assert!(b.infallible);
+        assert_eq!(b.error, None);
+        assert!(b.outputs.is_empty());
+    }
+
+    #[test_case::test_case("struct A { a: i32 };", "namespace is not declared"; "no namespace")]
+    #[test_case::test_case("namespace a;\nnamespace b;", "only one namespace may be declared"; "namespace conflict")]
+    #[test_case::test_case("namespace a;\nstruct A { a: B };", "B is not found in a"; "unresolved")]
+    #[test_case::test_case("namespace a;\nstruct A { a: i32 };\nenum A { B };", "enum A is declared multiple times"; "duplicate")]
+    #[test_case::test_case("namespace a;\n#[version(2)]\nstruct A { a: i32 };\n#[version(2)]\nstruct A { a: i64 };", "struct A is declared multiple times"; "duplicate version")]
+    #[test_case::test_case("namespace a;\n#[alias(B)]\nstruct A { a: i32 };", "only fields may declare an alias"; "item alias")]
+    #[test_case::test_case("namespace a;\nstruct A { #[version(2)] a: i32 };", "fields may only declare an alias"; "field version")]
+    #[test_case::test_case("namespace a;\nstruct A { #[alias(b)] #[alias(c)] a: i32 };", "a field may only declare one alias"; "field aliases")]
+    #[test_case::test_case("namespace a;\n#[kind(query)]\nstruct A { a: i32 };", "only operations may declare a kind"; "item kind")]
+    #[test_case::test_case("namespace a;\n#![kind(query)]\noperation a() -> i32;", "only operations may declare a kind"; "namespace kind")]
+    #[test_case::test_case("namespace a;\n#[kind(read)]\noperation a() -> i32;", "the kind of an operation is either query or mutation"; "unknown kind")]
+    #[test_case::test_case("namespace a;\n#[tag(1)]\nstruct A { a: i32 };", "only fields and variants may declare a tag"; "item tag")]
+    #[test_case::test_case("namespace a;\nstruct A { #[tag(1)] #[tag(2)] a: i32 };", "a field may only declare one tag"; "field tags")]
+    #[test_case::test_case("namespace a;\nstruct A { #[tag(0)] a: i32 };", "a tag must be positive"; "zero tag")]
+    #[test_case::test_case("namespace a;\nstruct A { #[tag(1)] a: i32, #[tag(1)] b: i32 };", "field b declares tag 1, which is already declared by a"; "duplicate tag")]
+    #[test_case::test_case("namespace a;\noneof A { #[tag(1)] a(i32), #[tag(1)] b(str) };", "variant b declares tag 1, which is already declared by a"; "duplicate variant tag")]
+    #[test_case::test_case("namespace a;\nenum A { #[tag(1)] Low = 1 };", "variants of int enums are numbered by their value, not a tag"; "int enum tag")]
+    #[test_case::test_case("namespace a;\nerror A { #[version(1)] Gone(i32) };", "variants may only declare a tag"; "variant version")]
+    #[test_case::test_case("namespace a;\nstruct A { a: i32, a: i64 };", "field a is declared multiple times"; "duplicate field")]
+    #[test_case::test_case("namespace a;\ntype A = B;\ntype B = A;", "is cyclic"; "cyclic alias")]
+    #[test_case::test_case("namespace a;\nstruct A { a: f16 };", "f16 is not supported"; "f16")]
+    #[test_case::test_case("namespace a;\nstruct A { a: i32! };", "may only be returned from operations"; "result")]
+    #[test_case::test_case("namespace a;\nenum E { A };\nstruct S { a: i32 };\ntype U = S & E;", "E is declared as an enum, only structs may be intersected"; "enum operand")]
+    #[test_case::test_case("namespace a;\ntype I = i32;\nstruct S { a: i32 };\ntype U = S & I;", "I is not a struct"; "builtin operand")]
+    #[test_case::test_case("namespace a;\nstruct S { a: i32 };\ntype U = S & V;\ntype V = S & U;", "is cyclic"; "cyclic intersection")]
+    #[test_case::test_case("namespace a;\nstruct A { a: i32 };\nstruct B { a: A@2 };", "A has no version 2"; "missing pinned version")]
+    fn test_lower_errors(
+        source: &str,
+        expect: &str,
+    ) {
+        let err = lower(source).unwrap_err().to_string();
+        assert!(err.contains(expect), "{err}");
+    }
+}
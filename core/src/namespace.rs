@@ -1,10 +1,13 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
 
 use convert_case::Casing;
 
 use crate::{
-    Contiguous, Definitions, Enum, ErrorTy, Field, FieldOrRef, Ident, OneOf, Operation, Struct,
-    Version,
+    Contiguous, Definitions, Enum, ErrorTy, Field, FieldOrRef, Ident, Key, OneOf, Operation,
+    Struct, Version, source::SourceSpan,
 };
 
 #[cfg(feature = "generate")]
@@ -33,11 +36,149 @@ pub struct Namespace {
     pub version: Version,
 
     pub fields: BTreeMap<Ident, Field<Ident>>,
-    pub ops: BTreeMap<Ident, Operation>,
-    pub defs: BTreeMap<Ident, Struct>,
-    pub enums: BTreeMap<Ident, Enum>,
-    pub one_ofs: BTreeMap<Ident, OneOf>,
-    pub errors: BTreeMap<Ident, ErrorTy>,
+    #[serde(with = "keyed")]
+    pub ops: BTreeMap<Key, Operation>,
+    #[serde(with = "keyed")]
+    pub defs: BTreeMap<Key, Struct>,
+    #[serde(with = "keyed")]
+    pub enums: BTreeMap<Key, Enum>,
+    #[serde(with = "keyed")]
+    pub one_ofs: BTreeMap<Key, OneOf>,
+    #[serde(with = "keyed")]
+    pub errors: BTreeMap<Key, ErrorTy>,
+
+    /// members (by parent and member name) which lie on a reference cycle, set by
+    /// [`crate::context::Context::finish`]
+    #[serde(skip)]
+    #[builder(default)]
+    pub recursive: BTreeSet<(Key, Ident)>,
+
+    /// where declarations of this namespace are referred to from schema sources, set when lowering
+    #[serde(skip)]
+    #[builder(default)]
+    pub references: BTreeMap<Ident, References>,
+}
+
+/// references to a declaration, used to rename it along with everything referring to it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct References {
+    /// spans of the referring identifiers, i.e. the last segment of a path
+    pub spans: Vec<SourceSpan>,
+    /// set when it is also referred to where no span is known, such as `use` items or error
+    /// attributes
+    pub untracked: bool,
+}
+
+/// a declaration which may be held in several versions
+pub trait Declaration {
+    fn key(&self) -> Key;
+}
+
+macro_rules! declaration {
+    ($($ty: ty), + $(,)?) => {
+        $(
+            impl Declaration for $ty {
+                fn key(&self) -> Key {
+                    self.meta.key()
+                }
+            }
+        )*
+    };
+}
+
+declaration!(Operation, Struct, Enum, OneOf, ErrorTy);
+
+/// declarations are written by name, qualified with their version only where several versions
+/// are held. they are keyed by their own name and version when read.
+mod keyed {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Declaration, Declarations};
+    use crate::Key;
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        decls: &BTreeMap<Key, T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(decls.iter().map(|(key, decl)| {
+            let name = match decls.is_versioned(&key.name) {
+                true => key.to_string(),
+                false => key.name.to_string(),
+            };
+            (name, decl)
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de> + Declaration>(
+        deserializer: D
+    ) -> Result<BTreeMap<Key, T>, D::Error> {
+        Ok(BTreeMap::<String, T>::deserialize(deserializer)?
+            .into_values()
+            .map(|decl| (decl.key(), decl))
+            .collect())
+    }
+}
+
+/// declarations of a namespace, by name and version
+pub trait Declarations<T> {
+    /// every version of a declaration, from the first
+    fn versions<'d>(
+        &'d self,
+        name: &Ident,
+    ) -> impl Iterator<Item = (&'d Key, &'d T)>
+    where
+        T: 'd;
+
+    /// the pinned version of a declaration, or else its latest
+    fn resolve(
+        &self,
+        name: &Ident,
+        version: Option<&Version>,
+    ) -> Option<(&Key, &T)>;
+
+    /// set when more than one version of a declaration is held
+    fn is_versioned(
+        &self,
+        name: &Ident,
+    ) -> bool {
+        self.versions(name).nth(1).is_some()
+    }
+}
+
+impl<T> Declarations<T> for BTreeMap<Key, T> {
+    fn versions<'d>(
+        &'d self,
+        name: &Ident,
+    ) -> impl Iterator<Item = (&'d Key, &'d T)>
+    where
+        T: 'd, {
+        let name = name.clone();
+        self.range(Key::new(name.clone(), 0_usize)..)
+            .take_while(move |(key, _)| key.name == name)
+    }
+
+    fn resolve(
+        &self,
+        name: &Ident,
+        version: Option<&Version>,
+    ) -> Option<(&Key, &T)> {
+        match version {
+            Some(version) => self.get_key_value(&Key::new(name.clone(), version.clone())),
+            None => self.versions(name).last(),
+        }
+    }
+}
+
+impl References {
+    pub fn extend(
+        &mut self,
+        other: Self,
+    ) {
+        self.spans.extend(other.spans);
+        self.untracked |= other.untracked;
+    }
 }
 
 impl Namespace {
@@ -51,6 +192,8 @@ impl Namespace {
             enums: Default::default(),
             one_ofs: Default::default(),
             errors: Default::default(),
+            recursive: Default::default(),
+            references: Default::default(),
         }
     }
 
@@ -79,49 +222,19 @@ impl Namespace {
                 )?;
             },
             Definitions::StructV1(def) => {
-                unique_ns_def(
-                    &mut self.defs,
-                    def.meta.name.clone(),
-                    &self.name,
-                    def,
-                    "struct",
-                )?;
+                unique_ns_def(&mut self.defs, def.meta.key(), &self.name, def, "struct")?;
             },
             Definitions::OperationV1(op) => {
-                unique_ns_def(
-                    &mut self.ops,
-                    op.meta.name.clone(),
-                    &self.name,
-                    op,
-                    "operation",
-                )?;
+                unique_ns_def(&mut self.ops, op.meta.key(), &self.name, op, "operation")?;
             },
             Definitions::EnumV1(enm) => {
-                unique_ns_def(
-                    &mut self.enums,
-                    enm.meta.name.clone(),
-                    &self.name,
-                    enm,
-                    "enum",
-                )?;
+                unique_ns_def(&mut self.enums, enm.meta.key(), &self.name, enm, "enum")?;
             },
             Definitions::OneOfV1(one) => {
-                unique_ns_def(
-                    &mut self.one_ofs,
-                    one.meta.name.clone(),
-                    &self.name,
-                    one,
-                    "one_of",
-                )?;
+                unique_ns_def(&mut self.one_ofs, one.meta.key(), &self.name, one, "one_of")?;
             },
             Definitions::ErrorV1(err) => {
-                unique_ns_def(
-                    &mut self.errors,
-                    err.meta.name.clone(),
-                    &self.name,
-                    err,
-                    "error",
-                )?;
+                unique_ns_def(&mut self.errors, err.meta.key(), &self.name, err, "error")?;
             },
             // note: this is an internal error
             Definitions::NamespaceV1(ns) => {
@@ -136,6 +249,15 @@ impl Namespace {
         Ok(())
     }
 
+    pub fn is_recursive(
+        &self,
+        parent: &Key,
+        member: &Ident,
+    ) -> bool {
+        self.recursive
+            .contains(&(parent.clone(), member.clone()))
+    }
+
     pub fn resolve_field(
         &self,
         name: &Ident,
@@ -148,28 +270,38 @@ impl Namespace {
         })
     }
 
+    /// the struct a local reference points at, pinned as `Name@1` or else the latest version
     pub fn resolve_struct(
         &self,
         name: &Ident,
     ) -> crate::Result<&Struct> {
-        self.defs.get(name).ok_or_else(|| {
-            crate::Error::NameNotFound {
-                name: name.clone(),
-                ns: self.name.clone(),
-            }
-        })
+        let (bare, version) = name.split_version();
+        self.defs
+            .resolve(&bare.into(), version.as_ref())
+            .map(|(_, it)| it)
+            .ok_or_else(|| {
+                crate::Error::NameNotFound {
+                    name: name.clone(),
+                    ns: self.name.clone(),
+                }
+            })
     }
 
+    /// the enum a local reference points at, pinned as `Name@1` or else the latest version
     pub fn resolve_enum(
         &self,
         name: &Ident,
     ) -> crate::Result<&Enum> {
-        self.enums.get(name).ok_or_else(|| {
-            crate::Error::NameNotFound {
-                name: name.clone(),
-                ns: self.name.clone(),
-            }
-        })
+        let (bare, version) = name.split_version();
+        self.enums
+            .resolve(&bare.into(), version.as_ref())
+            .map(|(_, it)| it)
+            .ok_or_else(|| {
+                crate::Error::NameNotFound {
+                    name: name.clone(),
+                    ns: self.name.clone(),
+                }
+            })
     }
 
     pub fn resolve_field_types(&mut self) -> crate::Result<()> {
@@ -204,8 +336,8 @@ impl Namespace {
     }
 
     pub fn ensure_contiguousness(&mut self) -> crate::Result<()> {
-        for (ident, desc) in self.enums.iter() {
-            desc.variants.is_contiguous(ident)?;
+        for (key, desc) in self.enums.iter() {
+            desc.variants.is_contiguous(&key.name)?;
         }
         Ok(())
     }
@@ -219,10 +351,7 @@ impl Namespace {
 
     #[cfg(feature = "generate")]
     pub fn normalized_path<L: LanguageTrait>(&self) -> String {
-        self.name
-            .to_string()
-            .replace(".", "_")
-            .to_case(L::file_case())
+        normalized_path::<L>(&self.name.to_string())
     }
 
     pub fn simplify_types(&mut self) {
@@ -264,23 +393,38 @@ impl Namespace {
         for op in other.ops.into_values() {
             self.with_definition(Definitions::OperationV1(op))?;
         }
+        for err in other.errors.into_values() {
+            self.with_definition(Definitions::ErrorV1(err))?;
+        }
+        for (name, references) in other.references {
+            self.references
+                .entry(name)
+                .or_default()
+                .extend(references);
+        }
         Ok(())
     }
 }
 
+/// the file (and module) name a namespace is generated into
+#[cfg(feature = "generate")]
+pub fn normalized_path<L: LanguageTrait>(ns: &str) -> String {
+    ns.replace(".", "_").to_case(L::file_case())
+}
+
 #[inline]
-pub(crate) fn unique_ns_def<T>(
-    sources: &mut BTreeMap<Ident, T>,
-    name: Ident,
+pub(crate) fn unique_ns_def<K: Ord + Clone + Display, T>(
+    sources: &mut BTreeMap<K, T>,
+    key: K,
     ns: &Ident,
     def: T,
     tag: &'static str,
 ) -> crate::Result<()> {
-    match sources.insert(name.clone(), def) {
+    match sources.insert(key.clone(), def) {
         Some(..) => {
             Err(crate::Error::NamespaceConflict {
                 ns: ns.clone(),
-                name,
+                name: key.to_string().into(),
                 tag,
             })
         },
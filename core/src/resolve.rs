@@ -0,0 +1,569 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    CompoundType, FieldOrRef, FieldsList, Ident, Named, OneOfVariant, Type, Version,
+    namespace::{Declarations, Namespace},
+    source::SourceSpan,
+};
+
+/// the kinds of declaration a reference may point at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RefKind {
+    Struct,
+    Enum,
+    OneOf,
+    Error,
+}
+
+const KINDS: [RefKind; 4] = [
+    RefKind::Struct,
+    RefKind::Enum,
+    RefKind::OneOf,
+    RefKind::Error,
+];
+
+impl RefKind {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::OneOf => "one_of",
+            Self::Error => "error",
+        }
+    }
+
+    fn declared(
+        &self,
+        ns: &Namespace,
+        name: &Ident,
+        version: Option<&Version>,
+    ) -> bool {
+        match self {
+            Self::Struct => ns.defs.resolve(name, version).is_some(),
+            Self::Enum => ns.enums.resolve(name, version).is_some(),
+            Self::OneOf => ns.one_ofs.resolve(name, version).is_some(),
+            Self::Error => ns.errors.resolve(name, version).is_some(),
+        }
+    }
+
+    /// the latest version of a declaration, if more than one is declared
+    fn latest(
+        &self,
+        ns: &Namespace,
+        name: &Ident,
+    ) -> Option<Version> {
+        fn latest<T>(
+            decls: &BTreeMap<crate::Key, T>,
+            name: &Ident,
+        ) -> Option<Version> {
+            decls
+                .is_versioned(name)
+                .then(|| decls.versions(name).last())
+                .flatten()
+                .map(|(key, _)| key.version.clone())
+        }
+        match self {
+            Self::Struct => latest(&ns.defs, name),
+            Self::Enum => latest(&ns.enums, name),
+            Self::OneOf => latest(&ns.one_ofs, name),
+            Self::Error => latest(&ns.errors, name),
+        }
+    }
+
+    fn names<'n>(
+        &self,
+        ns: &'n Namespace,
+    ) -> Box<dyn Iterator<Item = &'n Ident> + 'n> {
+        match self {
+            Self::Struct => Box::new(ns.defs.keys().map(|it| &it.name)),
+            Self::Enum => Box::new(ns.enums.keys().map(|it| &it.name)),
+            Self::OneOf => Box::new(ns.one_ofs.keys().map(|it| &it.name)),
+            Self::Error => Box::new(ns.errors.keys().map(|it| &it.name)),
+        }
+    }
+}
+
+/// checks that every reference points at a declaration of the expected kind, either in the
+/// referring namespace or in the namespace it was qualified with (`namespace::Name`)
+pub fn check(namespaces: &BTreeMap<Ident, Namespace>) -> crate::Result<()> {
+    let resolver = Resolver { namespaces };
+    for ns in namespaces.values() {
+        resolver.namespace(ns)?;
+    }
+    Ok(())
+}
+
+struct Resolver<'c> {
+    namespaces: &'c BTreeMap<Ident, Namespace>,
+}
+
+impl Resolver<'_> {
+    fn namespace(
+        &self,
+        ns: &Namespace,
+    ) -> crate::Result<()> {
+        for (name, field) in &ns.fields {
+            let site = format!("{}.{name}", ns.name);
+            self.ty(ns, &field.ty, &site, field.meta.span.as_ref())?;
+        }
+        for (key, def) in &ns.defs {
+            self.fields(ns, &key.name, &def.fields)?;
+        }
+        for (key, op) in &ns.ops {
+            self.fields(ns, &key.name, &op.inputs)?;
+            self.fields(ns, &key.name, &op.outputs)?;
+            if let Some(error) = &op.error {
+                let site = format!("{}.{}", ns.name, key.name);
+                self.reference(ns, error, RefKind::Error, &site, op.meta.span.as_ref())?;
+            }
+        }
+        for (key, def) in &ns.one_ofs {
+            self.variants(ns, &key.name, &def.variants)?;
+        }
+        for (key, def) in &ns.errors {
+            self.variants(ns, &key.name, &def.variants)?;
+        }
+        Ok(())
+    }
+
+    fn fields(
+        &self,
+        ns: &Namespace,
+        parent: &Ident,
+        fields: &FieldsList,
+    ) -> crate::Result<()> {
+        for (name, field) in fields.iter() {
+            // field refs are resolved by `Namespace::check`
+            if let FieldOrRef::Value(field) = field {
+                let site = format!("{}.{parent}.{name}", ns.name);
+                self.ty(ns, &field.ty, &site, field.meta.span.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn variants(
+        &self,
+        ns: &Namespace,
+        parent: &Ident,
+        variants: &Named<OneOfVariant>,
+    ) -> crate::Result<()> {
+        for (name, variant) in variants.iter() {
+            let site = format!("{}.{parent}.{name}", ns.name);
+            self.ty(ns, &variant.ty, &site, variant.span.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn ty(
+        &self,
+        ns: &Namespace,
+        ty: &Type,
+        site: &str,
+        span: Option<&SourceSpan>,
+    ) -> crate::Result<()> {
+        let Type::CompoundType(ty) = ty else {
+            return Ok(());
+        };
+        match ty {
+            CompoundType::Option { ty }
+            | CompoundType::Array { ty }
+            | CompoundType::SizedArray { ty, .. } => self.ty(ns, ty, site, span),
+            CompoundType::Struct { to } => self.reference(ns, to, RefKind::Struct, site, span),
+            CompoundType::Enum { to } => self.reference(ns, to, RefKind::Enum, site, span),
+            CompoundType::OneOf { to } => self.reference(ns, to, RefKind::OneOf, site, span),
+        }
+    }
+
+    fn reference(
+        &self,
+        ns: &Namespace,
+        to: &Ident,
+        kind: RefKind,
+        site: &str,
+        span: Option<&SourceSpan>,
+    ) -> crate::Result<()> {
+        let (qualifier, name) = to.split_qualified();
+        let name = Ident::new(name);
+        let (bare, version) = name.split_version();
+        let bare = Ident::new(bare);
+        let target = match qualifier.map(Ident::new) {
+            Some(qualifier) => {
+                match self.namespaces.get(&qualifier) {
+                    Some(target) => target,
+                    None => {
+                        let suggestion = suggest(&qualifier, self.namespaces.keys());
+                        return Err(at(
+                            span,
+                            crate::Error::UnresolvedNamespace {
+                                ns: qualifier,
+                                site: site.into(),
+                                suggestion,
+                            },
+                        ));
+                    },
+                }
+            },
+            None => ns,
+        };
+
+        if kind.declared(target, &bare, version.as_ref()) {
+            return Ok(());
+        }
+
+        let err = match KINDS
+            .iter()
+            .find(|other| other.declared(target, &bare, version.as_ref()))
+        {
+            Some(found) => {
+                crate::Error::MismatchedReference {
+                    tag: kind.tag(),
+                    found: found.tag(),
+                    name,
+                    ns: target.name.clone(),
+                    site: site.into(),
+                }
+            },
+            None => {
+                // prefer declarations of the expected kind, falling back to any declared type
+                let suggestion = suggest(&bare, kind.names(target)).or_else(|| {
+                    suggest(
+                        &bare,
+                        KINDS
+                            .iter()
+                            .flat_map(|other| other.names(target)),
+                    )
+                });
+                crate::Error::UnresolvedReference {
+                    tag: kind.tag(),
+                    name,
+                    ns: target.name.clone(),
+                    site: site.into(),
+                    suggestion,
+                }
+            },
+        };
+        Err(at(span, err))
+    }
+}
+
+/// rewrites references so that exactly those to a declaration held in several versions are
+/// pinned, pinning unpinned ones to the latest version. generators may then path pinned
+/// references through a module of their version, and leave all others as they are.
+///
+/// references are expected to resolve, see [`check`].
+pub fn pin(namespaces: &mut BTreeMap<Ident, Namespace>) {
+    let mut latest = BTreeMap::new();
+    for ns in namespaces.values() {
+        let names = KINDS.iter().flat_map(|kind| {
+            kind.names(ns).filter_map(move |name| {
+                Some((
+                    (ns.name.clone(), kind.tag(), name.clone()),
+                    kind.latest(ns, name)?,
+                ))
+            })
+        });
+        latest.extend(names);
+    }
+
+    let pinned = |ns: &Ident, kind: RefKind, to: &mut Ident| {
+        let (qualifier, name) = to.split_qualified();
+        let name = Ident::new(name);
+        let (bare, version) = name.split_version();
+        let target = qualifier
+            .map(Ident::new)
+            .unwrap_or_else(|| ns.clone());
+        let prefix = qualifier
+            .map(|it| format!("{it}::"))
+            .unwrap_or_default();
+        *to = match latest.get(&(target, kind.tag(), Ident::new(bare))) {
+            Some(latest) => format!("{prefix}{bare}@{}", version.as_ref().unwrap_or(latest)),
+            None => format!("{prefix}{bare}"),
+        }
+        .into();
+    };
+
+    for ns in namespaces.values_mut() {
+        let name = ns.name.clone();
+        let ty = |ty: &mut Type| pin_ty(ty, &mut |kind, to| pinned(&name, kind, to));
+        for field in ns.fields.values_mut() {
+            ty(&mut field.ty);
+        }
+        for def in ns.defs.values_mut() {
+            for field in def.fields.values_mut() {
+                if let FieldOrRef::Value(field) = field {
+                    ty(&mut field.ty);
+                }
+            }
+        }
+        for op in ns.ops.values_mut() {
+            for field in op
+                .inputs
+                .values_mut()
+                .chain(op.outputs.values_mut())
+            {
+                if let FieldOrRef::Value(field) = field {
+                    ty(&mut field.ty);
+                }
+            }
+        }
+        for variant in ns
+            .one_ofs
+            .values_mut()
+            .flat_map(|it| it.variants.values_mut())
+            .chain(
+                ns.errors
+                    .values_mut()
+                    .flat_map(|it| it.variants.values_mut()),
+            )
+        {
+            ty(&mut variant.ty);
+        }
+        for op in ns.ops.values_mut() {
+            if let Some(error) = &mut op.error {
+                pinned(&name, RefKind::Error, error);
+            }
+        }
+    }
+}
+
+fn pin_ty(
+    ty: &mut Type,
+    pinned: &mut impl FnMut(RefKind, &mut Ident),
+) {
+    let Type::CompoundType(ty) = ty else {
+        return;
+    };
+    match ty {
+        CompoundType::Option { ty }
+        | CompoundType::Array { ty }
+        | CompoundType::SizedArray { ty, .. } => pin_ty(ty, pinned),
+        CompoundType::Struct { to } => pinned(RefKind::Struct, to),
+        CompoundType::Enum { to } => pinned(RefKind::Enum, to),
+        CompoundType::OneOf { to } => pinned(RefKind::OneOf, to),
+    }
+}
+
+pub(crate) fn at(
+    span: Option<&SourceSpan>,
+    err: crate::Error,
+) -> crate::Error {
+    match span {
+        Some(span) => span.error(err.to_string()),
+        None => err,
+    }
+}
+
+/// the candidate closest to a misspelled name, if any is close enough to be a likely typo
+fn suggest<'n>(
+    name: &Ident,
+    candidates: impl Iterator<Item = &'n Ident>,
+) -> Option<Ident> {
+    let name = name.to_string();
+    candidates
+        .map(|it| (strsim::jaro_winkler(&name, &it.to_string()), it))
+        .filter(|(score, _)| *score > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, it)| it.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{CompoundType, Definitions, Ident, Key, Type, context::Context};
+
+    fn context(defs: &[&str]) -> Context {
+        let mut ctx = Context::new();
+        for def in defs {
+            ctx.with_definition(toml::from_str::<Definitions>(def).unwrap())
+                .unwrap();
+        }
+        ctx
+    }
+
+    const KNOWN_ERROR: &str = r#"
+type = "struct@v1"
+name = "KnownError"
+namespace = "a"
+version = 1
+
+[fields.desc]
+type = "string"
+"#;
+
+    const ERROR_CODE: &str = r#"
+type = "enum@v1"
+name = "ErrorCode"
+namespace = "a"
+version = 1
+
+[variants.Foo]
+name = "Foo"
+namespace = "a"
+value = 1
+"#;
+
+    const OPERATION_ERROR: &str = r#"
+type = "error@v1"
+name = "OperationError"
+namespace = "a"
+version = 1
+
+[variants.Known]
+name = "Known"
+ty.compound_type = { kind = "struct", ref = "KnownError" }
+"#;
+
+    const OTHER: &str = r#"
+type = "struct@v1"
+name = "Other"
+namespace = "other"
+version = 1
+
+[fields.known]
+type.compound_type = { kind = "struct", ref = "a::KnownError" }
+"#;
+
+    fn referencing(
+        kind: &str,
+        to: &str,
+    ) -> String {
+        format!(
+            r#"
+type = "struct@v1"
+name = "Holder"
+namespace = "a"
+version = 1
+
+[fields.held]
+type.compound_type = {{ kind = "array", type.compound_type = {{ kind = "{kind}", ref = "{to}" }} }}
+"#
+        )
+    }
+
+    fn operation(error: &str) -> String {
+        format!(
+            r#"
+type = "operation@v1"
+name = "get"
+namespace = "a"
+version = 1
+error = "{error}"
+
+[inputs]
+[outputs]
+"#
+        )
+    }
+
+    #[test]
+    fn test_resolves() {
+        let holder = referencing("enum", "ErrorCode");
+        let op = operation("OperationError");
+        let mut ctx = context(&[
+            KNOWN_ERROR,
+            ERROR_CODE,
+            OPERATION_ERROR,
+            OTHER,
+            &holder,
+            &op,
+        ]);
+        ctx.finish().unwrap();
+    }
+
+    #[test_case::test_case(
+        &referencing("struct", "KnownEror"),
+        "struct KnownEror referenced by a.Holder.held is not found in a, did you mean KnownError?";
+        "typo"
+    )]
+    #[test_case::test_case(
+        &referencing("enum", "KnownError"),
+        "KnownError referenced by a.Holder.held is declared as struct in a, expected enum";
+        "wrong kind"
+    )]
+    #[test_case::test_case(
+        &referencing("one_of", "Unrelated"),
+        "one_of Unrelated referenced by a.Holder.held is not found in a";
+        "no suggestion"
+    )]
+    #[test_case::test_case(
+        &referencing("struct", "othr::KnownError"),
+        "namespace othr referenced by a.Holder.held is not found, did you mean other?";
+        "unknown namespace"
+    )]
+    #[test_case::test_case(
+        &referencing("enum", "other::ErrorCode"),
+        "enum ErrorCode referenced by a.Holder.held is not found in other";
+        "qualified"
+    )]
+    #[test_case::test_case(
+        &operation("OperationEror"),
+        "error OperationEror referenced by a.get is not found in a, did you mean OperationError?";
+        "operation error"
+    )]
+    #[test_case::test_case(
+        &operation("KnownError"),
+        "KnownError referenced by a.get is declared as struct in a, expected error";
+        "operation error kind"
+    )]
+    fn test_unresolved(
+        def: &str,
+        expect: &str,
+    ) {
+        let mut ctx = context(&[KNOWN_ERROR, ERROR_CODE, OPERATION_ERROR, OTHER, def]);
+        let err = ctx.finish().unwrap_err();
+        assert_eq!(err.to_string(), expect);
+    }
+
+    #[test]
+    fn test_unresolved_variant() {
+        let mut ctx = context(&[&OPERATION_ERROR.replace("KnownError", "KnownErr")]);
+        let err = ctx.finish().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "struct KnownErr referenced by a.OperationError.Known is not found in a"
+        );
+    }
+
+    #[test_case::test_case("struct", "KnownError@1", "KnownError@1"; "pinned")]
+    #[test_case::test_case("struct", "KnownError", "KnownError@2"; "latest")]
+    #[test_case::test_case("struct", "a::KnownError@1", "a::KnownError@1"; "qualified")]
+    #[test_case::test_case("enum", "ErrorCode", "ErrorCode"; "unversioned")]
+    fn test_pin(
+        kind: &str,
+        to: &str,
+        expect: &str,
+    ) {
+        let known_v2 = KNOWN_ERROR.replace("version = 1", "version = 2");
+        let holder = referencing(kind, to);
+        let mut ctx = context(&[KNOWN_ERROR, &known_v2, ERROR_CODE, &holder]);
+        ctx.finish().unwrap();
+        let held = &ctx.namespaces[&Ident::new("a")].defs[&Key::from("Holder")].fields
+            [&Ident::new("held")];
+        let Type::CompoundType(CompoundType::Array { ty }) = &held.unwrap_value().ty else {
+            panic!("expected array")
+        };
+        let Type::CompoundType(CompoundType::Struct { to } | CompoundType::Enum { to }) = &**ty
+        else {
+            panic!("expected reference")
+        };
+        assert_eq!(to.to_string(), expect);
+    }
+
+    #[test]
+    fn test_unresolved_version() {
+        let mut ctx = context(&[KNOWN_ERROR, &referencing("struct", "KnownError@3")]);
+        let err = ctx.finish().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "struct KnownError@3 referenced by a.Holder.held is not found in a, did you mean KnownError?"
+        );
+    }
+
+    #[test]
+    fn test_resolves_package() {
+        let mut ctx = Context::new();
+        ctx.load_package("../parser/samples/abc-corp")
+            .unwrap();
+        ctx.finish().unwrap();
+    }
+}
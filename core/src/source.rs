@@ -0,0 +1,96 @@
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use operation_api_parser::{
+    defs::{Span, Spanned},
+    diagnostics::SpanDiagnostic,
+};
+
+/// a schema file which definitions were lowered from
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: Arc<str>,
+}
+
+impl SourceFile {
+    pub fn new<S: Into<Arc<str>>>(
+        path: impl AsRef<Path>,
+        source: S,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            path: path.as_ref().to_path_buf(),
+            source: source.into(),
+        })
+    }
+
+    pub fn span(
+        self: &Arc<Self>,
+        span: &Span,
+    ) -> SourceSpan {
+        SourceSpan {
+            file: self.clone(),
+            span: span.clone(),
+        }
+    }
+}
+
+impl Debug for SourceFile {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("SourceFile")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// location of a lowered definition within its source file.
+///
+/// spans are positional metadata only - two definitions are equal regardless of where they were declared.
+#[derive(Debug, Clone)]
+pub struct SourceSpan {
+    pub file: Arc<SourceFile>,
+    pub span: Span,
+}
+
+impl PartialEq for SourceSpan {
+    fn eq(
+        &self,
+        _: &Self,
+    ) -> bool {
+        true
+    }
+}
+
+impl SourceSpan {
+    pub fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    pub fn diagnostic(
+        &self,
+        message: impl Into<String>,
+        label: impl Into<String>,
+        help: Option<String>,
+    ) -> SpanDiagnostic {
+        SpanDiagnostic::new(
+            &Spanned::new(self.span.start, self.span.end, ()),
+            &self.file.path,
+            &self.file.source,
+            message,
+            label,
+            help,
+        )
+    }
+
+    pub fn error(
+        &self,
+        message: impl Into<String>,
+    ) -> crate::Error {
+        crate::Error::Miette(self.diagnostic(message, "here", None).into())
+    }
+}
@@ -1,5 +1,4 @@
 use std::{
-    collections::BTreeMap,
     fmt::Display,
     io::Write,
     ops::{Deref, DerefMut},
@@ -7,18 +6,84 @@ use std::{
 };
 
 use convert_case::Casing;
+use indexmap::IndexMap;
 
 #[cfg(feature = "generate")]
 use crate::generate::RustConfig;
-use crate::{namespace::Namespace, trace_replace};
+use crate::{namespace::Namespace, source::SourceSpan, trace_replace};
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 pub struct Ident(String);
 
 impl Ident {
     pub fn new<S: Into<String>>(s: S) -> Self {
         Self::from(s)
     }
+
+    /// splits a reference qualified as `namespace::Name`. bare names are local to the referring namespace.
+    pub fn split_qualified(&self) -> (Option<&str>, &str) {
+        match self.0.rsplit_once("::") {
+            Some((ns, name)) => (Some(ns), name),
+            None => (None, &self.0),
+        }
+    }
+
+    /// splits a reference pinned to a version as `Name@1`. unpinned references point at the
+    /// latest version of a declaration.
+    pub fn split_version(&self) -> (&str, Option<Version>) {
+        split_version(&self.0)
+    }
+}
+
+fn split_version(s: &str) -> (&str, Option<Version>) {
+    match s.rsplit_once('@') {
+        Some((name, version)) => {
+            match version.parse() {
+                Ok(version) => (name, Some(Version(version))),
+                Err(..) => (s, None),
+            }
+        },
+        None => (s, None),
+    }
+}
+
+/// a declaration by name and version, written as `Name@1`. a namespace may hold several
+/// versions of a declaration side by side.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    pub name: Ident,
+    pub version: Version,
+}
+
+impl Key {
+    pub fn new(
+        name: impl Into<Ident>,
+        version: impl Into<Version>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+}
+
+impl Display for Key {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// keys without a version are taken as the first version
+impl From<&str> for Key {
+    fn from(value: &str) -> Self {
+        let (name, version) = split_version(value);
+        Self::new(name, version.unwrap_or_default())
+    }
 }
 
 impl Display for Ident {
@@ -36,7 +101,9 @@ impl<S: Into<String>> From<S> for Ident {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
 pub struct Version(usize);
 
 impl Version {
@@ -45,6 +112,73 @@ impl Version {
     }
 }
 
+impl Display for Version {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// a value of one version of a declaration which has no counterpart in another, returned by the
+/// conversions generated between consecutive versions
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("{from} can not be converted to {to}: {reason}")]
+pub struct ConversionError {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub reason: &'static str,
+}
+
+impl ConversionError {
+    pub const fn new(
+        from: &'static str,
+        to: &'static str,
+        reason: &'static str,
+    ) -> Self {
+        Self { from, to, reason }
+    }
+}
+
+/// a request which the dispatcher generated for the operations of a namespace could not answer
+#[derive(thiserror::Error, Debug)]
+pub enum DispatchError {
+    #[error("no operation is named `{0}`")]
+    UnknownOperation(String),
+
+    #[error("the request of `{operation}` is malformed: {source}")]
+    Request {
+        operation: &'static str,
+        source: serde_json::Error,
+    },
+
+    #[error("the response of `{operation}` can not be serialized: {source}")]
+    Response {
+        operation: &'static str,
+        source: serde_json::Error,
+    },
+
+    /// the operation returned its error, held serialized
+    #[error("`{operation}` failed")]
+    Failed {
+        operation: &'static str,
+        error: serde_json::Value,
+    },
+}
+
+impl DispatchError {
+    pub fn failed<E: serde::Serialize>(
+        operation: &'static str,
+        error: E,
+    ) -> Self {
+        match serde_json::to_value(error) {
+            Ok(error) => Self::Failed { operation, error },
+            Err(source) => Self::Response { operation, source },
+        }
+    }
+}
+
 impl Default for Version {
     fn default() -> Self {
         Self(1)
@@ -322,6 +456,12 @@ impl<T: Typed> Typed for Vec<T> {
     }
 }
 
+impl<T: Typed> Typed for Box<T> {
+    fn ty() -> Type {
+        T::ty()
+    }
+}
+
 impl<T: Typed> Typed for Option<T> {
     fn ty() -> Type {
         if std::any::type_name::<T>() == "()" {
@@ -371,9 +511,12 @@ impl From<Field<Ident>> for Field<Option<Ident>> {
                 namespace: Some(value.meta.namespace),
                 description: value.meta.description,
                 version: value.meta.version,
+                span: value.meta.span,
             },
             ty: value.ty,
             optional: value.optional,
+            alias: value.alias,
+            tag: value.tag,
         }
     }
 }
@@ -388,14 +531,33 @@ pub struct Field<Ns> {
 
     #[serde(default = "crate::utils::default_no")]
     pub optional: bool,
+
+    /// the name of the field in the previous version of its struct, when it was renamed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<Ident>,
+
+    /// the number of the field, in formats which identify fields by number such as protobuf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<u32>,
 }
 
 impl Type {
-    #[allow(clippy::only_used_in_recursion)]
     #[cfg(feature = "generate")]
     pub fn ty(
         &self,
         opts: &RustConfig,
+    ) -> proc_macro2::TokenStream {
+        self.ty_at(opts, 0)
+    }
+
+    /// the type as named from `depth` modules below the module of its namespace, such as from
+    /// the module of a version
+    #[allow(clippy::only_used_in_recursion)]
+    #[cfg(feature = "generate")]
+    pub fn ty_at(
+        &self,
+        opts: &RustConfig,
+        depth: usize,
     ) -> proc_macro2::TokenStream {
         match self {
             Type::Complex => todo!(),
@@ -416,6 +578,7 @@ impl Type {
             Type::F32 => quote::quote!(f32),
             Type::F64 => quote::quote!(f64),
 
+            // serialized as an array of octets
             Type::Binary => quote::quote!(Vec<u8>),
             Type::String => quote::quote!(String),
 
@@ -446,32 +609,23 @@ impl Type {
             Type::Never => quote::quote!(),
             Type::CompoundType(outer_ty) => {
                 match outer_ty {
-                    CompoundType::Enum { to } => {
-                        let as_rs_ref = super::generate::rust::ident(to.to_string());
-                        quote::quote!(#as_rs_ref)
-                    },
-                    CompoundType::OneOf { to } => {
-                        let as_rs_ref = super::generate::rust::ident(to.to_string());
-                        quote::quote!(#as_rs_ref)
-                    },
-                    CompoundType::Struct { to } => {
-                        let as_rs_ref = super::generate::rust::ident(to.to_string());
-                        quote::quote!(#as_rs_ref)
-                    },
+                    CompoundType::Enum { to }
+                    | CompoundType::OneOf { to }
+                    | CompoundType::Struct { to } => super::generate::rust::reference(to, depth),
                     CompoundType::Array { ty } => {
-                        let inner = ty.ty(opts);
+                        let inner = ty.ty_at(opts, depth);
                         quote::quote!(
                             Vec<#inner>
                         )
                     },
                     CompoundType::Option { ty } => {
-                        let inner = ty.ty(opts);
+                        let inner = ty.ty_at(opts, depth);
                         quote::quote!(
                             Option<#inner>
                         )
                     },
                     CompoundType::SizedArray { size, ty } => {
-                        let inner = ty.ty(opts);
+                        let inner = ty.ty_at(opts, depth);
                         let size = crate::generate::rust::lit(format!("{size}"));
                         quote::quote!(
                             [#inner; #size]
@@ -482,6 +636,25 @@ impl Type {
         }
     }
 
+    /// the type behind a `Box`, for members on a reference cycle. options keep the box inside.
+    #[cfg(feature = "generate")]
+    pub fn boxed_ty(
+        &self,
+        opts: &RustConfig,
+        depth: usize,
+    ) -> proc_macro2::TokenStream {
+        match self {
+            Type::CompoundType(CompoundType::Option { ty }) => {
+                let inner = ty.boxed_ty(opts, depth);
+                quote::quote!(Option<#inner>)
+            },
+            ty => {
+                let inner = ty.ty_at(opts, depth);
+                quote::quote!(Box<#inner>)
+            },
+        }
+    }
+
     pub fn rust_attrs(&self) -> proc_macro2::TokenStream {
         match self {
             Self::CompoundType(CompoundType::Enum { .. }) => quote::quote!(#[fields(enm)]),
@@ -518,14 +691,21 @@ impl<T> From<T> for Reffable<T> {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone, Default)]
-pub struct Named<T>(BTreeMap<Ident, T>);
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone)]
+pub struct Named<T>(IndexMap<Ident, T>);
 
 pub type FieldsList = Named<FieldOrRef>;
 
 impl<T> Named<T> {
-    pub fn new<M: Into<BTreeMap<Ident, T>>>(map: M) -> Self {
-        Self(map.into())
+    /// entries keep the order they are given in, which is the declaration order of the source
+    pub fn new<M: IntoIterator<Item = (Ident, T)>>(map: M) -> Self {
+        Self(map.into_iter().collect())
+    }
+}
+
+impl<T> Default for Named<T> {
+    fn default() -> Self {
+        Self(IndexMap::new())
     }
 }
 
@@ -536,7 +716,7 @@ impl<T> DerefMut for Named<T> {
 }
 
 impl<T> Deref for Named<T> {
-    type Target = BTreeMap<Ident, T>;
+    type Target = IndexMap<Ident, T>;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
@@ -560,6 +740,11 @@ pub struct VariantKind {
     #[serde(flatten)]
     pub meta: Meta<Ident, Option<Ident>, Option<Version>>,
     pub value: StrOrInt,
+
+    /// the number of a variant of a string enum, in formats which identify values by number
+    /// such as protobuf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<u32>,
 }
 
 impl Named<VariantKind> {
@@ -602,6 +787,9 @@ pub struct Meta<IdentTy, NsTy, Version> {
     pub description: Option<String>,
 
     pub version: Version,
+
+    #[serde(skip)]
+    pub span: Option<SourceSpan>,
 }
 
 #[cfg(feature = "generate")]
@@ -624,6 +812,13 @@ impl<IdentTy, NsTy, Version> Meta<IdentTy, NsTy, Version> {
     }
 }
 
+impl<Ns> Meta<Ident, Ns, Version> {
+    /// the key of the declaration within its namespace
+    pub fn key(&self) -> Key {
+        Key::new(self.name.clone(), self.version.clone())
+    }
+}
+
 #[cfg(feature = "generate")]
 impl<IdentTy, Ns> Meta<IdentTy, Ns, Version> {
     pub fn version(&self) -> proc_macro2::TokenStream {
@@ -649,6 +844,39 @@ pub struct Struct {
     pub fields: FieldsList,
 }
 
+/// whether an operation only reads, or may also write. protocols which tell the two apart, such
+/// as graphql, place each operation accordingly.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Query,
+    #[default]
+    Mutation,
+}
+
+impl Display for OperationKind {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Query => write!(f, "query"),
+            Self::Mutation => write!(f, "mutation"),
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, bon::Builder, Clone)]
 pub struct Operation {
     #[serde(flatten)]
@@ -660,6 +888,10 @@ pub struct Operation {
     #[serde(default)]
     pub error: Option<Ident>,
 
+    #[serde(default)]
+    #[builder(default)]
+    pub kind: OperationKind,
+
     pub inputs: FieldsList,
     pub outputs: FieldsList,
 }
@@ -678,6 +910,20 @@ pub struct OneOfVariant {
     #[serde(default)]
     pub description: Option<String>,
     pub ty: Type,
+
+    /// the number of the variant, in formats which identify variants by number such as protobuf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<u32>,
+
+    /// the variant declares its fields inline, held as a struct named after it such as
+    /// `FailureTooLarge` of `TooLarge { limit: i32 }` of `Failure`. only known to the source
+    /// declaring it, as the generated rust types hold it as any other struct.
+    #[serde(skip)]
+    #[builder(default)]
+    pub inline: bool,
+
+    #[serde(skip)]
+    pub span: Option<SourceSpan>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, bon::Builder, Clone)]
@@ -60,7 +60,7 @@ pub fn derive_enum(tokens: TokenStream) -> TokenStream {
     }
 
     let fields_map = quote!(
-        let mut m = std::collections::BTreeMap::<_, _>::new();
+        let mut m = operation_api_sdk::Named::<_>::default();
     );
 
     let parent_iden = s.ident.clone();
@@ -112,8 +112,10 @@ pub fn derive_enum(tokens: TokenStream) -> TokenStream {
                         namespace: Some(#parent_iden::NAMESPACE.into()),
                         description: #desc_value,
                         version: None,
+                        span: None,
                     },
-                    value: #value
+                    value: #value,
+                    tag: None,
                 }.into());
             )
         })
@@ -138,8 +140,9 @@ pub fn derive_enum(tokens: TokenStream) -> TokenStream {
                     namespace: #iden::NAMESPACE.into(),
                     version: VERSION.into(),
                     description: #desc_value,
+                    span: None,
                 },
-                variants: operation_api_sdk::Named::new(m),
+                variants: m,
             })
         });
 
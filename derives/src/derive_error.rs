@@ -120,6 +120,9 @@ pub fn derive_error(tokens: TokenStream) -> TokenStream {
                         name: #var_ident_str.into(),
                         ty: #ty::ty(),
                         description: #desc_value,
+                        tag: None,
+                        inline: false,
+                        span: None,
                     });
                 });
             },
@@ -163,6 +166,9 @@ pub fn derive_error(tokens: TokenStream) -> TokenStream {
                         name: #var_ident_str.into(),
                         ty: #gen_name::ty(),
                         description: #desc_value,
+                        tag: None,
+                        inline: true,
+                        span: None,
                     });
                 });
             },
@@ -185,7 +191,7 @@ pub fn derive_error(tokens: TokenStream) -> TokenStream {
         static #def_static_ident: std::sync::LazyLock<operation_api_sdk::Definitions> = std::sync::LazyLock::new(|| {
             use operation_api_sdk::{OfNamespace, Defined, Typed};
 
-            let mut m = std::collections::BTreeMap::<_, operation_api_sdk::OneOfVariant>::new();
+            let mut m = operation_api_sdk::Named::<operation_api_sdk::OneOfVariant>::default();
             #gen_variant
 
             const VERSION: operation_api_sdk::Version = operation_api_sdk::Version::new(#version_lit);
@@ -195,8 +201,9 @@ pub fn derive_error(tokens: TokenStream) -> TokenStream {
                     namespace: #enum_ident::NAMESPACE.into(),
                     version: VERSION.into(),
                     description: #desc_value,
+                    span: None,
                 },
-                variants: operation_api_sdk::Named::new(m),
+                variants: m,
             })
         });
 
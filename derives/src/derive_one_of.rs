@@ -73,7 +73,7 @@ pub fn derive_one_of(tokens: TokenStream) -> TokenStream {
     }
 
     let fields_map = quote!(
-        let mut m = std::collections::BTreeMap::<_, operation_api_sdk::OneOfVariant>::new();
+        let mut m = operation_api_sdk::Named::<operation_api_sdk::OneOfVariant>::default();
     );
 
     let mut fields_def = quote!();
@@ -120,6 +120,9 @@ pub fn derive_one_of(tokens: TokenStream) -> TokenStream {
                 name: #iden_str.into(),
                 ty: <#ty>::ty(),
                 description: #desc_value,
+                tag: None,
+                inline: false,
+                span: None,
             });
         ));
     }
@@ -143,8 +146,9 @@ pub fn derive_one_of(tokens: TokenStream) -> TokenStream {
                     namespace: #iden::NAMESPACE.into(),
                     version: VERSION.into(),
                     description: #desc_value,
+                    span: None,
                 },
-                variants: operation_api_sdk::Named::new(m),
+                variants: m,
             })
         });
 
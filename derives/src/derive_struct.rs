@@ -68,7 +68,7 @@ pub fn derive_struct(tokens: TokenStream) -> TokenStream {
     }
 
     let fields_map = quote!(
-        let mut m = std::collections::BTreeMap::<_, _>::new();
+        let mut m = operation_api_sdk::Named::<_>::default();
     );
 
     let parent_iden = s.ident.clone();
@@ -110,9 +110,12 @@ pub fn derive_struct(tokens: TokenStream) -> TokenStream {
                     namespace: Some(#parent_iden::NAMESPACE.into()),
                     description: #desc_value,
                     version: None,
+                    span: None,
                 },
                 ty: <#ty>::ty(),
                 optional: false,
+                alias: None,
+                tag: None,
             }.into());
         ));
     }
@@ -136,8 +139,9 @@ pub fn derive_struct(tokens: TokenStream) -> TokenStream {
                     namespace: #iden::NAMESPACE.into(),
                     version: VERSION.into(),
                     description: #desc_value,
+                    span: None,
                 },
-                fields: operation_api_sdk::FieldsList::new(m),
+                fields: m,
             })
         });
 
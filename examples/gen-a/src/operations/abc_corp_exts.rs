@@ -38,4 +38,10 @@ pub struct StructWithOneOf {
     #[fields(one_of)]
     pub my_flag: MaybeFlagType,
 }
-operation_api_sdk::namespace! { "abc.corp.exts" { CommentWithTestChrono , CommentWithTestTime , StructWithOneOf , MaybeFlagType , } }
+#[derive(serde :: Serialize, serde :: Deserialize, operation_api_sdk :: Struct)]
+#[fields(version = 1)]
+pub struct WithKnownError {
+    #[serde(rename = "error")]
+    pub error: super::abc_corp_test::KnownError,
+}
+operation_api_sdk::namespace! { "abc.corp.exts" { CommentWithTestChrono , CommentWithTestTime , StructWithOneOf , WithKnownError , MaybeFlagType , } }
@@ -0,0 +1,3 @@
+#![allow(dead_code, unused_imports)]
+
+pub mod operations;
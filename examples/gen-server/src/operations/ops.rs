@@ -0,0 +1,168 @@
+#[derive(serde :: Serialize, serde :: Deserialize, operation_api_sdk :: Struct)]
+#[fields(version = 1)]
+pub struct FailureNotFound {
+    #[serde(rename = "desc")]
+    pub desc: String,
+}
+#[derive(serde :: Serialize, serde :: Deserialize, operation_api_sdk :: Struct)]
+#[fields(version = 1)]
+pub struct FailureTooLarge {
+    #[serde(rename = "limit")]
+    pub limit: i32,
+}
+#[derive(serde :: Serialize, serde :: Deserialize, operation_api_sdk :: Error)]
+#[fields(version = 1)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Failure {
+    NotFound(FailureNotFound),
+    TooLarge(FailureTooLarge),
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+#[doc = "adds two numbers"]
+pub struct AddRequest {
+    #[serde(rename = "a")]
+    pub a: i32,
+    #[serde(rename = "b")]
+    pub b: Option<i32>,
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+#[doc = "adds two numbers"]
+pub struct AddResponse {
+    #[serde(rename = "value")]
+    pub value: i32,
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+pub struct FindRequest {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+pub struct FindResponse {
+    #[serde(rename = "found")]
+    pub found: Vec<i32>,
+    #[serde(rename = "total")]
+    pub total: i32,
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+pub struct PingV1Request {}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+pub struct PingV1Response {
+    #[serde(rename = "value")]
+    pub value: bool,
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+pub struct PingV2Request {
+    #[serde(rename = "retries")]
+    pub retries: u8,
+}
+#[derive(serde :: Serialize, serde :: Deserialize)]
+pub struct PingV2Response {
+    #[serde(rename = "value")]
+    pub value: bool,
+}
+#[doc = "the operations of `ops`"]
+pub trait OpsServer: Send + Sync {
+    #[doc = "adds two numbers"]
+    fn add(
+        &self,
+        a: i32,
+        b: Option<i32>,
+    ) -> impl std::future::Future<Output = Result<i32, Failure>> + Send;
+    fn find(
+        &self,
+        name: String,
+    ) -> impl std::future::Future<Output = FindResponse> + Send;
+    fn ping_v1(&self) -> impl std::future::Future<Output = bool> + Send;
+    fn ping_v2(
+        &self,
+        retries: u8,
+    ) -> impl std::future::Future<Output = bool> + Send;
+}
+#[doc = r" routes the serialized request of the operation named `operation` to `server`"]
+pub async fn dispatch<S: OpsServer>(
+    server: &S,
+    operation: &str,
+    request: operation_api_sdk::serde_json::Value,
+) -> Result<operation_api_sdk::serde_json::Value, operation_api_sdk::DispatchError> {
+    match operation {
+        "add" => {
+            let AddRequest { a, b } =
+                operation_api_sdk::serde_json::from_value(request).map_err(|source| {
+                    operation_api_sdk::DispatchError::Request {
+                        operation: "add",
+                        source,
+                    }
+                })?;
+            let response = AddResponse {
+                value: server
+                    .add(a, b)
+                    .await
+                    .map_err(|error| operation_api_sdk::DispatchError::failed("add", error))?,
+            };
+            operation_api_sdk::serde_json::to_value(response).map_err(|source| {
+                operation_api_sdk::DispatchError::Response {
+                    operation: "add",
+                    source,
+                }
+            })
+        },
+        "find" => {
+            let FindRequest { name } =
+                operation_api_sdk::serde_json::from_value(request).map_err(|source| {
+                    operation_api_sdk::DispatchError::Request {
+                        operation: "find",
+                        source,
+                    }
+                })?;
+            let response: FindResponse = server.find(name).await;
+            operation_api_sdk::serde_json::to_value(response).map_err(|source| {
+                operation_api_sdk::DispatchError::Response {
+                    operation: "find",
+                    source,
+                }
+            })
+        },
+        "ping@1" => {
+            let PingV1Request {} =
+                operation_api_sdk::serde_json::from_value(request).map_err(|source| {
+                    operation_api_sdk::DispatchError::Request {
+                        operation: "ping@1",
+                        source,
+                    }
+                })?;
+            let response = PingV1Response {
+                value: server.ping_v1().await,
+            };
+            operation_api_sdk::serde_json::to_value(response).map_err(|source| {
+                operation_api_sdk::DispatchError::Response {
+                    operation: "ping@1",
+                    source,
+                }
+            })
+        },
+        "ping@2" | "ping" => {
+            let PingV2Request { retries } = operation_api_sdk::serde_json::from_value(request)
+                .map_err(|source| {
+                    operation_api_sdk::DispatchError::Request {
+                        operation: "ping@2",
+                        source,
+                    }
+                })?;
+            let response = PingV2Response {
+                value: server.ping_v2(retries).await,
+            };
+            operation_api_sdk::serde_json::to_value(response).map_err(|source| {
+                operation_api_sdk::DispatchError::Response {
+                    operation: "ping@2",
+                    source,
+                }
+            })
+        },
+        _ => {
+            Err(operation_api_sdk::DispatchError::UnknownOperation(
+                operation.to_string(),
+            ))
+        },
+    }
+}
+operation_api_sdk::namespace! { "ops" { FailureNotFound , FailureTooLarge , Failure , } }
@@ -0,0 +1,96 @@
+use operation_api_sdk::DispatchError;
+use serde_json::{Value, json};
+use test_case::test_case;
+use test_gen_server::operations::ops::*;
+
+struct Ops;
+
+impl OpsServer for Ops {
+    async fn add(
+        &self,
+        a: i32,
+        b: Option<i32>,
+    ) -> Result<i32, Failure> {
+        match a.checked_add(b.unwrap_or_default()) {
+            Some(sum) => Ok(sum),
+            None => Err(Failure::TooLarge(FailureTooLarge { limit: i32::MAX })),
+        }
+    }
+
+    async fn find(
+        &self,
+        name: String,
+    ) -> FindResponse {
+        let found: Vec<i32> = name.bytes().map(i32::from).collect();
+        FindResponse {
+            total: found.len() as i32,
+            found,
+        }
+    }
+
+    async fn ping_v1(&self) -> bool {
+        true
+    }
+
+    async fn ping_v2(
+        &self,
+        retries: u8,
+    ) -> bool {
+        retries > 0
+    }
+}
+
+#[test_case("add", json!({"a": 1, "b": 2}), json!({"value": 3}); "add")]
+#[test_case("add", json!({"a": 1}), json!({"value": 1}); "add optional")]
+#[test_case("find", json!({"name": "ab"}), json!({"found": [97, 98], "total": 2}); "find")]
+#[test_case("ping@1", json!({}), json!({"value": true}); "ping v1")]
+#[test_case("ping@2", json!({"retries": 0}), json!({"value": false}); "ping v2")]
+#[test_case("ping", json!({"retries": 1}), json!({"value": true}); "ping latest")]
+#[tokio::test]
+async fn test_dispatch(
+    operation: &str,
+    request: Value,
+    expect: Value,
+) {
+    assert_eq!(
+        dispatch(&Ops, operation, request)
+            .await
+            .unwrap(),
+        expect
+    );
+}
+
+#[tokio::test]
+async fn test_dispatch_failed() {
+    let err = dispatch(&Ops, "add", json!({"a": i32::MAX, "b": 1}))
+        .await
+        .unwrap_err();
+    match err {
+        DispatchError::Failed { operation, error } => {
+            assert_eq!(operation, "add");
+            assert_eq!(error, json!({"type": "too_large", "limit": i32::MAX}));
+        },
+        err => panic!("{err}"),
+    }
+}
+
+#[test_case("add", json!({"b": 1}); "missing argument")]
+#[test_case("ping@1", json!(null); "not an object")]
+#[tokio::test]
+async fn test_dispatch_request(
+    operation: &str,
+    request: Value,
+) {
+    assert!(matches!(
+        dispatch(&Ops, operation, request).await,
+        Err(DispatchError::Request { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_dispatch_unknown() {
+    assert!(matches!(
+        dispatch(&Ops, "ping@3", json!({})).await,
+        Err(DispatchError::UnknownOperation(operation)) if operation == "ping@3"
+    ));
+}
@@ -0,0 +1,197 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, TextEdit, Url};
+use operation_api_parser::{
+    ast::AstStream,
+    defs::Span,
+    fmt::{FormatConfig, print_ast, safety},
+    tokens::LexingError,
+};
+
+use crate::index::FileIndex;
+
+/// byte offsets of each line start, converting between source offsets and utf-16 lsp positions
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(
+            text.char_indices()
+                .filter(|(_, ch)| *ch == '\n')
+                .map(|(i, _)| i + 1),
+        );
+        Self { starts }
+    }
+
+    pub fn position(
+        &self,
+        text: &str,
+        offset: usize,
+    ) -> Position {
+        let offset = offset.min(text.len());
+        let line = self
+            .starts
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1);
+        let character = text[self.starts[line]..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn offset(
+        &self,
+        text: &str,
+        position: Position,
+    ) -> usize {
+        let Some(start) = self.starts.get(position.line as usize) else {
+            return text.len();
+        };
+        let end = self
+            .starts
+            .get(position.line as usize + 1)
+            .copied()
+            .unwrap_or(text.len());
+
+        let mut units = 0;
+        for (i, ch) in text[*start..end].char_indices() {
+            if units >= position.character as usize || ch == '\n' {
+                return start + i;
+            }
+            units += ch.len_utf16();
+        }
+        end
+    }
+
+    pub fn range(
+        &self,
+        text: &str,
+        span: &Span,
+    ) -> Range {
+        Range::new(
+            self.position(text, span.start),
+            self.position(text, span.end),
+        )
+    }
+}
+
+/// a schema file known to the server, reparsed with error recovery on every change
+pub struct Document {
+    pub uri: Url,
+    pub text: String,
+    pub lines: LineIndex,
+    pub ast: AstStream,
+    pub errors: Vec<LexingError>,
+    pub index: FileIndex,
+}
+
+impl Document {
+    pub fn new(
+        uri: Url,
+        text: String,
+    ) -> Self {
+        let (ast, errors) = AstStream::from_string_recovering(&text);
+        let index = FileIndex::new(&ast);
+        Self {
+            uri,
+            lines: LineIndex::new(&text),
+            text,
+            ast,
+            errors,
+            index,
+        }
+    }
+
+    pub fn range(
+        &self,
+        span: &Span,
+    ) -> Range {
+        self.lines.range(&self.text, span)
+    }
+
+    pub fn offset(
+        &self,
+        position: Position,
+    ) -> usize {
+        self.lines.offset(&self.text, position)
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors
+            .iter()
+            .map(|err| {
+                let range = match err {
+                    LexingError::Spanned { span, .. } => self.range(span),
+                    _ => Range::default(),
+                };
+                Diagnostic {
+                    range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("op".into()),
+                    message: err.to_string(),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// edits formatting the whole document. files with syntax errors, or whose tokens formatting
+    /// would change, are left untouched.
+    pub fn format(
+        &self,
+        cfg: &FormatConfig,
+    ) -> Option<Vec<TextEdit>> {
+        if !self.errors.is_empty() {
+            return None;
+        }
+        let formatted = print_ast(&self.ast, cfg);
+        if formatted == self.text {
+            return Some(vec![]);
+        }
+        let path = std::path::Path::new(self.uri.path());
+        if let Some(unsafe_edit) = safety::verify(path, &self.text, &formatted).unsafe_edit {
+            tracing::warn!("not formatting {}: {unsafe_edit}", self.uri);
+            return None;
+        }
+        Some(vec![TextEdit::new(
+            self.range(&Span::new(0, self.text.len())),
+            formatted,
+        )])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{Position, Url};
+    use operation_api_parser::fmt::FormatConfig;
+
+    use super::{Document, LineIndex};
+
+    #[test_case::test_case("abc\ndef", 5, Position::new(1, 1); "second line")]
+    #[test_case::test_case("abc\n", 4, Position::new(1, 0); "trailing newline")]
+    #[test_case::test_case("// é\nx", 5, Position::new(0, 4); "multi byte")]
+    #[test_case::test_case("// 😀 x", 8, Position::new(0, 6); "surrogate pair")]
+    fn test_line_index(
+        text: &str,
+        offset: usize,
+        expect: Position,
+    ) {
+        let lines = LineIndex::new(text);
+        assert_eq!(lines.position(text, offset), expect);
+        assert_eq!(lines.offset(text, expect), offset.min(text.len()));
+    }
+
+    #[test_case::test_case("namespace a;\nstruct A { a: i32 };", Some(1); "formatted")]
+    #[test_case::test_case("namespace a;\n\nstruct A {\n\ta: i32\n};\n", Some(0); "unchanged")]
+    #[test_case::test_case("namespace a;\nstruct A { a: i32 /* trails */\n};", None; "changed tokens")]
+    fn test_format(
+        text: &str,
+        edits: Option<usize>,
+    ) {
+        let doc = Document::new(Url::parse("file:///a.pld").unwrap(), text.into());
+        let formatted = doc.format(&FormatConfig::default());
+        assert_eq!(formatted.map(|it| it.len()), edits);
+    }
+}
@@ -0,0 +1,346 @@
+use operation_api_parser::{
+    ast::{
+        AstStream,
+        anonymous::StructFields,
+        array::Array,
+        comment::CommentStream,
+        enm::Enum,
+        items::Items,
+        ty::{PathOrIdent, Type},
+        union::{IdentOrUnion, Union},
+        variadic::Variant,
+    },
+    defs::Span,
+};
+
+/// a named item declared in a schema file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub tag: &'static str,
+    pub namespace: Option<String>,
+    pub name: String,
+    /// span of the declared name
+    pub span: Span,
+    pub description: Option<String>,
+}
+
+/// a type reference to a declared item, e.g. a field of type `Foo` or `pkg::Foo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// namespace the reference was made from
+    pub namespace: Option<String>,
+    pub path: Vec<String>,
+    pub span: Span,
+}
+
+impl Reference {
+    pub fn name(&self) -> &str {
+        self.path
+            .last()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// the namespace segment the reference was qualified with, if any
+    pub fn qualifier(&self) -> Option<&str> {
+        self.path
+            .len()
+            .checked_sub(2)
+            .map(|i| self.path[i].as_str())
+    }
+}
+
+/// declarations and references of a single file
+#[derive(Debug, Default)]
+pub struct FileIndex {
+    pub declarations: Vec<Declaration>,
+    pub references: Vec<Reference>,
+}
+
+impl FileIndex {
+    pub fn new(ast: &AstStream) -> Self {
+        let mut this = Self::default();
+        this.items(ast, None);
+        this
+    }
+
+    pub fn declaration_at(
+        &self,
+        offset: usize,
+    ) -> Option<&Declaration> {
+        self.declarations
+            .iter()
+            .find(|it| contains(&it.span, offset))
+    }
+
+    pub fn reference_at(
+        &self,
+        offset: usize,
+    ) -> Option<&Reference> {
+        self.references
+            .iter()
+            .find(|it| contains(&it.span, offset))
+    }
+
+    fn items(
+        &mut self,
+        ast: &AstStream,
+        scope: Option<String>,
+    ) {
+        let mut current = scope;
+        for node in &ast.nodes {
+            match &node.value {
+                Items::Namespace(def) => {
+                    let name = def.def.name.borrow_string().clone();
+                    self.declare("namespace", None, &name, &def.def.name.span, &def.comments);
+                    current = Some(name);
+                },
+                Items::SpannedNamespace(def) => {
+                    let name = def.def.name.borrow_string();
+                    let ns = match &current {
+                        Some(parent) => format!("{parent}.{name}"),
+                        None => name.clone(),
+                    };
+                    self.declare(
+                        "namespace",
+                        current.clone(),
+                        name,
+                        &def.def.name.span,
+                        &def.comments,
+                    );
+                    self.items(&def.def.ast.value, Some(ns));
+                },
+                Items::Struct(def) => {
+                    self.declare(
+                        "struct",
+                        current.clone(),
+                        def.def.name.borrow_string(),
+                        &def.def.name.span,
+                        &def.comments,
+                    );
+                    for arg in &def.def.args.values {
+                        self.ty(&current, &arg.value.typ);
+                    }
+                },
+                Items::Enum(def) => {
+                    let name = match &def.def.value {
+                        Enum::Int(e) => &e.name,
+                        Enum::Str(e) => &e.name,
+                    };
+                    self.declare(
+                        "enum",
+                        current.clone(),
+                        name.borrow_string(),
+                        &name.span,
+                        &def.comments,
+                    );
+                },
+                Items::OneOf(def) => {
+                    self.declare(
+                        "oneof",
+                        current.clone(),
+                        def.def.name.borrow_string(),
+                        &def.def.name.span,
+                        &def.comments,
+                    );
+                    for variant in &def.def.variants.values {
+                        self.variant(&current, &variant.value);
+                    }
+                },
+                Items::Error(def) => {
+                    self.declare(
+                        "error",
+                        current.clone(),
+                        def.def.name.borrow_string(),
+                        &def.def.name.span,
+                        &def.comments,
+                    );
+                    for variant in &def.def.variants.values {
+                        self.variant(&current, &variant.value);
+                    }
+                },
+                Items::Type(def) => {
+                    self.declare(
+                        "type",
+                        current.clone(),
+                        def.def.name.borrow_string(),
+                        &def.def.name.span,
+                        &def.comments,
+                    );
+                    self.ty(&current, &def.def.ty);
+                },
+                Items::Operation(def) => {
+                    self.declare(
+                        "operation",
+                        current.clone(),
+                        def.def.name.borrow_string(),
+                        &def.def.name.span,
+                        &def.comments,
+                    );
+                    if let Some(args) = &def.def.args {
+                        for arg in &args.values {
+                            self.ty(&current, &arg.value.typ);
+                        }
+                    }
+                    self.ty(&current, &def.def.return_type);
+                },
+                Items::Use(..) | Items::Meta(..) | Items::Invalid(..) => {},
+            }
+        }
+    }
+
+    fn declare(
+        &mut self,
+        tag: &'static str,
+        namespace: Option<String>,
+        name: &str,
+        span: &Span,
+        comments: &CommentStream,
+    ) {
+        self.declarations.push(Declaration {
+            tag,
+            namespace,
+            name: name.to_string(),
+            span: span.clone(),
+            description: comments.description(),
+        });
+    }
+
+    fn reference(
+        &mut self,
+        scope: &Option<String>,
+        to: &PathOrIdent,
+    ) {
+        let (path, span) = match to {
+            PathOrIdent::Ident(ident) => (vec![ident.borrow_string().clone()], &ident.span),
+            PathOrIdent::Path(path) => (path.borrow_path_inner().segments().clone(), &path.span),
+        };
+        self.references.push(Reference {
+            namespace: scope.clone(),
+            path,
+            span: span.clone(),
+        });
+    }
+
+    fn ty(
+        &mut self,
+        scope: &Option<String>,
+        ty: &Type,
+    ) {
+        match ty {
+            Type::Builtin { .. } => {},
+            Type::Ident { to, .. } => self.reference(scope, to),
+            Type::OneOf { ty } => {
+                for variant in &ty.variants.values {
+                    self.ty(scope, &variant.value);
+                }
+            },
+            Type::Array { ty } => {
+                match &ty.value {
+                    Array::Unsized { ty, .. } | Array::Sized { ty, .. } => self.ty(scope, ty),
+                }
+            },
+            Type::Paren { ty, .. } | Type::Result { ty, .. } => self.ty(scope, ty),
+            Type::Union { ty } => self.union(scope, ty),
+            Type::Struct { ty } => self.fields(scope, &ty.fields),
+        }
+    }
+
+    fn union(
+        &mut self,
+        scope: &Option<String>,
+        union: &Union,
+    ) {
+        for operand in &union.types.values {
+            match &operand.value.value {
+                IdentOrUnion::Ident(to) => self.reference(scope, to),
+                IdentOrUnion::Union { inner, .. } => self.union(scope, inner),
+            }
+        }
+    }
+
+    fn fields(
+        &mut self,
+        scope: &Option<String>,
+        fields: &StructFields,
+    ) {
+        for arg in &fields.values {
+            self.ty(scope, &arg.value.typ);
+        }
+    }
+
+    fn variant(
+        &mut self,
+        scope: &Option<String>,
+        variant: &Variant,
+    ) {
+        match variant {
+            Variant::Tuple { inner, .. } => self.ty(scope, inner),
+            Variant::LocalStruct { inner, .. } => self.fields(scope, &inner.fields),
+        }
+    }
+}
+
+fn contains(
+    span: &Span,
+    offset: usize,
+) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+#[cfg(test)]
+mod test {
+    use operation_api_parser::ast::AstStream;
+
+    use super::FileIndex;
+
+    #[test]
+    fn test_index() {
+        let (ast, errors) = AstStream::from_string_recovering(
+            "namespace test;
+
+// a foo
+struct Foo {
+    a: Bar,
+    b: (oneof other::Baz | str)[],
+    c: { d: Bar & Qux },
+};
+
+oneof Bar {
+    a(Foo),
+    b { c: Foo },
+};
+
+operation get(id: i32) -> Foo!;
+",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let index = FileIndex::new(&ast);
+        let decls: Vec<_> = index
+            .declarations
+            .iter()
+            .map(|it| (it.tag, it.namespace.as_deref(), it.name.as_str()))
+            .collect();
+        assert_eq!(
+            decls,
+            vec![
+                ("namespace", None, "test"),
+                ("struct", Some("test"), "Foo"),
+                ("oneof", Some("test"), "Bar"),
+                ("operation", Some("test"), "get"),
+            ]
+        );
+        assert_eq!(index.declarations[1].description.as_deref(), Some("a foo"));
+
+        let refs: Vec<_> = index
+            .references
+            .iter()
+            .map(|it| it.path.join("::"))
+            .collect();
+        assert_eq!(
+            refs,
+            vec!["Bar", "other::Baz", "Bar", "Qux", "Foo", "Foo", "Foo"]
+        );
+    }
+}
@@ -0,0 +1,34 @@
+pub mod document;
+pub mod index;
+pub mod server;
+
+pub use server::Server;
+
+use miette::IntoDiagnostic;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("protocol error: {0}")]
+    Protocol(#[from] lsp_server::ProtocolError),
+
+    #[error("connection closed: {0}")]
+    Send(#[from] crossbeam_channel::SendError<lsp_server::Message>),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// serves the language server over stdio until the client exits
+pub fn stdio() -> miette::Result<()> {
+    let (connection, threads) = lsp_server::Connection::stdio();
+    Server::initialize(&connection)
+        .and_then(|server| server.run(&connection))
+        .into_diagnostic()?;
+    drop(connection);
+    threads.join().into_diagnostic()
+}
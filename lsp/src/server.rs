@@ -0,0 +1,712 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, Location,
+    MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, ServerCapabilities, ServerInfo,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{Completion, Formatting, GotoDefinition, HoverRequest, Request as _},
+};
+use operation_api_manifests::NewForConfig;
+use operation_api_parser::{Token, ast::ty::Builtin, fmt::FormatConfig, tokens::ImplDiagnostic};
+
+use crate::{document::Document, index::Declaration};
+
+pub struct Server {
+    fmt: FormatConfig,
+    /// documents opened by the client, in their edited state
+    open: BTreeMap<Url, Document>,
+    /// schema files found in the workspace at startup, used to resolve references in closed files
+    workspace: BTreeMap<Url, Document>,
+}
+
+impl Server {
+    pub fn new(root: Option<PathBuf>) -> Self {
+        let fmt = match &root {
+            Some(root) => {
+                FormatConfig::new(Some(root.display().to_string())).unwrap_or_else(|err| {
+                    tracing::warn!("could not load format config: {err}");
+                    FormatConfig::default()
+                })
+            },
+            None => FormatConfig::default(),
+        };
+
+        let mut workspace = BTreeMap::new();
+        if let Some(root) = &root {
+            for path in schema_files(root) {
+                let Ok(text) = std::fs::read_to_string(&path) else {
+                    continue;
+                };
+                if let Ok(uri) = Url::from_file_path(&path) {
+                    workspace.insert(uri.clone(), Document::new(uri, text));
+                }
+            }
+        }
+
+        Self {
+            fmt,
+            open: BTreeMap::new(),
+            workspace,
+        }
+    }
+
+    pub fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            definition_provider: Some(OneOf::Left(true)),
+            completion_provider: Some(CompletionOptions::default()),
+            ..Default::default()
+        }
+    }
+
+    /// performs the initialize handshake, loading the workspace the client opened
+    pub fn initialize(connection: &Connection) -> crate::Result<Self> {
+        let (id, params) = connection.initialize_start()?;
+        let params: InitializeParams = serde_json::from_value(params)?;
+
+        #[allow(deprecated)]
+        let root = params
+            .workspace_folders
+            .as_ref()
+            .and_then(|folders| folders.first())
+            .map(|folder| &folder.uri)
+            .or(params.root_uri.as_ref())
+            .and_then(|uri| uri.to_file_path().ok());
+
+        let server = Self::new(root);
+        connection.initialize_finish(
+            id,
+            serde_json::to_value(InitializeResult {
+                capabilities: Self::capabilities(),
+                server_info: Some(ServerInfo {
+                    name: "op".into(),
+                    version: Some(env!("CARGO_PKG_VERSION").into()),
+                }),
+            })?,
+        )?;
+        Ok(server)
+    }
+
+    pub fn run(
+        mut self,
+        connection: &Connection,
+    ) -> crate::Result<()> {
+        for msg in &connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    connection
+                        .sender
+                        .send(Message::Response(self.request(req)))?;
+                },
+                Message::Notification(not) => {
+                    let method = not.method.clone();
+                    // a malformed notification is dropped, rather than shutting the server down
+                    let out = self.notification(not).unwrap_or_else(|err| {
+                        tracing::warn!("could not handle {method}: {err}");
+                        vec![]
+                    });
+                    for out in out {
+                        connection
+                            .sender
+                            .send(Message::Notification(out))?;
+                    }
+                },
+                Message::Response(_) => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn request(
+        &self,
+        req: Request,
+    ) -> Response {
+        match req.method.as_str() {
+            Formatting::METHOD => self.respond::<Formatting>(req, Self::formatting),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(req, Self::hover),
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(req, Self::definition),
+            Completion::METHOD => self.respond::<Completion>(req, Self::completion),
+            method => {
+                Response::new_err(
+                    req.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unhandled method {method}"),
+                )
+            },
+        }
+    }
+
+    fn respond<R: lsp_types::request::Request>(
+        &self,
+        req: Request,
+        handler: fn(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        match serde_json::from_value(req.params) {
+            Ok(params) => Response::new_ok(req.id, handler(self, params)),
+            Err(err) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    fn notification(
+        &mut self,
+        not: Notification,
+    ) -> crate::Result<Vec<Notification>> {
+        let uri = match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                self.open.insert(
+                    uri.clone(),
+                    Document::new(uri.clone(), params.text_document.text),
+                );
+                uri
+            },
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                // the document is synced in full, so only the last change matters
+                let Some(change) = params.content_changes.into_iter().last() else {
+                    return Ok(vec![]);
+                };
+                self.open
+                    .insert(uri.clone(), Document::new(uri.clone(), change.text));
+                uri
+            },
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                self.open.remove(&uri);
+                if let Some(text) = uri
+                    .to_file_path()
+                    .ok()
+                    .and_then(|path| std::fs::read_to_string(path).ok())
+                {
+                    self.workspace
+                        .insert(uri.clone(), Document::new(uri.clone(), text));
+                }
+                return Ok(vec![publish(uri, vec![])]);
+            },
+            _ => return Ok(vec![]),
+        };
+
+        let diagnostics = self.open[&uri].diagnostics();
+        Ok(vec![publish(uri, diagnostics)])
+    }
+
+    fn document(
+        &self,
+        uri: &Url,
+    ) -> Option<&Document> {
+        self.open
+            .get(uri)
+            .or_else(|| self.workspace.get(uri))
+    }
+
+    /// every known document, preferring the edited state of open documents
+    fn documents(&self) -> impl Iterator<Item = &Document> {
+        self.open.values().chain(
+            self.workspace
+                .iter()
+                .filter(|(uri, _)| !self.open.contains_key(*uri))
+                .map(|(_, doc)| doc),
+        )
+    }
+
+    fn formatting(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> Option<Vec<TextEdit>> {
+        self.document(&params.text_document.uri)?
+            .format(&self.fmt)
+    }
+
+    /// the declaration under the cursor, or the declaration a reference under the cursor points to
+    fn target(
+        &self,
+        uri: &Url,
+        position: lsp_types::Position,
+    ) -> Option<(&Document, &Declaration)> {
+        let doc = self.document(uri)?;
+        let offset = doc.offset(position);
+        if let Some(decl) = doc.index.declaration_at(offset) {
+            return Some((doc, decl));
+        }
+
+        let reference = doc.index.reference_at(offset)?;
+        // prefer declarations in the referenced namespace, then those in the same file
+        self.documents()
+            .flat_map(|other| {
+                other
+                    .index
+                    .declarations
+                    .iter()
+                    .map(move |decl| (other, decl))
+            })
+            .filter(|(_, decl)| decl.tag != "namespace" && decl.name == reference.name())
+            .max_by_key(|(other, decl)| {
+                let in_namespace = match reference.qualifier() {
+                    Some(qualifier) => {
+                        decl.namespace
+                            .as_deref()
+                            .and_then(|ns| ns.rsplit('.').next())
+                            == Some(qualifier)
+                    },
+                    None => decl.namespace == reference.namespace,
+                };
+                (in_namespace, other.uri == doc.uri)
+            })
+    }
+
+    fn hover(
+        &self,
+        params: HoverParams,
+    ) -> Option<Hover> {
+        let at = params.text_document_position_params;
+        let (_, decl) = self.target(&at.text_document.uri, at.position)?;
+
+        let mut value = format!("```pld\n{} {}\n```", decl.tag, decl.name);
+        if let Some(ns) = &decl.namespace {
+            value.push_str(&format!("\n\nnamespace `{ns}`"));
+        }
+        if let Some(desc) = &decl.description {
+            value.push_str("\n\n---\n\n");
+            value.push_str(desc);
+        }
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        })
+    }
+
+    fn definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Option<GotoDefinitionResponse> {
+        let at = params.text_document_position_params;
+        let (doc, decl) = self.target(&at.text_document.uri, at.position)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            doc.uri.clone(),
+            doc.range(&decl.span),
+        )))
+    }
+
+    fn completion(
+        &self,
+        _: CompletionParams,
+    ) -> Option<CompletionResponse> {
+        let keywords = [
+            <Token![namespace]>::fmt(),
+            <Token![use]>::fmt(),
+            <Token![struct]>::fmt(),
+            <Token![enum]>::fmt(),
+            <Token![oneof]>::fmt(),
+            <Token![error]>::fmt(),
+            <Token![type]>::fmt(),
+            <Token![operation]>::fmt(),
+        ];
+
+        let mut items: Vec<_> = keywords
+            .into_iter()
+            .map(|kw| completion(kw, CompletionItemKind::KEYWORD, None))
+            .chain(
+                Builtin::names()
+                    .into_iter()
+                    .map(|ty| completion(ty, CompletionItemKind::TYPE_PARAMETER, None)),
+            )
+            .collect();
+
+        let mut declared = BTreeMap::new();
+        for decl in self
+            .documents()
+            .flat_map(|doc| &doc.index.declarations)
+        {
+            let kind = match decl.tag {
+                "struct" | "error" => CompletionItemKind::STRUCT,
+                "enum" | "oneof" => CompletionItemKind::ENUM,
+                "type" => CompletionItemKind::CLASS,
+                "namespace" => CompletionItemKind::MODULE,
+                _ => continue,
+            };
+            declared
+                .entry(decl.name.as_str())
+                .or_insert_with(|| completion(&decl.name, kind, Some(decl.tag)));
+        }
+        items.extend(declared.into_values());
+
+        Some(CompletionResponse::Array(items))
+    }
+}
+
+fn completion(
+    label: &str,
+    kind: CompletionItemKind,
+    detail: Option<&str>,
+) -> CompletionItem {
+    CompletionItem {
+        label: label.into(),
+        kind: Some(kind),
+        detail: detail.map(String::from),
+        ..Default::default()
+    }
+}
+
+fn publish(
+    uri: Url,
+    diagnostics: Vec<lsp_types::Diagnostic>,
+) -> Notification {
+    Notification::new(
+        PublishDiagnostics::METHOD.into(),
+        PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        },
+    )
+}
+
+fn schema_files(root: &Path) -> Vec<PathBuf> {
+    let pattern = root.join("**").join("*.pld");
+    glob::glob(&pattern.display().to_string())
+        .map(|paths| paths.flatten().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::VecDeque, path::Path, thread::JoinHandle};
+
+    use lsp_server::{Connection, Message, Notification, Request, RequestId};
+    use lsp_types::{
+        CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+        DidOpenTextDocumentParams, DocumentFormattingParams, FormattingOptions,
+        GotoDefinitionParams, GotoDefinitionResponse, HoverContents, HoverParams, InitializeParams,
+        Position, PublishDiagnosticsParams, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+        TextDocumentItem, TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier,
+        notification::{
+            DidChangeTextDocument, DidOpenTextDocument, Exit, Initialized, PublishDiagnostics,
+        },
+        request::{Completion, Formatting, GotoDefinition, HoverRequest, Initialize, Shutdown},
+    };
+
+    use super::Server;
+
+    /// an in-process client driving the server over an in-memory connection
+    struct Client {
+        conn: Connection,
+        server: Option<JoinHandle<crate::Result<()>>>,
+        notifications: VecDeque<Notification>,
+        id: i32,
+    }
+
+    impl Client {
+        fn start(root: Option<&Path>) -> Self {
+            let (server, conn) = Connection::memory();
+            let handle = std::thread::spawn(move || Server::initialize(&server)?.run(&server));
+            let mut this = Self {
+                conn,
+                server: Some(handle),
+                notifications: VecDeque::new(),
+                id: 0,
+            };
+            #[allow(deprecated)]
+            this.request::<Initialize>(InitializeParams {
+                root_uri: root.map(|root| Url::from_directory_path(root).unwrap()),
+                ..Default::default()
+            });
+            this.notify::<Initialized>(lsp_types::InitializedParams {});
+            this
+        }
+
+        fn request<R: lsp_types::request::Request>(
+            &mut self,
+            params: R::Params,
+        ) -> R::Result {
+            self.id += 1;
+            let id = RequestId::from(self.id);
+            self.conn
+                .sender
+                .send(Message::Request(Request::new(
+                    id.clone(),
+                    R::METHOD.into(),
+                    params,
+                )))
+                .unwrap();
+            loop {
+                match self.conn.receiver.recv().unwrap() {
+                    Message::Response(resp) if resp.id == id => {
+                        assert!(resp.error.is_none(), "{:?}", resp.error);
+                        return serde_json::from_value(resp.result.unwrap_or_default()).unwrap();
+                    },
+                    Message::Notification(not) => self.notifications.push_back(not),
+                    msg => panic!("unexpected message {msg:?}"),
+                }
+            }
+        }
+
+        fn notify<N: lsp_types::notification::Notification>(
+            &self,
+            params: N::Params,
+        ) {
+            self.conn
+                .sender
+                .send(Message::Notification(Notification::new(
+                    N::METHOD.into(),
+                    params,
+                )))
+                .unwrap();
+        }
+
+        fn diagnostics(&mut self) -> PublishDiagnosticsParams {
+            let not = match self.notifications.pop_front() {
+                Some(not) => not,
+                None => {
+                    match self.conn.receiver.recv().unwrap() {
+                        Message::Notification(not) => not,
+                        msg => panic!("unexpected message {msg:?}"),
+                    }
+                },
+            };
+            assert_eq!(
+                not.method,
+                <PublishDiagnostics as lsp_types::notification::Notification>::METHOD
+            );
+            serde_json::from_value(not.params).unwrap()
+        }
+
+        fn open(
+            &mut self,
+            uri: &Url,
+            text: &str,
+        ) -> PublishDiagnosticsParams {
+            self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri.clone(), "pld".into(), 0, text.into()),
+            });
+            self.diagnostics()
+        }
+
+        fn at(
+            uri: &Url,
+            line: u32,
+            character: u32,
+        ) -> TextDocumentPositionParams {
+            TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.clone()),
+                Position::new(line, character),
+            )
+        }
+
+        fn shutdown(mut self) {
+            self.request::<Shutdown>(());
+            self.notify::<Exit>(());
+            self.server
+                .take()
+                .unwrap()
+                .join()
+                .unwrap()
+                .unwrap();
+        }
+    }
+
+    fn uri(path: &str) -> Url {
+        Url::parse(&format!("file:///tmp/{path}")).unwrap()
+    }
+
+    #[test]
+    fn test_publish_diagnostics() {
+        let mut client = Client::start(None);
+        let uri = uri("a.pld");
+
+        let published = client.open(
+            &uri,
+            "namespace test;\n\nstruct A { a: };\n\nstruct B { b: i32 };\n\nstrcut C;\n",
+        );
+        assert_eq!(published.uri, uri);
+        let lines: Vec<_> = published
+            .diagnostics
+            .iter()
+            .map(|it| it.range.start.line)
+            .collect();
+        assert_eq!(lines, vec![2, 6]);
+
+        client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 1),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "namespace test;\n\nstruct A { a: i32 };\n".into(),
+            }],
+        });
+        assert!(client.diagnostics().diagnostics.is_empty());
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn test_malformed_notification() {
+        let mut client = Client::start(None);
+        let uri = uri("a.pld");
+
+        client
+            .conn
+            .sender
+            .send(Message::Notification(Notification::new(
+                <DidOpenTextDocument as lsp_types::notification::Notification>::METHOD.into(),
+                serde_json::json!({ "textDocument": 1 }),
+            )))
+            .unwrap();
+
+        let published = client.open(&uri, "namespace test;\n\nstruct A { a: i32 };\n");
+        assert_eq!(published.uri, uri);
+        assert!(published.diagnostics.is_empty());
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn test_formatting() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("op-fmt.toml"),
+            "indent_with_tabs = false\nindent_width = 2\n",
+        )
+        .unwrap();
+
+        let mut client = Client::start(Some(root.path()));
+        let uri = uri("a.pld");
+        client.open(&uri, "namespace test;\n\nstruct A { a: i32 };\n");
+
+        let formatting = |uri: &Url| {
+            DocumentFormattingParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                options: FormattingOptions::default(),
+                work_done_progress_params: Default::default(),
+            }
+        };
+        let edits = client
+            .request::<Formatting>(formatting(&uri))
+            .unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0].new_text,
+            "namespace test;\n\nstruct A {\n  a: i32\n};\n"
+        );
+
+        let broken = self::uri("b.pld");
+        client.open(&broken, "namespace test;\n\nstruct A { a: };\n");
+        assert_eq!(client.request::<Formatting>(formatting(&broken)), None);
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn test_hover_and_definition() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("types.pld"),
+            "namespace types;\n\n// the user's identity\nstruct User {\n\tid: i32\n};\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.path().join("other.pld"),
+            "namespace other;\n\nstruct User {\n\tname: str\n};\n",
+        )
+        .unwrap();
+
+        let mut client = Client::start(Some(root.path()));
+        let uri = Url::from_file_path(root.path().join("api.pld")).unwrap();
+        client.open(
+            &uri,
+            "namespace api;\n\nstruct Req {\n\tuser: types::User\n};\n",
+        );
+
+        let hover = client
+            .request::<HoverRequest>(HoverParams {
+                text_document_position_params: Client::at(&uri, 3, 16),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+        let HoverContents::Markup(markup) = hover.contents else {
+            panic!("expected markup");
+        };
+        assert_eq!(
+            markup.value,
+            "```pld\nstruct User\n```\n\nnamespace `types`\n\n---\n\nthe user's identity"
+        );
+
+        let Some(GotoDefinitionResponse::Scalar(location)) =
+            client.request::<GotoDefinition>(GotoDefinitionParams {
+                text_document_position_params: Client::at(&uri, 3, 16),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+        else {
+            panic!("expected a definition");
+        };
+        assert_eq!(
+            location.uri,
+            Url::from_file_path(root.path().join("types.pld")).unwrap()
+        );
+        assert_eq!(location.range.start, Position::new(3, 7));
+
+        // positions outside of a reference resolve nothing
+        assert_eq!(
+            client.request::<GotoDefinition>(GotoDefinitionParams {
+                text_document_position_params: Client::at(&uri, 3, 2),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            }),
+            None
+        );
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut client = Client::start(None);
+        let uri = uri("a.pld");
+        client.open(
+            &uri,
+            "namespace test;\n\nenum Color { Red = 1 };\n\nstruct A { a: ",
+        );
+
+        let Some(CompletionResponse::Array(items)) =
+            client.request::<Completion>(CompletionParams {
+                text_document_position: Client::at(&uri, 4, 14),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: None,
+            })
+        else {
+            panic!("expected completions");
+        };
+        let labels: Vec<_> = items
+            .iter()
+            .map(|it| it.label.as_str())
+            .collect();
+        for expect in ["struct", "oneof", "i32", "datetime", "Color", "test"] {
+            assert!(labels.contains(&expect), "missing {expect} in {labels:?}");
+        }
+
+        client.shutdown();
+    }
+}
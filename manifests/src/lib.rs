@@ -72,7 +72,7 @@ pub fn init(
 
     let dir = dir.unwrap_or_else(|| PathBuf::from(pkg.package.name.clone()));
 
-    let manifest = dir.join("manifest.toml");
+    let manifest = dir.join("schema.toml");
 
     if !dir.exists() {
         std::fs::create_dir(&dir)?;
@@ -83,7 +83,7 @@ pub fn init(
 
     let schema = dir.join("schema/");
     if !schema.exists() {
-        std::fs::create_dir(dir)?;
+        std::fs::create_dir(&schema)?;
     }
 
     let lib = schema.join("lib.pld");
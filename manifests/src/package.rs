@@ -63,6 +63,10 @@ pub struct PackageManifest {
     pub dependencies: BTreeMap<String, Dependency>,
 }
 
+impl crate::NewForConfig for PackageManifest {
+    const NAME: &'static str = "schema";
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(untagged)]
 pub enum Dependency {
@@ -111,7 +115,7 @@ mod test {
         "name: Validation error: length"; "name too long")]
     #[test_case::test_case("abc_types!", "0.1.0", "https://github.com/abc/foo.git", "name: package name must be provided without spaces or special characters"; "invalid character in name")]
     #[test_case::test_case("abc_types", "0.1.0", "not-a-url", "homepage: Validation error: url [{\"value\": String(\"not-a-url\")}]"; "invalid homepage url")]
-    #[test_case::test_case("abc", "0.1", "https://github.com/abc/foo.git", "version: Validation error:"; "version without patch")]
+    #[test_case::test_case("abc", "0.1", "https://github.com/abc/foo.git", "version: patch version is required in fully qualified package versions"; "version without patch")]
 
     fn test_pkg_validate_err(
         name: &str,
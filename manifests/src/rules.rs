@@ -23,8 +23,8 @@ impl Fix {
     pub fn diagnostic(&self) -> &'static str {
         match self {
             Self::Skip => "info: this fix is being skipped",
-            Self::Safe => "info: safe to fix",
-            Self::Unsafe => "warning: warning: this fix is unsafe and requires the `--unsafe` flag",
+            Self::Safe => "info: safe to fix with `--fix`",
+            Self::Unsafe => "warning: this fix is unsafe and requires the `--unsafe-fixes` flag",
         }
     }
 }
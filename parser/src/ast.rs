@@ -24,13 +24,14 @@ use crate::{
     Parse,
     ast::comment::CommentStream,
     defs::Spanned,
-    tokens::{AstResult, TokenStream, tokenize},
+    diagnostics::{SpanDiagnostic, SpanDiagnostics},
+    tokens::{AstResult, LexingError, TokenStream, tokenize, toks::Token},
 };
 
 pub struct AstStream {
-    module_comments: CommentStream,
-    module_meta: Spanned<meta::ItemMeta>,
-    nodes: Vec<Spanned<items::Items>>,
+    pub module_comments: CommentStream,
+    pub module_meta: Spanned<meta::ItemMeta>,
+    pub nodes: Vec<Spanned<items::Items>>,
 }
 
 impl crate::Parse for AstStream {
@@ -69,6 +70,115 @@ impl AstStream {
         })
     }
 
+    /// parses as many items as possible, resynchronising at `;` and `}` item boundaries after a syntax
+    /// error. items which fail to parse are kept as [`items::Items::Invalid`] placeholders.
+    pub fn from_tokens_recovering(tt: &mut TokenStream) -> (Self, Vec<LexingError>) {
+        let mut errors = vec![];
+        // a malformed header is reported as part of the first item instead
+        let (module_comments, module_meta) = CommentStream::parse(tt)
+            .and_then(|comments| Ok((comments, tt.parse()?)))
+            .unwrap_or_else(|_| {
+                tt.rewind(0);
+                (
+                    CommentStream { comments: vec![] },
+                    Spanned::new(0, 0, meta::ItemMeta { meta: vec![] }),
+                )
+            });
+
+        let mut nodes = vec![];
+        while let Some(first) = tt.peek_unchecked() {
+            let start = tt.cursor();
+            let from = first.span.start;
+            match tt.parse::<items::Items>() {
+                Ok(node) => nodes.push(node),
+                Err(err) => {
+                    let err = match err {
+                        err @ LexingError::Spanned { .. } => err,
+                        err => {
+                            err.with_span(
+                                tt.last_span()
+                                    .unwrap_or(tt.current_span())
+                                    .clone(),
+                            )
+                        },
+                    };
+                    errors.push(err);
+                    tt.rewind(start);
+                    Self::synchronize(tt);
+                    let to = tt
+                        .last_span()
+                        .map(|it| it.end)
+                        .unwrap_or(from)
+                        .max(from);
+                    nodes.push(Spanned::new(
+                        from,
+                        to,
+                        items::Items::Invalid(items::InvalidDef {
+                            source: tt.source()[from..to].to_string(),
+                        }),
+                    ));
+                },
+            }
+        }
+
+        (
+            Self {
+                module_comments,
+                module_meta,
+                nodes,
+            },
+            errors,
+        )
+    }
+
+    /// skips to the end of the current item: a `;` outside of any braces, or the `}` closing the
+    /// item's block (along with its trailing `;`).
+    fn synchronize(tt: &mut TokenStream) {
+        let mut depth = 0usize;
+        while let Some(tok) = tt.next() {
+            match tok.value {
+                Token::LBrace => depth += 1,
+                Token::RBrace => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        if matches!(tt.peek_unchecked().map(|it| &it.value), Some(Token::Semi)) {
+                            tt.next();
+                        }
+                        return;
+                    }
+                },
+                Token::Semi if depth == 0 => return,
+                _ => {},
+            }
+        }
+    }
+
+    pub fn from_string_recovering(src: &str) -> (Self, Vec<LexingError>) {
+        let (mut tt, mut errors) = TokenStream::lex_recovering(src);
+        let (ast, parse_errors) = Self::from_tokens_recovering(&mut tt);
+        errors.extend(parse_errors);
+        errors.sort_by_key(|err| {
+            match err {
+                LexingError::Spanned { span, .. } => span.start,
+                _ => 0,
+            }
+        });
+        (ast, errors)
+    }
+
+    /// parses a file with error recovery, returning every syntax error as a diagnostic
+    pub fn from_string_with_recovering(
+        path: impl AsRef<Path>,
+        data: &str,
+    ) -> (Self, SpanDiagnostics) {
+        let (ast, errors) = Self::from_string_recovering(data);
+        let diagnostics = errors
+            .iter()
+            .map(|err| SpanDiagnostic::from_lexing(err, path.as_ref(), data))
+            .collect();
+        (ast, SpanDiagnostics::new(path.as_ref(), diagnostics))
+    }
+
     pub fn from_string(src: &str) -> AstResult<Self> {
         let mut tt = tokenize(src)?;
         Self::from_tokens(&mut tt)
@@ -134,7 +244,7 @@ impl crate::tokens::ToTokens for AstStream {
 
 #[cfg(test)]
 mod test {
-    use crate::{fmt::FormatConfig, tokens::ToTokens};
+    use crate::fmt::FormatConfig;
 
     use super::*;
 
@@ -149,6 +259,7 @@ mod test {
     #[test_case::test_case("samples/op.pld")]
     #[test_case::test_case("samples/some_import.pld")]
     #[test_case::test_case("samples/test_message.pld")]
+    #[test_case::test_case("samples/versions.pld")]
     fn round_trip(path: &str) {
         crate::tst::logging();
 
@@ -161,4 +272,124 @@ mod test {
 
         assert_eq!(data, fmt, "expected:\n{}\ngot:\n{}", data, fmt);
     }
+
+    #[test_case::test_case(
+        "operation create_user(name: str, email: str, age?: i32, tags: str[]) -> User!;", 120,
+        "operation_fits"; "operation fits"
+    )]
+    #[test_case::test_case(
+        "operation create_user(name: str, email: str, age?: i32, tags: str[]) -> User!;", 48,
+        "operation_args"; "operation args break"
+    )]
+    #[test_case::test_case(
+        "type Value = oneof str | i32 | i64 | f64 | bool | binary | datetime | Record;", 48,
+        "oneof_members"; "oneof members break"
+    )]
+    #[test_case::test_case(
+        "type Account = Identity & Timestamps & Ownership & Permissions;", 40,
+        "intersection"; "intersection operands break"
+    )]
+    #[test_case::test_case(
+        "struct Page { cursor: { next?: str, prev?: str }, items: i32[] };", 120,
+        "anonymous_struct_fits"; "anonymous struct fits"
+    )]
+    #[test_case::test_case(
+        "struct Page { cursor: { next?: str, prev?: str, first: str, last: str } };", 40,
+        "anonymous_struct"; "anonymous struct breaks"
+    )]
+    #[test_case::test_case(
+        "operation search(query: str, page: { size: i32, cursor?: str }) -> oneof Results | Empty;", 56,
+        "nested"; "nested groups break outermost first"
+    )]
+    fn wraps(
+        src: &str,
+        max_width: usize,
+        name: &str,
+    ) {
+        let cfg = FormatConfig {
+            max_width,
+            ..Default::default()
+        };
+        let src = format!("namespace test;\n\n{src}\n");
+        let formatted = AstStream::from_string(&src)
+            .unwrap()
+            .format(&cfg);
+
+        for line in formatted.lines() {
+            let width = line.replace('\t', "    ").len();
+            assert!(width <= max_width, "{line:?} exceeds {max_width}");
+        }
+
+        let again = AstStream::from_string(&formatted)
+            .unwrap()
+            .format(&cfg);
+        assert_eq!(formatted, again, "formatting is not idempotent");
+
+        operation_api_testing::insta_test!(|| {
+            operation_api_testing::assert_yaml_snapshot!(format!("wrap_{name}"), formatted);
+        });
+    }
+
+    fn kinds(ast: &AstStream) -> Vec<&'static str> {
+        ast.nodes
+            .iter()
+            .map(|node| {
+                match &node.value {
+                    items::Items::Namespace(..) => "namespace",
+                    items::Items::Struct(..) => "struct",
+                    items::Items::Enum(..) => "enum",
+                    items::Items::Invalid(..) => "invalid",
+                    _ => "other",
+                }
+            })
+            .collect()
+    }
+
+    #[test_case::test_case(
+        "namespace test;\n\nstruct A { a: i32 };\n",
+        0, vec!["namespace", "struct"]; "no errors"
+    )]
+    #[test_case::test_case(
+        "namespace test;\n\nstruct A { a: };\n\nstruct B { b: i32 };\n\nstruct C { c i32 };\n",
+        2, vec!["namespace", "invalid", "struct", "invalid"]; "errors in struct bodies"
+    )]
+    #[test_case::test_case(
+        "namespace test;\n\nstruct A { a: i32 }\n\nstruct B { b: i32 };\n",
+        1, vec!["namespace", "invalid", "struct"]; "missing terminator"
+    )]
+    #[test_case::test_case(
+        "namespace test;\n\nstrcut A;\n\nenum B { C = 1 };\n",
+        1, vec!["namespace", "invalid", "enum"]; "unknown item"
+    )]
+    #[test_case::test_case(
+        "namespace test;\n\nstruct A { a: i32 $ };\n\nstruct B { b: };\n",
+        2, vec!["namespace", "struct", "invalid"]; "invalid characters"
+    )]
+    fn recovers(
+        src: &str,
+        n_errors: usize,
+        expect: Vec<&'static str>,
+    ) {
+        let (ast, errors) = AstStream::from_string_recovering(src);
+        assert_eq!(errors.len(), n_errors, "{errors:#?}");
+        assert!(
+            errors
+                .iter()
+                .all(|err| matches!(err, LexingError::Spanned { .. }))
+        );
+        assert_eq!(kinds(&ast), expect);
+    }
+
+    #[test]
+    fn recovered_source_is_kept() {
+        let src = "namespace test;\n\nstruct A { a: };\n\nstruct B {\n\tb: i32\n};\n";
+        let (ast, diagnostics) = AstStream::from_string_with_recovering("test.pld", src);
+        assert_eq!(diagnostics.len(), 1);
+
+        let items::Items::Invalid(invalid) = &ast.nodes[1].value else {
+            panic!("expected an invalid placeholder");
+        };
+        assert_eq!(invalid.source, "struct A { a: };");
+        assert_eq!(ast.format(&FormatConfig::default()), src);
+    }
 }
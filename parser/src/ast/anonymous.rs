@@ -8,8 +8,8 @@ pub type StructFields = Spanned<Repeated<super::strct::Arg, Token![,]>>;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct AnonymousStruct {
-    brace: Brace,
-    fields: StructFields,
+    pub brace: Brace,
+    pub fields: StructFields,
 }
 
 impl Parse for AnonymousStruct {
@@ -42,24 +42,29 @@ impl ToTokens for AnonymousStruct {
     ) {
         use crate::tokens::toks::Token as TokType;
 
-        tt.open_block();
-
-        for (i, item) in self.fields.value.values.iter().enumerate() {
-            tt.write(&item.value);
-            if i < self.fields.value.values.len() - 1 {
-                tt.token(&TokType::Comma);
-                tt.add_newline();
-            }
-        }
-
-        tt.close_block();
+        let fields = &self.fields.value.values;
+        tt.group(|tt| {
+            tt.token(&TokType::LBrace);
+            tt.indented(|tt| {
+                tt.soft_line();
+                for (i, item) in fields.iter().enumerate() {
+                    tt.write(&item.value);
+                    if i < fields.len() - 1 {
+                        tt.token(&TokType::Comma);
+                        tt.soft_line();
+                    }
+                }
+            });
+            tt.soft_line();
+            tt.token(&TokType::RBrace);
+        });
     }
 }
 
 #[cfg(test)]
 mod test {
-    #[test_case::test_case("{\n\ta: i32\n}"; "basic one field")]
-    #[test_case::test_case("{\n\ta: i32,\n\tb: i64\n}"; "basic multi field")]
+    #[test_case::test_case("{ a: i32 }"; "basic one field")]
+    #[test_case::test_case("{ a: i32, b: i64 }"; "basic multi field")]
     #[test_case::test_case("{\n\ta: i32,\n\t/* some comment */\n\tb: i64\n}"; "fields with comment")]
     pub fn rt_anon(src: &str) {
         crate::tst::round_trip::<super::AnonymousStruct>(src).unwrap();
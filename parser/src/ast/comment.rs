@@ -91,6 +91,21 @@ impl CommentStream {
             }
         })
     }
+
+    /// the comments joined as a description, trimmed per line
+    pub fn description(&self) -> Option<String> {
+        let desc = self
+            .comments()
+            .flat_map(|comment| comment.lines().map(str::trim))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let desc = desc.trim();
+        if desc.is_empty() {
+            None
+        } else {
+            Some(desc.to_string())
+        }
+    }
 }
 
 impl tokens::ToTokens for CommentStream {
@@ -5,7 +5,7 @@ use crate::{
 
 use crate::{
     SpannedToken,
-    ast::comment::CommentStream,
+    ast::{comment::CommentStream, meta::ItemMeta},
     defs::Spanned,
     tokens::{ImplDiagnostic, Parse, Peek, Repeated, brace},
 };
@@ -45,6 +45,7 @@ where
 
 pub struct EnumVariant<Value: Parse> {
     pub comments: Spanned<CommentStream>,
+    pub meta: Spanned<ItemMeta>,
     pub name: SpannedToken![ident],
     pub value: Option<EnumValue<Value>>,
 }
@@ -53,12 +54,25 @@ impl<Value: Parse + Peek> Peek for EnumVariant<Value> {
     fn is(token: &crate::tokens::toks::Token) -> bool {
         <Token![ident]>::is(token)
     }
+
+    fn peek(stream: &crate::tokens::TokenStream) -> bool {
+        let mut fork = stream.fork();
+        if fork
+            .parse::<Spanned<CommentStream>>()
+            .is_err()
+            || fork.parse::<Spanned<ItemMeta>>().is_err()
+        {
+            return false;
+        }
+        fork.peek::<Token![ident]>()
+    }
 }
 
 impl<Value: Parse + Peek> Parse for EnumVariant<Value> {
     fn parse(stream: &mut crate::tokens::TokenStream) -> Result<Self, crate::tokens::LexingError> {
         Ok(Self {
             comments: stream.parse()?,
+            meta: stream.parse()?,
             name: stream.parse()?,
             value: Option::parse(stream)?,
         })
@@ -80,6 +94,7 @@ where
         tt: &mut crate::fmt::Printer,
     ) {
         tt.write(&self.comments);
+        tt.write(&self.meta);
         tt.write(&self.name);
         if let Some(val) = &self.value {
             tt.space();
@@ -211,6 +226,12 @@ mod tests {
         assert!(matches!(it, Enum::Str(..)))
     };
     "parses enum variant with str value"
+)]
+    #[test_case::test_case(
+    "enum Abc {\n\t#[tag(1)]\n\tBar = \"a\"\n}", |it| {
+        assert!(matches!(it, Enum::Str(..)))
+    };
+    "parses str enum variant with tag"
 )]
     fn test_enum_variant_parse_str(
         input: &str,
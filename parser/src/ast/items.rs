@@ -30,6 +30,40 @@ straight_through! {
     }
 }
 
+/// free-standing `#![...]` attributes which apply to the enclosing namespace
+pub struct InnerMetaDef {
+    pub comments: CommentStream,
+    pub meta: Spanned<ItemMeta>,
+}
+
+impl ToTokens for InnerMetaDef {
+    fn write(
+        &self,
+        tt: &mut crate::fmt::Printer,
+    ) {
+        tt.write(&self.comments);
+        tt.write(&self.meta);
+        if tt.buf.ends_with('\n') {
+            tt.buf.pop();
+        }
+    }
+}
+
+/// source which failed to parse while recovering from syntax errors. the text is kept verbatim so a
+/// partially parsed stream still prints the original content.
+pub struct InvalidDef {
+    pub source: String,
+}
+
+impl ToTokens for InvalidDef {
+    fn write(
+        &self,
+        tt: &mut crate::fmt::Printer,
+    ) {
+        tt.word(self.source.trim_end());
+    }
+}
+
 pub type NamespaceDef = Item<super::namespace::Namespace>;
 pub type SpannedNamespaceDef = Item<super::namespace::SpannedNamespace>;
 pub type UseDef = Item<super::import::Use>;
@@ -50,14 +84,18 @@ pub enum Items {
     Operation(OperationDef),
     Namespace(NamespaceDef),
     SpannedNamespace(SpannedNamespaceDef),
+    Meta(InnerMetaDef),
+    Invalid(InvalidDef),
 }
 
 impl Parse for Items {
     fn parse(stream: &mut crate::tokens::TokenStream) -> Result<Self, crate::tokens::LexingError> {
         let comments = CommentStream::parse(stream)?;
-        let meta = stream.parse()?;
+        let meta: Spanned<ItemMeta> = stream.parse()?;
 
-        Ok(if stream.peek::<ast::namespace::Namespace>() {
+        Ok(if meta.is_inner() {
+            Self::Meta(InnerMetaDef { comments, meta })
+        } else if stream.peek::<ast::namespace::Namespace>() {
             Self::Namespace(NamespaceDef {
                 comments,
                 meta,
@@ -144,7 +182,10 @@ impl Peek for Items {
     fn peek(stream: &crate::tokens::TokenStream) -> bool {
         let mut fork = stream.fork();
         let _ = CommentStream::parse(&mut fork).ok();
-        let _: Spanned<ItemMeta> = bail_unchecked!(fork.parse(); false);
+        let meta: Spanned<ItemMeta> = bail_unchecked!(fork.parse(); false);
+        if meta.is_inner() {
+            return true;
+        }
         if let Some(token) = fork.next() {
             let token = &token.value;
             <Token![namespace]>::is(token)
@@ -177,6 +218,8 @@ impl ToTokens for Items {
             Operation(def) => tt.write(def),
             Namespace(def) => tt.write(def),
             SpannedNamespace(def) => tt.write(def),
+            Meta(def) => tt.write(def),
+            Invalid(def) => tt.write(def),
         }
     }
 }
@@ -6,6 +6,7 @@ use crate::{
     tokens::{self, Bracket, Paren, Parse, Peek, ToTokens, bracket, paren},
 };
 
+#[derive(serde::Deserialize, serde::Serialize)]
 pub struct Meta<Value: Parse> {
     pub open: SpannedToken![#],
     pub inner: Option<Spanned<Token![!]>>,
@@ -15,6 +16,17 @@ pub struct Meta<Value: Parse> {
     pub value: Spanned<Value>,
 }
 
+// `error` lexes as a keyword, but is a valid meta name (`#![error(MyError)]`)
+fn parse_name(
+    stream: &mut tokens::TokenStream
+) -> Result<SpannedToken![ident], tokens::LexingError> {
+    if stream.peek::<Token![error]>() {
+        let kw: SpannedToken![error] = stream.parse()?;
+        return Ok(kw.map(|_| tokens::IdentToken::new("error".into())));
+    }
+    stream.parse()
+}
+
 impl<Value: Parse + Peek> tokens::Peek for Meta<Value> {
     fn peek(stream: &tokens::TokenStream) -> bool {
         let mut stream = stream.fork();
@@ -27,7 +39,7 @@ impl<Value: Parse + Peek> tokens::Peek for Meta<Value> {
 
         let bracket_tok = bracket!(bracket in stream; false);
 
-        let name = bail_unchecked!(bracket.parse(); false);
+        let name = bail_unchecked!(parse_name(&mut bracket); false);
 
         let paren_tok = paren!(paren in bracket; false);
 
@@ -52,7 +64,7 @@ impl<Value: Parse + Peek> tokens::Parse for Meta<Value> {
             open: stream.parse()?,
             inner: Option::parse(stream)?,
             bracket: bracket!(bracket in stream),
-            name: bracket.parse()?,
+            name: parse_name(&mut bracket)?,
             paren: paren!(paren in bracket),
             value: paren.parse()?,
         })
@@ -82,11 +94,19 @@ pub type IntMeta = Meta<Token![number]>;
 pub type StrMeta = Meta<Token![string]>;
 pub type IdentMeta = Meta<PathOrIdent>;
 
+#[derive(serde::Deserialize, serde::Serialize)]
 pub enum ItemMetaItem {
     Version(Spanned<IntMeta>),
     Error(Spanned<IdentMeta>),
+    /// `#[alias(old_name)]`, the name of a field in the previous version of its struct
+    Alias(Spanned<IdentMeta>),
+    /// `#[tag(3)]`, the number of a field in formats which identify fields by number
+    Tag(Spanned<IntMeta>),
+    /// `#[kind(query)]`, whether an operation only reads (`query`) or may also write (`mutation`)
+    Kind(Spanned<IdentMeta>),
 }
 
+#[derive(serde::Deserialize, serde::Serialize)]
 pub struct ItemMeta {
     pub meta: Vec<ItemMetaItem>,
 }
@@ -99,9 +119,10 @@ impl Parse for ItemMeta {
                 let this: Spanned<IntMeta> = stream.parse()?;
                 match this.name.borrow_string().as_ref() {
                     "version" => meta.push(ItemMetaItem::Version(this)),
+                    "tag" => meta.push(ItemMetaItem::Tag(this)),
                     unknown => {
                         return Err(crate::LexingError::unknown_meta(
-                            vec!["version"],
+                            vec!["version", "tag"],
                             unknown.into(),
                             &this.name.span,
                         ));
@@ -118,9 +139,11 @@ impl Parse for ItemMeta {
                 let this: Spanned<IdentMeta> = stream.parse()?;
                 match this.name.borrow_string().as_ref() {
                     "error" => meta.push(ItemMetaItem::Error(this)),
+                    "alias" => meta.push(ItemMetaItem::Alias(this)),
+                    "kind" => meta.push(ItemMetaItem::Kind(this)),
                     unknown => {
                         return Err(crate::LexingError::unknown_meta(
-                            vec!["error"],
+                            vec!["error", "alias", "kind"],
                             unknown.into(),
                             &this.name.span,
                         ));
@@ -135,6 +158,25 @@ impl Parse for ItemMeta {
     }
 }
 
+impl ItemMetaItem {
+    pub fn is_inner(&self) -> bool {
+        match self {
+            Self::Version(m) => m.inner.is_some(),
+            Self::Error(m) => m.inner.is_some(),
+            Self::Alias(m) => m.inner.is_some(),
+            Self::Tag(m) => m.inner.is_some(),
+            Self::Kind(m) => m.inner.is_some(),
+        }
+    }
+}
+
+impl ItemMeta {
+    /// whether this meta is entirely made of `#![...]` attributes
+    pub fn is_inner(&self) -> bool {
+        !self.meta.is_empty() && self.meta.iter().all(ItemMetaItem::is_inner)
+    }
+}
+
 impl ToTokens for ItemMetaItem {
     fn write(
         &self,
@@ -143,6 +185,9 @@ impl ToTokens for ItemMetaItem {
         match self {
             ItemMetaItem::Version(m) => tt.write(m),
             ItemMetaItem::Error(m) => tt.write(m),
+            ItemMetaItem::Alias(m) => tt.write(m),
+            ItemMetaItem::Tag(m) => tt.write(m),
+            ItemMetaItem::Kind(m) => tt.write(m),
         }
     }
 }
@@ -196,7 +241,7 @@ mod test {
     ) {
         let mut tt = tokenize(src).expect("Should parse");
         let meta: Spanned<ItemMeta> = tt.parse().unwrap();
-        let version = match meta.meta.get(0).unwrap() {
+        let version = match meta.meta.first().unwrap() {
             ItemMetaItem::Version(ver) => ver,
             #[allow(unreachable_patterns)]
             _ => panic!("not version"),
@@ -213,7 +258,7 @@ mod test {
         let mut tt = tokenize(src).expect("Should parse");
         let meta: Spanned<ItemMeta> = tt.parse().unwrap();
         assert!(!meta.meta.is_empty(), "meta.meta is empty!");
-        let error = match meta.meta.get(0).unwrap() {
+        let error = match meta.meta.first().unwrap() {
             ItemMetaItem::Error(err) => err,
             #[allow(unreachable_patterns)]
             _ => panic!("not error"),
@@ -225,6 +270,29 @@ mod test {
         assert_eq!(error_name, expect_error);
     }
 
+    #[test]
+    fn test_alias_parse() {
+        let mut tt = tokenize("#[alias(old_name)]").expect("Should parse");
+        let meta: Spanned<ItemMeta> = tt.parse().unwrap();
+        let ItemMetaItem::Alias(alias) = meta.meta.first().unwrap() else {
+            panic!("not alias")
+        };
+        let crate::ast::ty::PathOrIdent::Ident(ident) = &alias.value.value.value else {
+            panic!("not an ident")
+        };
+        assert_eq!(ident.borrow_string(), "old_name");
+    }
+
+    #[test]
+    fn test_tag_parse() {
+        let mut tt = tokenize("#[tag(3)]").expect("Should parse");
+        let meta: Spanned<ItemMeta> = tt.parse().unwrap();
+        let ItemMetaItem::Tag(tag) = meta.meta.first().unwrap() else {
+            panic!("not tag")
+        };
+        assert_eq!(*tag.value.value.borrow_i32(), 3);
+    }
+
     #[test_case::test_case("#[unknown(1)]", vec![
         "unknown meta attribute, 'unknown'. expected one of version",
         "1:3"
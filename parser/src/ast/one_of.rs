@@ -7,8 +7,8 @@ use crate::{
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct AnonymousOneOf {
-    pub(crate) kw: SpannedToken![oneof],
-    pub(crate) variants: Spanned<Repeated<Type, Token![|]>>,
+    pub kw: SpannedToken![oneof],
+    pub variants: Spanned<Repeated<Type, Token![|]>>,
 }
 
 impl ToTokens for AnonymousOneOf {
@@ -16,19 +16,28 @@ impl ToTokens for AnonymousOneOf {
         &self,
         tt: &mut crate::fmt::Printer,
     ) {
-        tt.write(&self.kw);
-        tt.space();
-
-        for (i, item) in self.variants.value.values.iter().enumerate() {
-            if i > 0 {
-                tt.space();
-            }
-            item.value.write(tt);
-            if let Some(sep) = &item.sep {
-                tt.space();
-                sep.write(tt);
-            }
-        }
+        // separators lead each broken line, e.g. `oneof a\n\t| b`
+        tt.group(|tt| {
+            tt.write(&self.kw);
+            tt.space();
+            tt.indented(|tt| {
+                let values = &self.variants.value.values;
+                for (i, item) in values.iter().enumerate() {
+                    if i > 0 {
+                        tt.soft_line();
+                        if let Some(sep) = &values[i - 1].sep {
+                            sep.write(tt);
+                            tt.space();
+                        }
+                    }
+                    item.value.write(tt);
+                }
+                if let Some(sep) = values.last().and_then(|it| it.sep.as_ref()) {
+                    tt.space();
+                    sep.write(tt);
+                }
+            });
+        });
     }
 }
 
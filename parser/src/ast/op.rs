@@ -39,25 +39,33 @@ impl ToTokens for Operation {
         &self,
         tt: &mut crate::fmt::Printer,
     ) {
-        tt.write(&self.kw);
-        tt.space();
-        tt.write(&self.name);
+        tt.group(|tt| {
+            tt.write(&self.kw);
+            tt.space();
+            tt.write(&self.name);
 
-        self.paren.write_with(tt, |tt| {
-            if let Some(args) = &self.args {
-                for (idx, item) in args.value.values.iter().enumerate() {
-                    tt.write(&item.value);
-                    if idx < args.value.values.len() - 1 {
-                        tt.token(&Token::Comma);
-                        tt.space();
+            self.paren.write_with(tt, |tt| {
+                let Some(args) = &self.args else {
+                    return;
+                };
+                let args = &args.value.values;
+                tt.indented(|tt| {
+                    tt.soft_break();
+                    for (idx, item) in args.iter().enumerate() {
+                        tt.write(&item.value);
+                        if idx < args.len() - 1 {
+                            tt.token(&Token::Comma);
+                            tt.soft_line();
+                        }
                     }
-                }
-            }
-        });
+                });
+                tt.soft_break();
+            });
 
-        tt.space();
-        tt.write(&self.ret);
-        tt.space();
-        tt.write(&self.return_type);
+            tt.space();
+            tt.write(&self.ret);
+            tt.space();
+            tt.write(&self.return_type);
+        });
     }
 }
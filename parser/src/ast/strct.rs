@@ -1,6 +1,7 @@
 use crate::{
     ast::{
         comment::{CommentAst, CommentStream},
+        meta::ItemMeta,
         ty::Type,
     },
     tokens::{self, Token},
@@ -64,6 +65,7 @@ impl ImplDiagnostic for Sep {
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Arg {
     pub comments: CommentStream,
+    pub meta: Spanned<ItemMeta>,
     pub name: SpannedToken![ident],
     pub sep: Spanned<Sep>,
     pub typ: Type,
@@ -75,6 +77,7 @@ impl tokens::ToTokens for Arg {
         tt: &mut crate::fmt::Printer,
     ) {
         tt.write(&self.comments);
+        tt.write(&self.meta);
         tt.write(&self.name);
         tt.write(&self.sep);
         tt.space();
@@ -92,6 +95,7 @@ impl Parse for Arg {
     fn parse(stream: &mut crate::tokens::TokenStream) -> Result<Self, crate::tokens::LexingError> {
         Ok(Self {
             comments: CommentStream::parse(stream)?,
+            meta: stream.parse()?,
             name: stream.parse()?,
             sep: stream.parse()?,
             typ: Type::parse(stream)?,
@@ -110,6 +114,9 @@ impl Peek for Arg {
                 break;
             }
         }
+        if fork.parse::<Spanned<ItemMeta>>().is_err() {
+            return false;
+        }
         fork.peek::<Token![ident]>()
     }
 }
@@ -164,7 +171,7 @@ impl tokens::ToTokens for Struct {
 
 #[cfg(test)]
 mod test {
-    use crate::tokens::{ToTokens, tokenize};
+    use crate::tokens::tokenize;
 
     use super::*;
 
@@ -191,17 +198,14 @@ mod test {
             p.write(&field.value.typ);
             assert_eq!(&p.buf, expect_ty);
             assert_eq!(
-                &format!(
-                    "{}",
-                    field
-                        .value
-                        .value
-                        .comments
-                        .comments()
-                        .map(Clone::clone)
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                ),
+                &field
+                    .value
+                    .value
+                    .comments
+                    .comments()
+                    .map(Clone::clone)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
                 expect_comment
             );
 
@@ -16,6 +16,17 @@ macro_rules! builtin {
                 )*
             }
 
+            impl Builtin {
+                /// the keywords of every builtin type
+                pub fn names() -> Vec<&'static str> {
+                    vec![
+                        $(
+                            crate::tokens::toks::[<Kw $t Token>]::fmt(),
+                        )*
+                    ]
+                }
+            }
+
             impl Peek for Builtin {
                 fn is(token: &toks::Token) -> bool {
                     false  $(
@@ -150,6 +161,32 @@ impl ToTokens for PathOrIdent {
     }
 }
 
+/// pins a reference to one version of a declaration, e.g. `KnownError@1`
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Pin {
+    pub at: SpannedToken![@],
+    pub version: SpannedToken![number],
+}
+
+impl Parse for Pin {
+    fn parse(stream: &mut TokenStream) -> Result<Self, LexingError> {
+        Ok(Self {
+            at: stream.parse()?,
+            version: stream.parse()?,
+        })
+    }
+}
+
+impl ToTokens for Pin {
+    fn write(
+        &self,
+        tt: &mut crate::fmt::Printer,
+    ) {
+        self.at.write(tt);
+        self.version.write(tt);
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Type {
@@ -158,6 +195,8 @@ pub enum Type {
     },
     Ident {
         to: PathOrIdent,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pin: Option<Pin>,
     },
     OneOf {
         ty: Spanned<AnonymousOneOf>,
@@ -212,6 +251,10 @@ impl Parse for Type {
             tracing::trace!("parsing ident in type");
             Type::Ident {
                 to: PathOrIdent::parse(stream)?,
+                pin: match stream.peek::<Token![@]>() {
+                    true => Some(Pin::parse(stream)?),
+                    false => None,
+                },
             }
         } else if stream.peek::<AnonymousStruct>() {
             tracing::trace!("parsing struct in type");
@@ -303,7 +346,12 @@ impl ToTokens for Type {
     ) {
         match self {
             Self::Builtin { ty } => ty.write(tt),
-            Self::Ident { to } => to.write(tt),
+            Self::Ident { to, pin } => {
+                to.write(tt);
+                if let Some(pin) = pin {
+                    pin.write(tt);
+                }
+            },
             Self::OneOf { ty } => ty.write(tt),
             Self::Array { ty } => ty.write(tt),
             Self::Struct { ty } => ty.write(tt),
@@ -109,16 +109,26 @@ impl ToTokens for Union {
         &self,
         tt: &mut crate::fmt::Printer,
     ) {
-        for (i, item) in self.types.values.iter().enumerate() {
-            if i > 0 {
-                tt.space();
-            }
-            item.value.write(tt);
-            if let Some(sep) = &item.sep {
-                tt.space();
-                sep.write(tt);
-            }
-        }
+        // operators lead each broken line, e.g. `a\n\t& b`
+        tt.group(|tt| {
+            tt.indented(|tt| {
+                let values = &self.types.values;
+                for (i, item) in values.iter().enumerate() {
+                    if i > 0 {
+                        tt.soft_line();
+                        if let Some(sep) = &values[i - 1].sep {
+                            sep.write(tt);
+                            tt.space();
+                        }
+                    }
+                    item.value.write(tt);
+                }
+                if let Some(sep) = values.last().and_then(|it| it.sep.as_ref()) {
+                    tt.space();
+                    sep.write(tt);
+                }
+            });
+        });
     }
 }
 
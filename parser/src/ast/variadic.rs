@@ -1,6 +1,6 @@
 use crate::{
     SpannedToken, Token,
-    ast::{anonymous::AnonymousStruct, comment::CommentStream, ty::Type},
+    ast::{anonymous::AnonymousStruct, comment::CommentStream, meta::ItemMeta, ty::Type},
     defs::Spanned,
     tokens::{ImplDiagnostic, Paren, Parse, Peek, ToTokens, paren, toks},
 };
@@ -9,12 +9,14 @@ use crate::{
 pub enum Variant {
     Tuple {
         comments: CommentStream,
+        meta: Spanned<ItemMeta>,
         name: SpannedToken![ident],
         paren: Paren,
         inner: Type,
     },
     LocalStruct {
         comments: CommentStream,
+        meta: Spanned<ItemMeta>,
         name: SpannedToken![ident],
         inner: Spanned<AnonymousStruct>,
     },
@@ -29,6 +31,7 @@ impl ImplDiagnostic for Variant {
 impl Parse for Variant {
     fn parse(stream: &mut crate::tokens::TokenStream) -> Result<Self, crate::tokens::LexingError> {
         let comments = CommentStream::parse(stream)?;
+        let meta = stream.parse()?;
         let name = stream.parse()?;
 
         let mut inner;
@@ -36,6 +39,7 @@ impl Parse for Variant {
         Ok(if stream.peek::<toks::LBraceToken>() {
             Self::LocalStruct {
                 comments,
+                meta,
                 name,
                 inner: stream.parse()?,
             }
@@ -44,6 +48,7 @@ impl Parse for Variant {
             let inner = Type::parse(&mut inner)?;
             Self::Tuple {
                 comments,
+                meta,
                 name,
                 paren,
                 inner,
@@ -56,6 +61,14 @@ impl Peek for Variant {
     fn is(token: &toks::Token) -> bool {
         <Token![ident]>::is(token)
     }
+
+    fn peek(stream: &crate::tokens::TokenStream) -> bool {
+        let mut fork = stream.fork();
+        if CommentStream::parse(&mut fork).is_err() || fork.parse::<Spanned<ItemMeta>>().is_err() {
+            return false;
+        }
+        fork.peek::<Token![ident]>()
+    }
 }
 
 impl ToTokens for Variant {
@@ -66,21 +79,25 @@ impl ToTokens for Variant {
         match self {
             Self::LocalStruct {
                 comments,
+                meta,
                 name,
                 inner,
             } => {
                 tt.write(comments);
+                tt.write(meta);
                 tt.write(name);
                 tt.space();
                 tt.write(inner);
             },
             Self::Tuple {
                 comments,
+                meta,
                 name,
                 inner,
                 paren,
             } => {
                 tt.write(comments);
+                tt.write(meta);
                 tt.write(name);
                 paren.write_with(tt, |tt| tt.write(inner))
             },
@@ -143,10 +160,11 @@ pub(crate) use variadic;
 #[cfg(test)]
 mod test {
     #[test_case::test_case("a(i32)"; "type variant")]
-    #[test_case::test_case("b {\n\tdesc: str\n}"; "type anonymous struct")]
-    #[test_case::test_case("/* some comment */\nb {\n\tdesc: str\n}"; "type anonymous struct with comment before")]
+    #[test_case::test_case("b { desc: str }"; "type anonymous struct")]
+    #[test_case::test_case("/* some comment */\nb { desc: str }"; "type anonymous struct with comment before")]
     #[test_case::test_case("b {\n\t// some comment\n\tdesc: str\n}"; "type anonymous struct with sl comment in fields")]
     #[test_case::test_case("b {\n\t/*\n\t\tsome\n\t\tcomment\n\t*/\n\tdesc: str\n}"; "type anonymous struct with ml comment in fields")]
+    #[test_case::test_case("#[tag(2)]\na(i32)"; "type variant with tag")]
     fn round_trip(src: &str) {
         crate::tst::round_trip::<super::Variant>(src).unwrap();
     }
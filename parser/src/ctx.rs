@@ -1,399 +1,773 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use miette::IntoDiagnostic;
+use operation_api_manifests::{
+    NewForConfig,
+    package::{Dependency, PackageManifest},
+};
 
 use crate::{
     SpannedToken,
     ast::{
-        items::{EnumDef, ErrorDef, NamespaceDef, OneOfDef, StructDef, TypeDef, UseDef},
-        meta::ItemMeta,
+        AstStream,
+        items::Items,
+        meta::{ItemMeta, ItemMetaItem},
+        path::Path as PathInner,
+        ty::PathOrIdent,
     },
+    defs::{Span, Spanned},
+    tokens::{IdentToken, PathToken},
 };
 
-// use crate::{
-//     defs::{
-//         EnumSealed, SpannedToken![ident], UseDef, NamespaceSealed, Payload, PayloadTypesSealed, StructSealed,
-//         TypeDefSealed,
-//     },
-//     parser::PayloadParser,
-//     utils::insert_unique_ident_or_err_spanned,
-// };
-
-// pub trait GetCtx {
-//     fn get(
-//         &self,
-//         ident: &Ident,
-//     ) -> Option<PayloadTypesSealed>;
-//     fn get_spanned(
-//         &self,
-//         ident: &Ident>,
-//     ) -> Option<PayloadTypesSealed> {
-//         self.get(&ident.value)
-//     }
-//     fn must_get(
-//         &self,
-//         ident: &Ident>,
-//     ) -> crate::Result<PayloadTypesSealed> {
-//         match self.get_spanned(ident) {
-//             Some(rf) => Ok(rf),
-//             None => {
-//                 Err(crate::Error::resolution_spanned(
-//                     ident.value.clone(),
-//                     ident.span.start,
-//                     ident.span.end,
-//                 ))
-//             },
-//         }
-//     }
-// }
+pub const SCHEMA_DIR: &str = "schema";
+pub const SCHEMA_LIB: &str = "lib.pld";
+pub const SCHEMA_EXT: &str = "pld";
 
-#[derive(Default)]
-pub struct InnerNamespace {
-    pub inner_meta: Option<ItemMeta>,
+/// a parsed schema file, along with what each of its `use` items resolved to
+pub struct SchemaFile {
+    pub path: PathBuf,
+    pub source: Arc<str>,
+    pub ast: AstStream,
+    pub imports: Vec<Import>,
+}
+
+pub struct Import {
+    pub span: Span,
+    pub target: ImportTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportTarget {
+    /// the path only named files, which are now part of the package graph
+    Files,
+    /// `use a::b` where `a.b` is a declared namespace, binding `b`
+    Namespace { alias: String, namespace: String },
+    /// `use a::B` where `B` is declared within namespace `a`
+    Item { namespace: String, name: String },
+}
 
-    pub imports: Vec<UseDef>,
+struct Package {
+    schema: PathBuf,
+    deps: BTreeMap<String, PathBuf>,
+}
 
-    pub one_ofs: BTreeMap<SpannedToken![ident], OneOfDef>,
+struct PendingImport {
+    file: usize,
+    path: SpannedToken![path],
+    rest: Vec<String>,
+    scope: Vec<usize>,
+}
 
-    pub enums: BTreeMap<SpannedToken![ident], EnumDef>,
+struct Declared {
+    file: usize,
+    tag: &'static str,
+    /// the version attribute of each declaration of the name
+    versions: Vec<Option<i32>>,
+}
 
-    pub structs: BTreeMap<SpannedToken![ident], StructDef>,
+/// loads schema files by following their `use` items, starting from a package's `schema/lib.pld`
+/// or from individual files.
+///
+/// `schema::` paths are relative to the package's schema directory, paths starting with the name
+/// of a dependency are relative to that package, and any other path is relative to the importing
+/// file. once a path stops naming files or directories, the remaining segments must name a
+/// namespace or an item within one.
+#[derive(Default)]
+pub struct Resolver {
+    files: Vec<SchemaFile>,
+    loaded: BTreeMap<PathBuf, usize>,
+    stack: Vec<(PathBuf, PathBuf)>,
+    packages: Vec<Package>,
+    roots: BTreeMap<PathBuf, usize>,
+    pending: Vec<PendingImport>,
+}
 
-    pub types: BTreeMap<SpannedToken![ident], TypeDef>,
+impl Resolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// loads `{root}/schema/lib.pld`, and everything it imports
+    pub fn load_package(
+        &mut self,
+        root: impl AsRef<Path>,
+    ) -> miette::Result<()> {
+        let package = self.package(root.as_ref())?;
+        let lib = self.packages[package]
+            .schema
+            .join(SCHEMA_LIB);
+        self.load_file(&lib, package, None)?;
+        Ok(())
+    }
+
+    pub fn load(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> miette::Result<()> {
+        let path = path.as_ref();
+        let package = self.package_for(path)?;
+        self.load_file(path, package, None)?;
+        Ok(())
+    }
+
+    pub fn load_source(
+        &mut self,
+        path: impl AsRef<Path>,
+        source: impl Into<Arc<str>>,
+    ) -> miette::Result<()> {
+        let path = path.as_ref();
+        let package = self.package_for(path)?;
+        self.parse(path, path.to_path_buf(), source.into(), package)?;
+        Ok(())
+    }
+
+    fn package(
+        &mut self,
+        root: &Path,
+    ) -> miette::Result<usize> {
+        let key = root.canonicalize().into_diagnostic()?;
+        if let Some(idx) = self.roots.get(&key) {
+            return Ok(*idx);
+        }
+
+        let mut deps = BTreeMap::new();
+        if root.join("schema.toml").is_file() {
+            let manifest = PackageManifest::new(Some(root.to_string_lossy()))
+                .map_err(|err| miette::miette!("{err}"))?;
+            for (name, dep) in manifest.dependencies {
+                match dep {
+                    Dependency::Path { path } => {
+                        deps.insert(name.replace('-', "_"), root.join(path));
+                    },
+                    Dependency::Git { .. } | Dependency::Remote { .. } => {
+                        return Err(miette::miette!(
+                            "dependency {name} of {} is not a path dependency, which is not supported yet",
+                            root.display()
+                        ));
+                    },
+                }
+            }
+        }
+
+        let idx = self.packages.len();
+        self.packages.push(Package {
+            schema: root.join(SCHEMA_DIR),
+            deps,
+        });
+        self.roots.insert(key, idx);
+        Ok(idx)
+    }
+
+    /// finds the package a file belongs to by looking for the nearest `schema.toml`. files outside
+    /// of a package are treated as their own package, rooted at their directory.
+    fn package_for(
+        &mut self,
+        path: &Path,
+    ) -> miette::Result<usize> {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let canonical = dir.canonicalize().into_diagnostic()?;
+        for ancestor in canonical.ancestors() {
+            if ancestor.join("schema.toml").is_file() {
+                return self.package(ancestor);
+            }
+        }
+
+        if let Some(idx) = self.roots.get(&canonical) {
+            return Ok(*idx);
+        }
+        let idx = self.packages.len();
+        self.packages.push(Package {
+            schema: dir.to_path_buf(),
+            deps: BTreeMap::new(),
+        });
+        self.roots.insert(canonical, idx);
+        Ok(idx)
+    }
+
+    fn load_file(
+        &mut self,
+        path: &Path,
+        package: usize,
+        from: Option<(usize, &Span)>,
+    ) -> miette::Result<usize> {
+        let key = path.canonicalize().into_diagnostic()?;
+
+        if let Some(pos) = self
+            .stack
+            .iter()
+            .position(|(it, _)| it == &key)
+        {
+            let chain = self.stack[pos..]
+                .iter()
+                .map(|(_, display)| display.clone())
+                .chain(std::iter::once(path.to_path_buf()))
+                .collect();
+            let err = crate::Error::ImportCycle { chain };
+            return Err(match from {
+                Some((file, span)) => self.report(file, err.with_span(span.start, span.end)),
+                None => miette::miette!("{err}"),
+            });
+        }
+
+        if let Some(idx) = self.loaded.get(&key) {
+            return Ok(*idx);
+        }
+
+        let source = std::fs::read_to_string(path).into_diagnostic()?;
+        self.parse(path, key, source.into(), package)
+    }
+
+    fn load_dir(
+        &mut self,
+        dir: &Path,
+        package: usize,
+        from: (usize, &Span),
+    ) -> miette::Result<Vec<usize>> {
+        let mut paths = std::fs::read_dir(dir)
+            .into_diagnostic()?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+        paths.retain(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == SCHEMA_EXT)
+        });
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| self.load_file(path, package, Some(from)))
+            .collect()
+    }
+
+    fn parse(
+        &mut self,
+        path: &Path,
+        key: PathBuf,
+        source: Arc<str>,
+        package: usize,
+    ) -> miette::Result<usize> {
+        let ast = AstStream::from_string_with(path, &source)?;
+
+        let mut uses = vec![];
+        collect_uses(&ast, &mut uses);
+
+        let idx = self.files.len();
+        self.files.push(SchemaFile {
+            path: path.to_path_buf(),
+            source,
+            ast,
+            imports: vec![],
+        });
+        self.loaded.insert(key.clone(), idx);
+
+        self.stack.push((key, path.to_path_buf()));
+        let dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        for (local, path) in uses {
+            self.resolve_use(idx, package, &dir, local, path)?;
+        }
+        self.stack.pop();
+
+        Ok(idx)
+    }
+
+    fn resolve_use(
+        &mut self,
+        file: usize,
+        package: usize,
+        dir: &Path,
+        local: bool,
+        path: SpannedToken![path],
+    ) -> miette::Result<()> {
+        let mut rest = path.borrow_path_inner().segments().clone();
+        let from = (file, &path.span);
+
+        let (mut dir, package, mut scope) = if local {
+            (self.packages[package].schema.clone(), package, vec![])
+        } else if let Some(root) = self.packages[package]
+            .deps
+            .get(&rest[0])
+            .cloned()
+        {
+            rest.remove(0);
+            let dep = self.package(&root)?;
+            let schema = self.packages[dep].schema.clone();
+            let lib = self.load_file(&schema.join(SCHEMA_LIB), dep, Some(from))?;
+            (schema, dep, vec![lib])
+        } else {
+            (dir.to_path_buf(), package, vec![])
+        };
+
+        while let Some(segment) = rest.first() {
+            let file = dir.join(format!("{segment}.{SCHEMA_EXT}"));
+            let sub = dir.join(segment);
+            if file.is_file() {
+                scope = vec![self.load_file(&file, package, Some(from))?];
+            } else if sub.is_dir() {
+                scope = self.load_dir(&sub, package, from)?;
+            } else {
+                break;
+            }
+            dir = sub;
+            rest.remove(0);
+        }
+
+        self.pending.push(PendingImport {
+            file,
+            path,
+            rest,
+            scope,
+        });
+        Ok(())
+    }
+
+    /// checks that declarations are unique within each namespace and resolves what each `use`
+    /// item refers to
+    pub fn finish(mut self) -> miette::Result<Vec<SchemaFile>> {
+        let mut namespaces: BTreeMap<String, BTreeMap<String, Declared>> = BTreeMap::new();
+        let mut declares: Vec<Vec<String>> = vec![];
+        for (idx, file) in self.files.iter().enumerate() {
+            let mut decls = vec![];
+            collect_decls(&file.ast, None, &mut decls);
+
+            let mut file_ns = vec![];
+            for (ns, name, tag, version) in decls {
+                if !file_ns.contains(&ns) {
+                    file_ns.push(ns.clone());
+                }
+                let Some((name, tag)) = name.zip(tag) else {
+                    namespaces.entry(ns).or_default();
+                    continue;
+                };
+                let items = namespaces.entry(ns.clone()).or_default();
+                match items.get_mut(name.borrow_string()) {
+                    // versions of a declaration may be held side by side, so long as each is
+                    // explicitly versioned
+                    Some(declared)
+                        if declared.tag == tag
+                            && version.is_some()
+                            && declared
+                                .versions
+                                .iter()
+                                .all(|it| it.is_some() && it != &version) =>
+                    {
+                        declared.versions.push(version);
+                    },
+                    Some(..) => {
+                        let namespace =
+                            Spanned::new(name.span.start, name.span.end, IdentToken::new(ns));
+                        return Err(self.report(idx, crate::Error::conflict(namespace, name, tag)));
+                    },
+                    None => {
+                        items.insert(
+                            name.borrow_string().clone(),
+                            Declared {
+                                file: idx,
+                                tag,
+                                versions: vec![version],
+                            },
+                        );
+                    },
+                }
+            }
+            declares.push(file_ns);
+        }
+
+        for pending in std::mem::take(&mut self.pending) {
+            let target = if pending.rest.is_empty() {
+                Some(ImportTarget::Files)
+            } else if namespaces.contains_key(&pending.rest.join(".")) {
+                Some(ImportTarget::Namespace {
+                    alias: pending
+                        .rest
+                        .last()
+                        .cloned()
+                        .unwrap_or_default(),
+                    namespace: pending.rest.join("."),
+                })
+            } else {
+                let (name, prefix) = pending
+                    .rest
+                    .split_last()
+                    .expect("rest is not empty");
+                let candidates = if prefix.is_empty() {
+                    pending
+                        .scope
+                        .iter()
+                        .flat_map(|file| declares[*file].iter().cloned())
+                        .collect()
+                } else {
+                    vec![prefix.join(".")]
+                };
+                candidates
+                    .into_iter()
+                    .find(|ns| {
+                        namespaces
+                            .get(ns)
+                            .is_some_and(|items| items.contains_key(name))
+                    })
+                    .map(|namespace| {
+                        ImportTarget::Item {
+                            namespace,
+                            name: name.clone(),
+                        }
+                    })
+            };
+
+            let Some(target) = target else {
+                let err = crate::Error::resolution(pending.path);
+                return Err(self.report(pending.file, err));
+            };
+            self.files[pending.file]
+                .imports
+                .push(Import {
+                    span: pending.path.span.clone(),
+                    target,
+                });
+        }
+
+        tracing::trace!(
+            files = self.files.len(),
+            namespaces = namespaces.len(),
+            "resolved package graph"
+        );
+        for (ns, items) in &namespaces {
+            for (name, declared) in items {
+                tracing::trace!(
+                    "{ns}::{name} ({}) declared in {}",
+                    declared.tag,
+                    self.files[declared.file].path.display()
+                );
+            }
+        }
+
+        Ok(self.files)
+    }
+
+    fn report(
+        &self,
+        file: usize,
+        err: crate::Error,
+    ) -> miette::Report {
+        let file = &self.files[file];
+        err.to_report_with(&file.path, &file.source, None)
+    }
+}
 
-    pub errors: BTreeMap<SpannedToken![ident], ErrorDef>,
+/// loads a package and everything it imports
+pub fn load_package(root: impl AsRef<Path>) -> miette::Result<Vec<SchemaFile>> {
+    let mut resolver = Resolver::new();
+    resolver.load_package(root)?;
+    resolver.finish()
 }
 
-pub struct NamespaceCtx {
-    pub source: PathBuf,
+fn collect_uses(
+    ast: &AstStream,
+    uses: &mut Vec<(bool, SpannedToken![path])>,
+) {
+    for node in &ast.nodes {
+        match &node.value {
+            Items::Use(def) => {
+                uses.push(match &def.def.value.namespace {
+                    PathOrIdent::Path(path) => {
+                        (
+                            matches!(path.borrow_path_inner(), PathInner::Local { .. }),
+                            path.clone(),
+                        )
+                    },
+                    PathOrIdent::Ident(ident) => {
+                        (
+                            false,
+                            Spanned::new(
+                                ident.span.start,
+                                ident.span.end,
+                                PathToken::new(PathInner::Ambiguous {
+                                    bits: vec![ident.borrow_string().clone()],
+                                }),
+                            ),
+                        )
+                    },
+                })
+            },
+            Items::SpannedNamespace(def) => collect_uses(&def.def.value.ast.value, uses),
+            _ => {},
+        }
+    }
+}
 
-    pub namespace: NamespaceDef,
+type Decl = (
+    String,
+    Option<SpannedToken![ident]>,
+    Option<&'static str>,
+    Option<i32>,
+);
+
+/// the version attribute of an item, if it declares one
+fn version(meta: &Spanned<ItemMeta>) -> Option<i32> {
+    meta.meta.iter().find_map(|item| {
+        match item {
+            ItemMetaItem::Version(version) => Some(*version.value.value.borrow_i32()),
+            _ => None,
+        }
+    })
+}
 
-    pub inner: InnerNamespace,
+fn collect_decls(
+    ast: &AstStream,
+    scope: Option<&str>,
+    decls: &mut Vec<Decl>,
+) {
+    let mut current = scope.map(String::from);
+    for node in &ast.nodes {
+        let (name, tag, meta) = match &node.value {
+            Items::Namespace(def) => {
+                let name = def.def.value.name.borrow_string().clone();
+                decls.push((name.clone(), None, None, None));
+                current = Some(name);
+                continue;
+            },
+            Items::SpannedNamespace(def) => {
+                let name = def.def.value.name.borrow_string();
+                let ns = match &current {
+                    Some(parent) => format!("{parent}.{name}"),
+                    None => name.clone(),
+                };
+                decls.push((ns.clone(), None, None, None));
+                collect_decls(&def.def.value.ast.value, Some(&ns), decls);
+                continue;
+            },
+            Items::Struct(def) => (&def.def.value.name, "struct", &def.meta),
+            Items::Enum(def) => {
+                (
+                    match &def.def.value {
+                        crate::ast::enm::Enum::Int(e) => &e.name,
+                        crate::ast::enm::Enum::Str(e) => &e.name,
+                    },
+                    "enum",
+                    &def.meta,
+                )
+            },
+            Items::OneOf(def) => (&def.def.value.name, "oneof", &def.meta),
+            Items::Type(def) => (&def.def.value.name, "type", &def.meta),
+            Items::Error(def) => (&def.def.value.name, "error", &def.meta),
+            Items::Operation(def) => (&def.def.value.name, "operation", &def.meta),
+            Items::Use(..) | Items::Meta(..) | Items::Invalid(..) => continue,
+        };
+        // items outside of a namespace are reported when lowering
+        if let Some(ns) = &current {
+            decls.push((ns.clone(), Some(name.clone()), Some(tag), version(meta)));
+        }
+    }
 }
 
-// impl NamespaceCtx {
-//     pub fn new(
-//         source: PathBuf,
-//         ast: crate::ast::AstStream,
-//     ) -> crate::Result<Self> {
-//         let mut inner = InnerNamespace::default();
-//         let mut namespace = None;
-
-//         use crate::ast::items::Items::*;
-//         for node in ast {
-//             match node.value {
-//                 Use(def) => {
-//                     inner.imports.push(def);
-//                 },
-//                 Namespace(ns) => {
-//                     if namespace.is_some() {
-//                         return Err(
-//                             crate::Error::NsConflict.with_span(ns.def.span.start, ns.def.span.end)
-//                         );
-//                     }
-//                     namespace = Some(ns);
-//                 },
-//                 Error(e) => {},
-//                 _ => todo!(),
-//             }
-//         }
-
-//         Ok(Self {
-//             source,
-//             namespace: namespace.ok_or_else(|| crate::Error::NsNotDeclared)?,
-//             inner,
-//         })
-//     }
-// }
-
-// impl NamespaceCtx {
-//     pub fn is_part_of(
-//         &self,
-//         ns: &Ident,
-//     ) -> bool {
-//         Ident::path_equals(&self.namespace.SpannedToken![ident], &ns.qualified_path())
-//     }
-
-//     fn merge<T>(
-//         ns: &NamespaceSealed,
-//         values: &mut HashMap<SpannedToken![ident], T>,
-//         take_from: HashMap<SpannedToken![ident], T>,
-//     ) -> crate::Result<()> {
-//         for (SpannedToken![ident], def) in take_from.into_iter() {
-//             if values.insert(ident.clone(), def).is_some() {
-//                 return Err(crate::Error::conflict(ns.ident.clone(), ident));
-//             }
-//         }
-//         Ok(())
-//     }
-
-//     pub fn join(
-//         &mut self,
-//         other: Self,
-//     ) -> crate::Result<()> {
-//         let mut other = other;
-
-//         Self::merge(&self.namespace, &mut self.types, other.types)?;
-//         Self::merge(&self.namespace, &mut self.structs, other.structs)?;
-//         Self::merge(&self.namespace, &mut self.enums, other.enums)?;
-
-//         self.imports.append(&mut other.imports);
-
-//         // deduplicate imports by path (keep first occurrence span)
-//         let mut seen = HashSet::new();
-//         self.imports.retain(|imp| {
-//             let key = &imp.value.path;
-//             if seen.contains(key) {
-//                 false
-//             } else {
-//                 seen.insert(key.clone());
-//                 true
-//             }
-//         });
-
-//         Ok(())
-//     }
-// }
-
-// impl GetCtx for NamespaceCtx {
-//     fn get(
-//         &self,
-//         ident: &Ident,
-//     ) -> Option<PayloadTypesSealed> {
-//         if let Some(ty) = self.types.get(ident) {
-//             return Some(PayloadTypesSealed::Type(ty.clone()));
-//         }
-
-//         if let Some(msg) = self.structs.get(ident) {
-//             return Some(PayloadTypesSealed::Struct(msg.clone()));
-//         }
-
-//         if let Some(enm) = self.enums.get(ident) {
-//             return Some(PayloadTypesSealed::Enum(enm.clone()));
-//         }
-//         None
-//     }
-// }
-
-// impl NamespaceCtx {
-//     fn resolve_imports<P: Parse>(
-//         &self,
-//         ctx: &P,
-//     ) -> miette::Result<()> {
-//         for import in &self.imports {
-//             ctx.parse_file(
-//                 self.source
-//                     .parent()
-//                     .expect("non root dir")
-//                     .join(&import.path),
-//             )?;
-//         }
-//         Ok(())
-//     }
-// }
-// impl TryFrom<Payload> for NamespaceCtx {
-//     type Error = crate::Error;
-//     fn try_from(value: Payload) -> crate::Result<Self> {
-//         let namespace = Self::builder();
-
-//         let mut types: HashMap<SpannedToken![ident], TypeDefSealed>> = HashMap::new();
-//         let mut structs: HashMap<SpannedToken![ident], StructSealed>> = HashMap::new();
-//         let mut enums: HashMap<SpannedToken![ident], EnumSealed>> = HashMap::new();
-
-//         let mut imports = Vec::new();
-
-//         let ns_spanned = match value
-//             .defs
-//             .iter()
-//             .find(|ns| matches!(ns, PayloadTypesSealed::Namespace(..)))
-//             .cloned()
-//         {
-//             Some(PayloadTypesSealed::Namespace(ns)) => ns,
-//             _ => return Err(crate::Error::NsNotDeclared),
-//         };
-//         let this_ident_path = ns_spanned.value.ident.clone();
-
-//         for v in value.defs {
-//             match v {
-//                 PayloadTypesSealed::Type(ty) => {
-//                     let (start, end, ident) = (ty.span.start, ty.span.end, ty.value.ident.clone());
-//                     let clone_for_insert = ty.clone();
-//                     insert_unique_ident_or_err_spanned(
-//                         this_ident_path.clone(),
-//                         &mut types,
-//                         ident,
-//                         clone_for_insert,
-//                         start,
-//                         end,
-//                     )?
-//                 },
-//                 PayloadTypesSealed::Struct(msg) => {
-//                     let (start, end, ident) =
-//                         (msg.span.start, msg.span.end, msg.value.ident.clone());
-//                     let clone_for_insert = msg.clone();
-//                     insert_unique_ident_or_err_spanned(
-//                         this_ident_path.clone(),
-//                         &mut structs,
-//                         ident,
-//                         clone_for_insert,
-//                         start,
-//                         end,
-//                     )?
-//                 },
-//                 PayloadTypesSealed::Enum(enm) => {
-//                     let clone_for_insert = enm.clone();
-//                     insert_unique_ident_or_err_spanned(
-//                         this_ident_path.clone(),
-//                         &mut enums,
-//                         enm.value.ident.clone(),
-//                         clone_for_insert,
-//                         enm.span.start,
-//                         enm.span.end,
-//                     )?;
-//                 },
-//                 PayloadTypesSealed::Use(import) => imports.push(import),
-//                 PayloadTypesSealed::Namespace(..) => {},
-//             }
-//         }
-
-//         Ok(namespace
-//             .types(types)
-//             .imports(imports)
-//             .structs(structs)
-//             .enums(enums)
-//             .namespace(ns_spanned)
-//             .source(value.source)
-//             .build())
-//     }
-// }
-
-// #[derive(bon::Builder, Default)]
-// pub struct Ctx {
-//     sources: RwLock<Vec<NamespaceCtx>>,
-// }
-
-// pub trait Parse {
-//     fn parse_file<P: AsRef<Path>>(
-//         &self,
-//         f: P,
-//     ) -> miette::Result<()>;
-
-//     fn parse_data<P: Into<PathBuf>>(
-//         &self,
-//         source: P,
-//         s: &str,
-//     ) -> miette::Result<()>;
-// }
-
-// impl Parse for Ctx {
-//     fn parse_file<P: AsRef<Path>>(
-//         &self,
-//         f: P,
-//     ) -> miette::Result<()> {
-//         let data = std::fs::read_to_string(f.as_ref())
-//             .into_diagnostic()
-//             .wrap_err("failed to read source file")?;
-//         self.parse_data(f.as_ref(), &data)?;
-//         Ok(())
-//     }
-
-//     fn parse_data<P: Into<PathBuf>>(
-//         &self,
-//         source: P,
-//         s: &str,
-//     ) -> miette::Result<()> {
-//         let source = source.into();
-//         if self
-//             .sources
-//             .read()
-//             .unwrap()
-//             .iter()
-//             .any(|it| it.source == source)
-//         {
-//             return Ok(());
-//         }
-
-//         self.sources.write().unwrap().push(
-//             PayloadParser::parse_data(source, s)?
-//                 .try_into()
-//                 .into_diagnostic()
-//                 .wrap_err("failed to parse payload")?,
-//         );
-
-//         Ok(())
-//     }
-// }
-
-// impl Ctx {
-//     pub fn new() -> Self {
-//         Self::default()
-//     }
-
-//     pub fn namespaces(&self) -> RwLockReadGuard<'_, Vec<NamespaceCtx>> {
-//         self.sources.read().unwrap()
-//     }
-
-//     pub fn resolve_imports(&self) -> miette::Result<()> {
-//         let sources = self.sources.read().unwrap().clone();
-
-//         sources
-//             .iter()
-//             .try_for_each(|ns| ns.resolve_imports(self))?;
-//         Ok(())
-//     }
-// }
-
-// impl GetCtx for Ctx {
-//     fn get(
-//         &self,
-//         ident: &Ident,
-//     ) -> Option<PayloadTypesSealed> {
-//         let obj = ident.object();
-//         let find_ns = ident.namespace();
-
-//         let sources = self.sources.read().unwrap();
-//         for ns in sources.iter() {
-//             tracing::debug!("checking namespace: {:#?}", ns.namespace.ident);
-
-//             if !Ident::path_equals(&ns.namespace.SpannedToken![ident], &find_ns) {
-//                 continue;
-//             }
-//             if let Some(ty) = ns.types.get(&obj) {
-//                 return Some(PayloadTypesSealed::Type(ty.clone()));
-//             } else if let Some(msg) = ns.structs.get(&obj) {
-//                 return Some(PayloadTypesSealed::Struct(msg.clone()));
-//             } else if let Some(enm) = ns.enums.get(&obj) {
-//                 return Some(PayloadTypesSealed::Enum(enm.clone()));
-//             }
-//         }
-//         None
-//     }
-// }
-
-// pub fn parse_files<I: IntoIterator<Item = P>, P: AsRef<Path>>(files: I) -> miette::Result<Ctx> {
-//     let ctx = Ctx::new();
-//     for f in files {
-//         ctx.parse_file(f)?;
-//     }
-//     ctx.resolve_imports()?;
-//     Ok(ctx)
-// }
-
-// #[cfg(test)]
-// mod test {
-//     use crate::tst::logging;
-
-//     use super::*;
-
-//     #[test]
-//     fn test_parse() {
-//         logging();
-
-//         let ctx = parse_files(vec!["samples/test_message.pld"]).unwrap();
-
-//         let msg = ctx
-//             .get(&"test::test_message".into())
-//             .expect("should find TestMessage");
-
-//         assert!(matches!(msg, PayloadTypesSealed::Struct(..)));
-//     }
-// }
+#[cfg(test)]
+mod test {
+    use super::{ImportTarget, Resolver};
+
+    fn targets(files: &[super::SchemaFile]) -> Vec<(String, ImportTarget)> {
+        files
+            .iter()
+            .flat_map(|file| {
+                file.imports.iter().map(|import| {
+                    (
+                        file.path
+                            .file_name()
+                            .unwrap()
+                            .to_string_lossy()
+                            .to_string(),
+                        import.target.clone(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_load_package() {
+        let files = super::load_package("samples/abc-corp").unwrap();
+        let mut names = files
+            .iter()
+            .map(|file| {
+                file.path
+                    .strip_prefix("samples")
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "abc-corp/../bar-corp/schema/lib.pld",
+                "abc-corp/schema/errors.pld",
+                "abc-corp/schema/foo/array.pld",
+                "abc-corp/schema/foo/enums.pld",
+                "abc-corp/schema/foo/explicit_oneof.pld",
+                "abc-corp/schema/foo/message_with_enum.pld",
+                "abc-corp/schema/foo/meta.pld",
+                "abc-corp/schema/foo/ops.pld",
+                "abc-corp/schema/foo/test_message.pld",
+                "abc-corp/schema/lib.pld",
+            ]
+        );
+
+        let targets = targets(&files);
+        assert!(targets.contains(&("lib.pld".into(), ImportTarget::Files)));
+        assert!(targets.contains(&(
+            "test_message.pld".into(),
+            ImportTarget::Namespace {
+                alias: "baz".into(),
+                namespace: "baz".into()
+            }
+        )));
+        assert!(targets.contains(&(
+            "test_message.pld".into(),
+            ImportTarget::Item {
+                namespace: "baz".into(),
+                name: "BazOrString".into()
+            }
+        )));
+    }
+
+    fn resolve(files: &[(&str, &str)]) -> miette::Result<Vec<super::SchemaFile>> {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, source) in files {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+        let mut resolver = Resolver::new();
+        resolver.load(dir.path().join(files[0].0))?;
+        resolver.finish()
+    }
+
+    #[test]
+    fn test_resolve_local_item() {
+        let files = resolve(&[
+            ("lib.pld", "namespace a;\nuse b;\nuse schema::errors::E;"),
+            ("b.pld", "namespace b;\nstruct B { a: i32 };"),
+            ("errors.pld", "namespace errors;\nerror E { Code(i32) };"),
+        ])
+        .unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            targets(&files),
+            vec![
+                ("lib.pld".into(), ImportTarget::Files),
+                (
+                    "lib.pld".into(),
+                    ImportTarget::Item {
+                        namespace: "errors".into(),
+                        name: "E".into()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_versions() {
+        let files = resolve(&[
+            ("lib.pld", "namespace a;\nuse b;\nuse c;"),
+            (
+                "b.pld",
+                "namespace shared;\n#[version(1)]\nstruct B { a: i32 };",
+            ),
+            (
+                "c.pld",
+                "namespace shared;\n#[version(2)]\nstruct B { a: i64 };",
+            ),
+        ])
+        .unwrap();
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn test_import_cycle_chain() {
+        let err = match resolve(&[
+            ("lib.pld", "namespace a;\nuse b;"),
+            ("b.pld", "namespace b;\nuse c;"),
+            ("c.pld", "namespace c;\nuse d;"),
+            ("d.pld", "namespace d;\nuse b;"),
+        ]) {
+            Ok(..) => panic!("expected an error"),
+            Err(err) => err.to_string(),
+        };
+        let chain = err
+            .trim_start_matches("import cycle detected: ")
+            .split(" -> ")
+            .map(|it| {
+                std::path::Path::new(it)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(chain, vec!["b.pld", "c.pld", "d.pld", "b.pld"]);
+    }
+
+    #[test_case::test_case(
+        &[("lib.pld", "namespace a;\nuse missing;")],
+        "could not resolve 'missing'"; "missing file"
+    )]
+    #[test_case::test_case(
+        &[("lib.pld", "namespace a;\nuse b::C;"), ("b.pld", "namespace b;\nstruct B { a: i32 };")],
+        "could not resolve 'b::C'"; "missing item"
+    )]
+    #[test_case::test_case(
+        &[
+            ("lib.pld", "namespace a;\nuse b;\nuse c;"),
+            ("b.pld", "namespace shared;\nstruct B { a: i32 };"),
+            ("c.pld", "namespace shared;\nstruct B { a: i64 };"),
+        ],
+        "shared has conflicts. struct B is declared multiple times."; "conflict across files"
+    )]
+    #[test_case::test_case(
+        &[
+            ("lib.pld", "namespace a;\nuse b;\nuse c;"),
+            ("b.pld", "namespace shared;\n#[version(1)]\nstruct B { a: i32 };"),
+            ("c.pld", "namespace shared;\n#[version(1)]\nstruct B { a: i64 };"),
+        ],
+        "shared has conflicts. struct B is declared multiple times."; "version conflict across files"
+    )]
+    #[test_case::test_case(
+        &[
+            ("lib.pld", "namespace a;\nuse b;\nuse c;"),
+            ("b.pld", "namespace shared;\nstruct B { a: i32 };"),
+            ("c.pld", "namespace shared;\n#[version(2)]\nstruct B { a: i64 };"),
+        ],
+        "shared has conflicts. struct B is declared multiple times."; "unversioned conflict"
+    )]
+    #[test_case::test_case(
+        &[
+            ("lib.pld", "namespace a;\nuse b;"),
+            ("b.pld", "namespace b;\nuse c;"),
+            ("c.pld", "namespace c;\nuse b;"),
+        ],
+        "import cycle detected"; "import cycle"
+    )]
+    fn test_resolve_errors(
+        files: &[(&str, &str)],
+        expect: &str,
+    ) {
+        let err = match resolve(files) {
+            Ok(..) => panic!("expected an error"),
+            Err(err) => format!("{err:?}"),
+        };
+        assert!(err.contains(expect), "{err}");
+    }
+}
@@ -1,27 +1,47 @@
-use crate::defs::Spanned;
+use crate::{defs::Spanned, tokens::LexingError};
 use miette::{Diagnostic, NamedSource, SourceSpan};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
-#[derive(Debug, Error, Diagnostic)]
+#[derive(Debug, Error)]
 #[error("{message}")]
 pub struct SpanDiagnostic {
-    #[source_code]
     src: NamedSource<String>,
 
-    #[label]
     span: Option<SourceSpan>,
 
     message: String,
     #[allow(dead_code)]
     label: String,
-    #[diagnostic(help)]
     help: Option<String>,
 
-    #[diagnostic(level)]
     level: miette::Severity,
 }
 
+// implemented by hand as the derive has no way to take the severity from a field
+impl Diagnostic for SpanDiagnostic {
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.src)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let span = self.span?;
+        Some(Box::new(std::iter::once(
+            miette::LabeledSpan::new_with_span(None, span),
+        )))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn std::fmt::Display>)
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(self.level)
+    }
+}
+
 impl SpanDiagnostic {
     pub fn new<T>(
         sp: &Spanned<T>,
@@ -63,6 +83,26 @@ impl SpanDiagnostic {
         }
     }
 
+    pub fn from_lexing(
+        err: &LexingError,
+        path: &Path,
+        source: &str,
+    ) -> Self {
+        match err {
+            LexingError::Spanned { span, .. } => {
+                SpanDiagnostic::new(
+                    &Spanned::new(span.start, span.end, ()),
+                    path,
+                    source,
+                    err.to_string(),
+                    "here",
+                    None,
+                )
+            },
+            err => Self::no_span(path, source, err.to_string(), None),
+        }
+    }
+
     pub fn with_level(
         &mut self,
         level: miette::Severity,
@@ -71,6 +111,39 @@ impl SpanDiagnostic {
     }
 }
 
+/// all diagnostics reported for a single file, rendered together as one report
+#[derive(Debug, Error, Diagnostic)]
+#[error("{} error(s) found in {}", related.len(), path.display())]
+pub struct SpanDiagnostics {
+    path: PathBuf,
+    #[related]
+    related: Vec<SpanDiagnostic>,
+}
+
+impl SpanDiagnostics {
+    pub fn new(
+        path: &Path,
+        related: Vec<SpanDiagnostic>,
+    ) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            related,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.related.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.related.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpanDiagnostic> {
+        self.related.iter()
+    }
+}
+
 pub trait SpannedExt<T> {
     fn error(
         &self,
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use crate::{
+    ast::AstStream,
+    defs::Span,
+    fmt::{
+        FormatConfig,
+        printer::print_ast,
+        safety::{self, Safety},
+    },
+    tokens::AstResult,
+};
+
+/// a replacement of the source text within `span`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn new(
+        span: Span,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+}
+
+/// the outcome of applying edits to a single source
+#[derive(Debug)]
+pub struct Fixed {
+    /// the edited source, formatted
+    pub source: String,
+    pub applied: usize,
+    /// edits left out as they overlapped an earlier edit
+    pub skipped: usize,
+    /// what formatting changed beyond the edits, reported against the edited source
+    pub safety: Safety,
+}
+
+/// applies every edit which does not overlap an earlier one, then formats the result.
+///
+/// fails if the edited source no longer parses, in which case none of the edits should be kept.
+/// formatting is verified as for `fmt`, so that an unsafe edit may be refused.
+pub fn apply(
+    path: &Path,
+    source: &str,
+    mut edits: Vec<Edit>,
+    config: &FormatConfig,
+) -> AstResult<Fixed> {
+    edits.sort();
+    edits.dedup();
+
+    let mut accepted: Vec<Edit> = vec![];
+    let mut skipped = 0;
+    for edit in edits {
+        match accepted.last() {
+            Some(last) if last.span.end > edit.span.start => skipped += 1,
+            _ => accepted.push(edit),
+        }
+    }
+
+    let mut edited = source.to_string();
+    for edit in accepted.iter().rev() {
+        edited.replace_range(edit.span.start..edit.span.end, &edit.replacement);
+    }
+
+    let ast = AstStream::from_string(&edited)?;
+    let formatted = print_ast(&ast, config);
+    Ok(Fixed {
+        safety: safety::verify(path, &edited, &formatted),
+        source: formatted,
+        applied: accepted.len(),
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{Edit, apply};
+    use crate::{defs::Span, fmt::FormatConfig};
+
+    const SOURCE: &str = "namespace test;\n\nstruct some_struct {\n\tfooBar: i32\n};\n";
+
+    #[test_case::test_case(
+        vec![Edit::new(Span::new(24, 35), "SomeStruct"), Edit::new(Span::new(39, 45), "foo_bar")],
+        "namespace test;\n\nstruct SomeStruct {\n\tfoo_bar: i32\n};\n", 2, 0;
+        "renames"
+    )]
+    #[test_case::test_case(
+        vec![Edit::new(Span::new(39, 45), "foo_bar"), Edit::new(Span::new(39, 45), "foo_bar")],
+        "namespace test;\n\nstruct some_struct {\n\tfoo_bar: i32\n};\n", 1, 0;
+        "duplicates"
+    )]
+    #[test_case::test_case(
+        vec![Edit::new(Span::new(39, 45), "foo_bar"), Edit::new(Span::new(42, 45), "x")],
+        "namespace test;\n\nstruct some_struct {\n\tfoo_bar: i32\n};\n", 1, 1;
+        "overlapping"
+    )]
+    fn test_apply(
+        edits: Vec<Edit>,
+        expect: &str,
+        applied: usize,
+        skipped: usize,
+    ) {
+        let fixed = apply(
+            Path::new("test.pld"),
+            SOURCE,
+            edits,
+            &FormatConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(fixed.source, expect);
+        assert!(fixed.safety.unsafe_edit.is_none());
+        assert_eq!(fixed.applied, applied);
+        assert_eq!(fixed.skipped, skipped);
+    }
+
+    #[test]
+    fn test_apply_invalid() {
+        let edits = vec![Edit::new(Span::new(39, 45), "foo bar")];
+        assert!(
+            apply(
+                Path::new("test.pld"),
+                SOURCE,
+                edits,
+                &FormatConfig::default()
+            )
+            .is_err()
+        );
+    }
+}
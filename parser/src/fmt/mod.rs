@@ -2,9 +2,9 @@ use miette::IntoDiagnostic;
 use operation_api_manifests::NewForConfig;
 use std::sync::Arc;
 
-use crate::tokens::tokenize_with;
-
+pub mod fix;
 pub mod printer;
+pub mod safety;
 pub use printer::*;
 
 fn default_width() -> usize {
@@ -47,41 +47,215 @@ impl Default for FormatConfig {
     }
 }
 
-async fn format_file(
+#[derive(bon::Builder, Clone, Debug)]
+pub struct FmtOptions {
+    /// no edits are written to files
+    #[builder(default)]
+    pub dry: bool,
+    /// no edits are written, a diff is printed for every file which would change
+    #[builder(default)]
+    pub check: bool,
+    /// skip edits which would change more than whitespace
+    #[builder(default = true)]
+    pub safe: bool,
+    /// fail the run when warnings are reported
+    #[builder(default)]
+    pub warn_is_fail: bool,
+}
+
+/// the outcome of formatting a single file
+#[derive(Debug, Default)]
+pub struct Formatted {
+    /// a unified diff of the pending changes, in check mode
+    pub diff: Option<String>,
+    pub reports: Vec<miette::Report>,
+}
+
+pub async fn format_file(
     config: &FormatConfig,
     target: impl AsRef<std::path::Path>,
-    dry: bool,
-) -> miette::Result<Vec<miette::Report>> {
-    let data = tokio::fs::read_to_string(&target)
+    opts: &FmtOptions,
+) -> miette::Result<Formatted> {
+    let path = target.as_ref();
+    let data = tokio::fs::read_to_string(path)
         .await
         .into_diagnostic()?;
 
-    let mut tokens = tokenize_with(&target, &data)?;
-    let ast = crate::ast::AstStream::from_tokens_with(&target, &mut tokens)?;
+    let (ast, diagnostics) = crate::ast::AstStream::from_string_with_recovering(path, &data);
+    if !diagnostics.is_empty() {
+        return Ok(Formatted {
+            diff: None,
+            reports: vec![miette::Report::new(diagnostics)],
+        });
+    }
     let formatted = crate::fmt::printer::print_ast(&ast, config);
+    if data == formatted {
+        return Ok(Formatted::default());
+    }
+
+    let safety = safety::verify(path, &data, &formatted);
+    let mut out = Formatted {
+        diff: None,
+        reports: safety
+            .moved
+            .into_iter()
+            .map(miette::Report::new)
+            .collect(),
+    };
+    if let Some(unsafe_edit) = safety.unsafe_edit {
+        out.reports
+            .push(miette::Report::new(unsafe_edit));
+        // a check still fails on the file, as it is not formatted either way
+        if opts.safe && !opts.check {
+            return Ok(out);
+        }
+    }
 
-    if !dry && data != formatted {
-        tokio::fs::write(&target, formatted)
+    if opts.check {
+        let name = path.display().to_string();
+        out.diff = Some(
+            similar::TextDiff::from_lines(&data, &formatted)
+                .unified_diff()
+                .header(&name, &name)
+                .to_string(),
+        );
+        out.reports
+            .push(miette::miette!("{name} is not formatted"));
+    } else if !opts.dry {
+        tokio::fs::write(path, formatted)
             .await
             .into_diagnostic()?;
     }
 
-    Ok(Vec::new())
+    Ok(out)
 }
 
 pub async fn fmt<S: AsRef<str>>(
     config_dir: Option<S>,
     targets: Vec<impl AsRef<std::path::Path> + Send + Sync>,
-    dry: bool,
+    opts: FmtOptions,
 ) -> miette::Result<()> {
     let config = Arc::new(FormatConfig::new(config_dir).into_diagnostic()?);
+    let opts = Arc::new(opts);
     let mut futs = vec![];
     for t in targets {
         let config = config.clone();
-        futs.push(Box::pin(async move { format_file(&config, t, dry).await }));
+        let opts = opts.clone();
+        futs.push(Box::pin(
+            async move { format_file(&config, t, &opts).await },
+        ));
     }
+    // every file is reported before failing, so that one file failing does not hide the others
+    let mut failed = false;
     for f in futures_util::future::join_all(futs).await {
-        f?;
+        let formatted = match f {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                failed = true;
+                eprintln!("{err:?}");
+                continue;
+            },
+        };
+        if let Some(diff) = formatted.diff {
+            print!("{diff}");
+        }
+        for report in formatted.reports {
+            let warning = matches!(
+                report.severity(),
+                Some(miette::Severity::Warning | miette::Severity::Advice)
+            );
+            failed |= !warning || opts.warn_is_fail;
+            eprintln!("{report:?}");
+        }
+    }
+    if failed {
+        return Err(crate::Error::LintFailure).into_diagnostic();
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::{FmtOptions, FormatConfig, fmt, format_file};
+
+    const UNFORMATTED: &str = "namespace test;\nstruct A {\n\ta: i32, // first\n\tb: i32\n};\n";
+    const FORMATTED: &str = "namespace test;\n\nstruct A {\n\ta: i32,\n\t// first\n\tb: i32\n};\n";
+    const UNSAFE: &str = "namespace test;\nstruct A { a: i32 /* dropped */ };\n";
+
+    #[test_case::test_case(FmtOptions::builder().check(true).build(), true, 2, UNFORMATTED; "check")]
+    #[test_case::test_case(FmtOptions::builder().dry(true).build(), false, 1, UNFORMATTED; "dry")]
+    #[test_case::test_case(FmtOptions::builder().build(), false, 1, FORMATTED; "write")]
+    #[tokio::test]
+    async fn test_format_file(
+        opts: FmtOptions,
+        diff: bool,
+        reports: usize,
+        expect: &str,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.pld");
+        std::fs::write(&path, UNFORMATTED).unwrap();
+
+        let formatted = format_file(&FormatConfig::default(), &path, &opts)
+            .await
+            .unwrap();
+        assert_eq!(formatted.diff.is_some(), diff);
+        assert_eq!(formatted.reports.len(), reports);
+        assert_eq!(
+            formatted.reports[0].severity(),
+            Some(miette::Severity::Warning)
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expect);
+
+        if let Some(diff) = formatted.diff {
+            assert!(diff.contains("-\ta: i32, // first\n+\ta: i32,\n+\t// first\n"));
+        }
+
+        let again = format_file(&FormatConfig::default(), &path, &opts)
+            .await
+            .unwrap();
+        assert_eq!(again.reports.is_empty(), expect == FORMATTED);
+    }
+
+    #[tokio::test]
+    async fn test_check_unsafe() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.pld");
+        std::fs::write(&path, UNSAFE).unwrap();
+
+        let opts = FmtOptions::builder().check(true).build();
+        let formatted = format_file(&FormatConfig::default(), &path, &opts)
+            .await
+            .unwrap();
+        assert!(formatted.diff.is_some());
+        let severities: Vec<_> = formatted
+            .reports
+            .iter()
+            .map(|it| it.severity())
+            .collect();
+        assert_eq!(severities, vec![Some(miette::Severity::Warning), None]);
+        assert_eq!(
+            formatted.reports[0].to_string(),
+            "formatting would change the tokens of this file"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), UNSAFE);
+    }
+
+    #[tokio::test]
+    async fn test_fmt_failing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.pld");
+        let path = dir.path().join("a.pld");
+        std::fs::write(&path, UNFORMATTED).unwrap();
+
+        let result = fmt(
+            None::<&str>,
+            vec![missing, path.clone()],
+            FmtOptions::builder().build(),
+        )
+        .await;
+        // the missing file is reported along with the others rather than ending the run
+        assert_eq!(result.unwrap_err().to_string(), "linting errors");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), FORMATTED);
+    }
+}
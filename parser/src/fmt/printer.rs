@@ -10,6 +10,8 @@ pub struct Printer {
     pub brace_depth: usize,
     pub bracket_depth: usize,
     pub cfg: FormatConfig,
+    /// whether the enclosing group is laid out on a single line
+    flat: bool,
 }
 
 impl Printer {
@@ -21,6 +23,7 @@ impl Printer {
             brace_depth: 0,
             bracket_depth: 0,
             cfg: cfg.clone(),
+            flat: false,
         }
     }
 
@@ -72,6 +75,70 @@ impl Printer {
         self.token(&Token::RBrace);
     }
 
+    /// lays out `f` on a single line if it fits within `max_width`, otherwise with its soft breaks
+    /// broken onto new lines. nested groups are only broken once their parent group is.
+    pub fn group<F: Fn(&mut Self)>(
+        &mut self,
+        f: F,
+    ) {
+        if self.flat {
+            return f(self);
+        }
+
+        let start = self.buf.len();
+        self.flat = true;
+        f(self);
+        self.flat = false;
+
+        if self.buf[start..].contains('\n') || self.column() > self.cfg.max_width {
+            self.buf.truncate(start);
+            f(self);
+        }
+    }
+
+    /// a space within a flat group, or a new line when the group is broken
+    pub fn soft_line(&mut self) {
+        if self.flat {
+            self.space();
+        } else {
+            self.add_newline();
+        }
+    }
+
+    /// nothing within a flat group, or a new line when the group is broken
+    pub fn soft_break(&mut self) {
+        if !self.flat {
+            self.add_newline();
+        }
+    }
+
+    /// writes `f` one indentation level deeper, applying to any lines it breaks
+    pub fn indented<F: FnOnce(&mut Self)>(
+        &mut self,
+        f: F,
+    ) {
+        self.indent_level += 1;
+        f(self);
+        self.indent_level -= 1;
+    }
+
+    /// the display width of the current line
+    pub fn column(&self) -> usize {
+        let line = match self.buf.rfind('\n') {
+            Some(idx) => &self.buf[idx + 1..],
+            None => &self.buf,
+        };
+        line.chars()
+            .map(|ch| {
+                if ch == '\t' {
+                    self.cfg.indent_width
+                } else {
+                    1
+                }
+            })
+            .sum()
+    }
+
     pub fn write<W: ToTokens + ?Sized>(
         &mut self,
         w: &W,
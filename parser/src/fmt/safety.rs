@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use crate::{
+    defs::{Span, Spanned},
+    diagnostics::SpanDiagnostic,
+    tokens::{TokenStream, toks::Token},
+};
+
+/// differences between the significant tokens of a file and its formatted output
+#[derive(Debug, Default)]
+pub struct Safety {
+    /// set when formatting would add, drop or reorder tokens
+    pub unsafe_edit: Option<SpanDiagnostic>,
+    /// comments which were moved off of the line they trailed
+    pub moved: Vec<SpanDiagnostic>,
+}
+
+struct Significant {
+    token: Token,
+    span: Span,
+    /// a comment following code on the same line
+    trailing: bool,
+}
+
+fn significant(tt: &TokenStream) -> Vec<Significant> {
+    let mut out = vec![];
+    let mut code_on_line = false;
+    for tok in tt.all() {
+        match &tok.value {
+            Token::Newline => code_on_line = false,
+            Token::Space | Token::Tab => {},
+            token @ (Token::CommentSingleLine(..) | Token::CommentMultiLine(..)) => {
+                out.push(Significant {
+                    token: token.clone(),
+                    span: tok.span.clone(),
+                    trailing: code_on_line,
+                })
+            },
+            token => {
+                code_on_line = true;
+                out.push(Significant {
+                    token: token.clone(),
+                    span: tok.span.clone(),
+                    trailing: false,
+                });
+            },
+        }
+    }
+    out
+}
+
+/// checks that formatting only changed whitespace, reporting diagnostics against the original source
+pub fn verify(
+    path: &Path,
+    original: &str,
+    formatted: &str,
+) -> Safety {
+    let mut safety = Safety::default();
+    let (Ok(before), Ok(after)) = (TokenStream::lex(original), TokenStream::lex(formatted)) else {
+        safety.unsafe_edit = Some(unsafe_edit(path, original, &Span::new(0, 0)));
+        return safety;
+    };
+    let (before, after) = (significant(&before), significant(&after));
+
+    for (idx, tok) in before.iter().enumerate() {
+        let Some(other) = after
+            .get(idx)
+            .filter(|other| other.token == tok.token)
+        else {
+            safety.unsafe_edit = Some(unsafe_edit(path, original, &tok.span));
+            return safety;
+        };
+        if tok.trailing && !other.trailing {
+            let mut diag = SpanDiagnostic::new(
+                &Spanned::new(tok.span.start, tok.span.end, ()),
+                path,
+                original,
+                "comment was moved onto its own line",
+                "trailing comment",
+                Some("comments are attached to the item which follows them".into()),
+            );
+            diag.with_level(miette::Severity::Warning);
+            safety.moved.push(diag);
+        }
+    }
+
+    if after.len() > before.len() {
+        let end = original.len();
+        safety.unsafe_edit = Some(unsafe_edit(path, original, &Span::new(end, end)));
+    }
+    safety
+}
+
+fn unsafe_edit(
+    path: &Path,
+    original: &str,
+    span: &Span,
+) -> SpanDiagnostic {
+    let mut diag = SpanDiagnostic::new(
+        &Spanned::new(span.start, span.end, ()),
+        path,
+        original,
+        "formatting would change the tokens of this file",
+        "changed here",
+        Some("pass --safe=false to apply the edit anyway".into()),
+    );
+    diag.with_level(miette::Severity::Warning);
+    diag
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::verify;
+
+    #[test_case::test_case(
+        "namespace a;\nstruct A { a: i32 };", "namespace a;\n\nstruct A {\n\ta: i32\n};\n",
+        false, 0; "whitespace only"
+    )]
+    #[test_case::test_case(
+        "namespace a; // the namespace\n", "// the namespace\nnamespace a;\n",
+        true, 0; "reordered comment"
+    )]
+    #[test_case::test_case(
+        "struct A {\n\ta: i32, // first\n\tb: i32\n};", "struct A {\n\ta: i32,\n\t// first\n\tb: i32\n};",
+        false, 1; "moved trailing comment"
+    )]
+    #[test_case::test_case(
+        "struct A { a: i32 };", "struct A { a: i64 };",
+        true, 0; "changed token"
+    )]
+    #[test_case::test_case(
+        "struct A { a: i32 };", "struct A { a: i32 };;",
+        true, 0; "added token"
+    )]
+    fn test_verify(
+        original: &str,
+        formatted: &str,
+        is_unsafe: bool,
+        moved: usize,
+    ) {
+        let safety = verify(Path::new("test.pld"), original, formatted);
+        assert_eq!(safety.unsafe_edit.is_some(), is_unsafe);
+        assert_eq!(safety.moved.len(), moved);
+    }
+}
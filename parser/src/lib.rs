@@ -47,6 +47,9 @@ pub enum Error {
     #[error("resolution error. could not resolve '{}'", ident.borrow_path_inner())]
     ResolutionError { ident: SpannedToken![path] },
 
+    #[error("import cycle detected: {}", chain.iter().map(|it| it.display().to_string()).collect::<Vec<_>>().join(" -> "))]
+    ImportCycle { chain: Vec<std::path::PathBuf> },
+
     #[error("{inner}")]
     WithSpan {
         #[source]
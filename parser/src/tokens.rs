@@ -39,7 +39,7 @@ mod tests {
     fn samples_lex_without_error_tokens() {
         for src in SAMPLE_FILES.iter() {
             let ts = tokenize(src).unwrap_or_else(|e| panic!("lex errors in sample {src}: {e:#?}"));
-            assert!(ts.all().len() > 0);
+            assert!(!ts.all().is_empty());
         }
     }
 
@@ -76,6 +76,49 @@ impl TokenStream {
             is_fork: false,
         })
     }
+    /// lexes the whole source, skipping invalid characters instead of stopping at the first one.
+    pub fn lex_recovering(source: &str) -> (Self, Vec<LexingError>) {
+        let source: Arc<str> = Arc::from(source);
+        let mut lex = Token::lexer(&source);
+
+        let approx = (source.len() / 8).max(256);
+        let mut toks = Vec::with_capacity(approx);
+        let mut errors = vec![];
+        while let Some(token) = lex.next() {
+            let span = lex.span();
+            match token {
+                Ok(token) => toks.push(Spanned::new(span.start, span.end, token)),
+                Err(err) => {
+                    let err = match err {
+                        LexingError::Unknown => {
+                            LexingError::InvalidToken {
+                                found: lex.slice().to_string(),
+                            }
+                        },
+                        err => err,
+                    };
+                    errors.push(err.with_span(Span {
+                        start: span.start,
+                        end: span.end,
+                    }))
+                },
+            }
+        }
+
+        let range_end = toks.len();
+        (
+            Self {
+                source,
+                tokens: Arc::new(toks),
+                cursor: 0,
+                range_start: 0,
+                range_end,
+                is_fork: false,
+            },
+            errors,
+        )
+    }
+
     pub fn fork(&self) -> Self {
         Self {
             source: self.source.clone(),
@@ -294,7 +337,7 @@ fn parse_vs_token_contract() {
     crate::tst::logging();
 
     let src = "namespace test;\n\n// comment\n";
-    let mut tt = tokenize(src).expect("tokenize");
+    let tt = tokenize(src).expect("tokenize");
 
     let fork = tt.fork();
     let full = fork.all();
@@ -307,7 +350,7 @@ fn parse_vs_token_contract() {
     );
 
     let mut iter_count = 0usize;
-    while let Some(_) = tt.next() {
+    for _ in tt {
         iter_count += 1;
     }
 
@@ -536,7 +579,7 @@ mod test {
 
     use crate::{
         defs::Spanned,
-        tokens::{self, AstResult, TokenStream, brace, bracket, paren, tokenize},
+        tokens::{self, AstResult, TokenStream, tokenize},
     };
 
     #[test_case::test_case(
@@ -627,7 +670,7 @@ mod test {
         .unwrap_err();
         let as_crate = crate::Error::from(inner);
         let p = Path::new("test.pld");
-        let diag = format!("{:?}", as_crate.to_report_with(&p, src, None));
+        let diag = format!("{:?}", as_crate.to_report_with(p, src, None));
         eprintln!("{diag}");
         for e in expect {
             assert!(diag.contains(e), "'{}' is in outputted diagnostics", e)
@@ -169,6 +169,8 @@ declare_tokens! {
     Hash,
     #[token("!")]
     Bang,
+    #[token("@")]
+    At,
 
     #[token("namespace")]
     KwNamespace,
@@ -238,7 +240,7 @@ declare_tokens! {
     #[derive(PartialOrd, Ord, Hash, Eq)]
     Ident(String),
 
-    #[regex(r"(schema|[A-Za-z_][A-Za-z0-9_]*)::[A-Za-z_][A-Za-z0-9_]*(?::[A-Za-z_][A-Za-z0-9_]*)*", parse_path)]
+    #[regex(r"(schema|[A-Za-z_][A-Za-z0-9_]*)::[A-Za-z_][A-Za-z0-9_]*(?:::[A-Za-z_][A-Za-z0-9_]*)*", parse_path)]
     #[regfmt("path")]
     Path(PathInner),
 
@@ -297,6 +299,7 @@ impl fmt::Display for Token {
             Pipe => write!(f, "|"),
             Hash => write!(f, "#"),
             Bang => write!(f, "!"),
+            At => write!(f, "@"),
             KwNamespace => write!(f, "namespace"),
             KwUse => write!(f, "use"),
             KwStruct => write!(f, "struct"),
@@ -411,6 +414,7 @@ macro_rules! Token {
     [|] => { $crate::tokens::toks::PipeToken };
     [#] => { $crate::tokens::toks::HashToken };
     [!] => { $crate::tokens::toks::BangToken };
+    [@] => { $crate::tokens::toks::AtToken };
     [namespace] => { $crate::tokens::toks::KwNamespaceToken };
     [use] => { $crate::tokens::toks::KwUseToken };
     [struct] => { $crate::tokens::toks::KwStructToken };
@@ -552,7 +556,8 @@ syntax_desc! {{
         ? = "used to indicate an optional type.",
         = = "equals is used to declare a named type, or provide a static value to an enum member.",
         # = "pound tokens are used in meta. e.g. `#[...]`",
-        ! = "bang tokens are used to set meta as inner meta, or declare a return type may raise an error. e.g. `-> i32!`."
+        ! = "bang tokens are used to set meta as inner meta, or declare a return type may raise an error. e.g. `-> i32!`.",
+        @ = "at tokens pin a reference to one version of a declaration, with no whitespace. e.g. `KnownError@1`."
     ]
 }}
 
@@ -1,6 +1,6 @@
 use crate::{
     fmt::{FormatConfig, Printer},
-    tokens::{self, AstResult, SpannedToken, ToTokens, tokenize},
+    tokens::{self, AstResult, ToTokens, tokenize},
 };
 
 pub fn logging() {
@@ -1,7 +1,9 @@
 pub use operation_api_core::{
     CompoundType,
+    ConversionError,
     Defined,
     Definitions,
+    DispatchError,
     Enum,
     ErrorTy as Error,
     Field,
@@ -11,6 +13,7 @@ pub use operation_api_core::{
     OneOf,
     OneOfVariant,
     Operation,
+    OperationKind,
     StrOrInt,
     Struct,
     Type,
@@ -22,4 +25,5 @@ pub use operation_api_core::{
     namespace::OfNamespace, //ty::Result,
 };
 pub use operation_api_derives::{Enum, Error, OneOf, Struct, module, operation};
+pub use serde_json;
 pub use serde_repr::{Deserialize_repr as IntDeserialize, Serialize_repr as IntSerialize};
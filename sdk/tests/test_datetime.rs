@@ -11,10 +11,10 @@ macro_rules! test_time_lib {
                 #[derive(operation_api_sdk::Struct)]
                 #[fields(version = 1)]
                 pub struct [<CommentWith $name:camel>] {
-                    pub id: i64,
                     pub comment: String,
                     pub created_at: $t,
                     pub deleted_at: Option<$t>,
+                    pub id: i64,
                 }
 
                 namespace! {
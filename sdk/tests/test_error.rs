@@ -5,8 +5,8 @@ include!("./shared.rs");
 #[derive(Enum, serde::Serialize, serde::Deserialize)]
 #[fields(version = 1)]
 pub enum ErrorCode {
-    Foo = 1,
     Baz = 2,
+    Foo = 1,
 }
 
 #[derive(Struct, serde::Serialize, serde::Deserialize)]
@@ -19,11 +19,11 @@ pub struct KnownError {
 #[derive(Error, serde::Serialize, serde::Deserialize)]
 #[fields(version = 1)]
 pub enum OperationError {
+    Known(KnownError),
     Unknown {
         /// some nested doc
         desc: String,
     },
-    Known(KnownError),
 }
 
 namespace! {
@@ -8,10 +8,10 @@ include!("shared.rs");
 #[derive(OneOf)]
 #[fields(version = 1)]
 pub enum MaybeFlagType {
+    BoolFlag,
     /// some doc
     Int(i32),
     Str(String),
-    BoolFlag,
 }
 
 #[derive(Struct)]
@@ -62,6 +62,7 @@ fn sum(values: Vec<i32>) -> i32 {
 
 #[operation(version = 1)]
 /// this is a test description
+#[allow(clippy::needless_lifetimes)]
 fn with_lt<'a>(value: &'a str) -> &'a str {
     value
 }
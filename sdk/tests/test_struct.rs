@@ -25,9 +25,17 @@ pub struct BasicStructWithReadme {
     a: i32,
 }
 
+#[derive(Struct)]
+#[fields(version = 1)]
+pub struct Tree {
+    value: i32,
+    left: Option<Box<Tree>>,
+    right: Option<Box<Tree>>,
+}
+
 namespace! {
     "abc.corp.test" {
-        BasicStruct, BasicStructWithReadme
+        BasicStruct, BasicStructWithReadme, Tree
     }
 }
 
@@ -44,3 +52,40 @@ fn smoke_basic_with_text() {
         operation_api_testing::assert_yaml_snapshot!(ser)
     })
 }
+
+#[test]
+fn boxed_is_transparent() {
+    let operation_api_sdk::Definitions::StructV1(def) = Tree::definition() else {
+        panic!("expected struct")
+    };
+    assert_eq!(
+        def.fields[&operation_api_core::Ident::new("left")]
+            .unwrap_value()
+            .ty,
+        operation_api_sdk::Type::CompoundType(operation_api_sdk::CompoundType::Option {
+            ty: Box::new(operation_api_sdk::Type::CompoundType(
+                operation_api_sdk::CompoundType::Struct { to: "Tree".into() }
+            ))
+        })
+    );
+}
+
+#[test]
+fn fields_keep_declaration_order() {
+    let operation_api_sdk::Definitions::StructV1(def) = Tree::definition() else {
+        panic!("expected struct")
+    };
+    assert_eq!(
+        def.fields
+            .keys()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["value", "left", "right"]
+    );
+    let ser = toml::to_string(def).unwrap();
+    let at = |field: &str| {
+        ser.find(&format!("[fields.{field}]"))
+            .unwrap()
+    };
+    assert!(at("value") < at("left") && at("left") < at("right"));
+}
@@ -1,4 +1,4 @@
-pub use insta::{assert_yaml_snapshot, with_settings};
+pub use insta::{assert_snapshot, assert_yaml_snapshot, with_settings};
 
 #[macro_export]
 macro_rules! insta_test {